@@ -0,0 +1,84 @@
+use globe_vis::{colors::CategoryColors, geo::lat_lon_to_xyz, Point, VisBuilder, VisState};
+
+// slightly above globe surface so points aren't clipped by globe depth
+const POINT_RADIUS: f32 = 1.01;
+
+// point size in logical pixels for a magnitude 6 quake, grows by half for each magnitude
+const BASE_SIZE: f32 = 8.0;
+const SIZE_GROWTH: f32 = 1.5;
+
+// linear rgba from yellow to dark red, one color per whole magnitude from 6 up
+const MAGNITUDE_PALETTE: [[f32; 4]; 4] = [
+    [1.0, 0.8, 0.1, 1.0],
+    [1.0, 0.45, 0.05, 1.0],
+    [0.85, 0.1, 0.02, 1.0],
+    [0.4, 0.0, 0.02, 1.0],
+];
+
+// dark blue so yellow through red points stand out
+const BACKGROUND: [f32; 3] = [0.005, 0.01, 0.03];
+
+// start over the pacific ring of fire, where most large quakes are
+const INITIAL_VIEW: (f32, f32, f32) = (10.0, -170.0, 3.0);
+
+struct Earthquakes {
+    points: Vec<Point>,
+}
+
+// magnitude is logarithmic, so grow size exponentially to keep large quakes standing out
+fn size_for_magnitude(magnitude: f32) -> f32 {
+    BASE_SIZE * SIZE_GROWTH.powf(magnitude - 6.0)
+}
+
+// palette index for magnitude, clamped so colors don't wrap around palette
+fn magnitude_bucket(magnitude: f32) -> usize {
+    (magnitude.floor() - 6.0).clamp(0.0, (MAGNITUDE_PALETTE.len() - 1) as f32) as usize
+}
+
+impl Earthquakes {
+    // parse lat / lon / magnitude columns from csv with header row
+    fn from_csv(csv: &str) -> Self {
+        let colors = CategoryColors::from_palette(MAGNITUDE_PALETTE.to_vec());
+        let points = csv
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let cols: Vec<&str> = line.split(',').collect();
+                let lat = cols.get(1)?.parse::<f32>().ok()?;
+                let lon = cols.get(2)?.parse::<f32>().ok()?;
+                let magnitude = cols.get(3)?.parse::<f32>().ok()?;
+                Some(Point {
+                    pos: lat_lon_to_xyz(lat, lon, POINT_RADIUS),
+                    color: colors.color(magnitude_bucket(magnitude)),
+                    size: size_for_magnitude(magnitude),
+                })
+            })
+            .collect();
+        Self { points }
+    }
+}
+
+impl VisState for Earthquakes {
+    // data never changes, points are read once
+    fn points_revision(&self) -> Option<u64> {
+        Some(0)
+    }
+
+    fn update_points_typed(&mut self, _ms: f32) -> Vec<Point> {
+        self.points.clone()
+    }
+}
+
+fn main() {
+    let state = Earthquakes::from_csv(include_str!("data/earthquakes.csv"));
+    let (lat, lon, distance) = INITIAL_VIEW;
+
+    VisBuilder::new()
+        .with_title("Largest earthquakes")
+        .with_dimensions(1000.0, 700.0)
+        .with_background(BACKGROUND)
+        .with_initial_view(lat, lon, distance)
+        .with_state(state)
+        .start()
+        .unwrap();
+}
@@ -0,0 +1,8 @@
+layout(location = 0) in vec2 position;
+// 0 to 1 across viewport, for sampling screen sized textures
+out vec2 screenUv;
+
+void main() {
+    screenUv = 0.5 * position + 0.5;
+    gl_Position = vec4(position, 0.0, 1.0);
+}
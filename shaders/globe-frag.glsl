@@ -1,8 +1,78 @@
 precision highp float;
 
-in vec4 testColor;
+in vec4 vertexColor;
+in vec3 vertexNormal;
+in vec3 vertexPosition;
+in vec3 vertexView;
+uniform float gamma;
+uniform vec3 globeColor;
+uniform float doubleSided;
+// 1 for opaque globe, lower values blend over geometry behind it
+uniform float opacity;
+uniform vec3 ambient;
+uniform vec3 lightDir;
+uniform vec3 lightColor;
+uniform vec3 fillDir;
+uniform vec3 fillColor;
+// additional directional lights, unused slots are black
+const int MAX_LIGHTS = 4;
+uniform vec3 lightDirs[MAX_LIGHTS];
+uniform vec3 lightColors[MAX_LIGHTS];
+// ocean mask in red channel, scales blinn phong highlight from main light
+uniform sampler2D specularMask;
+uniform float specularIntensity;
+uniform float shininess;
+// 1 fades alpha over the pixel nearest the silhouette, smoothing the rim without msaa
+uniform float edgeSmoothing;
+// second base color, 0 for none, 1 poleward of toneLat (sine of lat), 2 where
+// specular mask is set
+uniform float toneMode;
+uniform float toneLat;
+uniform vec3 toneColor;
 out vec4 fragColor;
 
+const float PI = 3.14159265;
+
+// equirectangular uv matching geo lat / lon, lon 0 at +z and north at top row
+vec2 equirectUv(vec3 p) {
+    vec3 n = normalize(p);
+    float lon = atan(n.x, n.z);
+    float lat = asin(clamp(n.y, -1.0, 1.0));
+    return vec2(lon / (2.0 * PI) + 0.5, 0.5 - lat / PI);
+}
+
 void main() {
-    fragColor = testColor;
+    vec3 normal = normalize(vertexNormal);
+    // normal turns perpendicular to view at silhouette, dividing by its screen space
+    // derivative gives distance to silhouette in pixels. abs covers back faces at rim
+    float facing = abs(dot(normal, normalize(vertexView)));
+    float coverage = clamp(facing / max(fwidth(facing), 1e-4), 0.0, 1.0);
+    coverage = mix(1.0, coverage, edgeSmoothing);
+    // inside of globe faces toward center
+    if (doubleSided > 0.5 && !gl_FrontFacing) {
+        normal = -normal;
+    }
+    vec3 light = ambient;
+    light += lightColor * max(dot(normal, lightDir), 0.0);
+    light += fillColor * max(dot(normal, fillDir), 0.0);
+    for (int i = 0; i < MAX_LIGHTS; i++) {
+        light += lightColors[i] * max(dot(normal, lightDirs[i]), 0.0);
+    }
+    // intensity is 0 without a mask, so unbound sampler has no effect
+    float mask = texture(specularMask, equirectUv(vertexPosition)).r;
+    vec3 base = globeColor;
+    if (toneMode > 1.5) {
+        base = mix(globeColor, toneColor, mask);
+    } else if (toneMode > 0.5) {
+        float polar = step(toneLat, abs(normalize(vertexPosition).y));
+        base = mix(globeColor, toneColor, polar);
+    }
+    // clamp combined light to avoid over saturation
+    vec3 color = base * vertexColor.rgb * min(light, vec3(1.0));
+    vec3 halfDir = normalize(lightDir + normalize(vertexView));
+    float lit = step(0.0, dot(normal, lightDir));
+    float specular = lit * pow(max(dot(normal, halfDir), 0.0), shininess);
+    color += lightColor * specular * specularIntensity * mask;
+    // gamma of 1.0 passes color through unchanged
+    fragColor = vec4(pow(color, vec3(1.0 / gamma)), vertexColor.a * opacity * coverage);
 }
@@ -1,10 +1,33 @@
 in vec4 position;
+in vec4 color;
 uniform mat4 projMatrix;
 uniform mat4 viewMatrix;
 uniform mat4 modelMatrix;
-out vec4 testColor;
+uniform float pointSize;
+// 0 for globe, 1 for flat equirectangular map, between while morphing
+uniform float flatMap;
+out vec4 vertexColor;
+out vec3 vertexNormal;
+out vec3 vertexPosition;
+out vec3 vertexView;
+
+// flat map position, lon along x and lat along y in radians
+vec3 flatPosition(vec3 p) {
+    float len = max(length(p), 1e-6);
+    return vec3(atan(p.x, p.z), asin(clamp(p.y / len, -1.0, 1.0)), 0.0);
+}
 
 void main() {
-    gl_Position = projMatrix * viewMatrix * modelMatrix * position;
-    testColor = (position + 1.0) * 0.5;
+    vec4 worldPosition = vec4(mix(position.xyz, flatPosition(position.xyz), flatMap), 1.0);
+    gl_Position = projMatrix * viewMatrix * modelMatrix * worldPosition;
+    // only used in points render mode
+    gl_PointSize = pointSize / gl_Position.w;
+    vertexColor = color;
+    // unit sphere normal is position, rotated into world space, flat map faces camera
+    vertexNormal = mat3(modelMatrix) * mix(position.xyz, vec3(0.0, 0.0, 1.0), flatMap);
+    // model space position for texture lookup
+    vertexPosition = position.xyz;
+    // world space direction toward camera, view matrix is rigid
+    vec3 eye = -transpose(mat3(viewMatrix)) * viewMatrix[3].xyz;
+    vertexView = eye - (modelMatrix * worldPosition).xyz;
 }
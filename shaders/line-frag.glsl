@@ -0,0 +1,16 @@
+precision highp float;
+
+in vec4 lineColor;
+in float lineDistance;
+uniform float gamma;
+uniform float lineWidth;
+out vec4 fragColor;
+
+void main() {
+    // coverage falls off over a pixel past line edge
+    float coverage = clamp(0.5 * lineWidth + 0.5 - abs(lineDistance), 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    fragColor = vec4(pow(lineColor.rgb, vec3(1.0 / gamma)), lineColor.a * coverage);
+}
@@ -0,0 +1,51 @@
+in vec2 corner;
+in vec3 start;
+in vec3 end;
+in vec4 color;
+uniform mat4 projMatrix;
+uniform mat4 viewMatrix;
+uniform mat4 modelMatrix;
+// radial push away from globe surface
+uniform float surfaceOffset;
+// full line width in physical pixels
+uniform float lineWidth;
+// viewport size in physical pixels, for converting pixel offsets to clip space
+uniform vec2 viewportSize;
+// 0 for globe, 1 for flat equirectangular map, between while morphing
+uniform float flatMap;
+out vec4 lineColor;
+// signed distance from line center in pixels
+out float lineDistance;
+
+// pixels either side of line faded for anti aliasing
+const float FEATHER = 1.0;
+
+// flat map position, lon along x and lat along y in radians
+vec3 flatPosition(vec3 p) {
+    float len = max(length(p), 1e-6);
+    return vec3(atan(p.x, p.z), asin(clamp(p.y / len, -1.0, 1.0)), 0.0);
+}
+
+vec4 clipPosition(vec3 p) {
+    vec3 globe = p * (1.0 + surfaceOffset / max(length(p), 1e-6));
+    vec3 flatPos = flatPosition(p) + vec3(0.0, 0.0, surfaceOffset);
+    return projMatrix * viewMatrix * modelMatrix * vec4(mix(globe, flatPos, flatMap), 1.0);
+}
+
+void main() {
+    // segment expanded to quad in screen space, corner x picks end, y picks side
+    vec4 a = clipPosition(start);
+    vec4 b = clipPosition(end);
+    vec2 screenA = a.xy / a.w * viewportSize;
+    vec2 screenB = b.xy / b.w * viewportSize;
+    vec2 dir = screenB - screenA;
+    dir = length(dir) > 1e-6 ? normalize(dir) : vec2(1.0, 0.0);
+    vec2 normal = vec2(-dir.y, dir.x);
+    float halfWidth = 0.5 * lineWidth + FEATHER;
+    vec4 clip = mix(a, b, corner.x);
+    // ndc spans 2 units across viewport, so a pixel is 2 / size in ndc
+    clip.xy += normal * corner.y * halfWidth * 2.0 / viewportSize * clip.w;
+    gl_Position = clip;
+    lineColor = color;
+    lineDistance = corner.y * halfWidth;
+}
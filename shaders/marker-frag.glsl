@@ -0,0 +1,13 @@
+precision highp float;
+
+in vec4 markerColor;
+in vec2 markerCoord;
+uniform float gamma;
+out vec4 fragColor;
+
+void main() {
+    if (dot(markerCoord, markerCoord) > 1.0) {
+        discard;
+    }
+    fragColor = vec4(pow(markerColor.rgb, vec3(1.0 / gamma)), markerColor.a);
+}
@@ -0,0 +1,49 @@
+in vec2 corner;
+in vec3 position;
+in vec4 color;
+in float size;
+uniform mat4 projMatrix;
+uniform mat4 viewMatrix;
+uniform mat4 modelMatrix;
+// radial push away from globe surface
+uniform float surfaceOffset;
+// 1 faces camera, 0 lies flat on globe tangent plane
+uniform float billboard;
+// 0 for globe, 1 for flat equirectangular map, between while morphing
+uniform float flatMap;
+out vec4 markerColor;
+out vec2 markerCoord;
+
+// flat map position, lon along x and lat along y in radians
+vec3 flatPosition(vec3 p) {
+    float len = max(length(p), 1e-6);
+    return vec3(atan(p.x, p.z), asin(clamp(p.y / len, -1.0, 1.0)), 0.0);
+}
+
+void main() {
+    vec3 globeCenter = position * (1.0 + surfaceOffset / max(length(position), 1e-6));
+    vec3 flatCenter = flatPosition(position) + vec3(0.0, 0.0, surfaceOffset);
+    vec3 center = mix(globeCenter, flatCenter, flatMap);
+    vec3 right;
+    vec3 up;
+    if (flatMap > 0.5) {
+        // map plane faces camera, so flat and billboard markers are the same
+        right = vec3(1.0, 0.0, 0.0);
+        up = vec3(0.0, 1.0, 0.0);
+    } else if (billboard > 0.5) {
+        // camera axes in model space, rows of view model rotation
+        mat3 viewModel = mat3(viewMatrix * modelMatrix);
+        right = vec3(viewModel[0][0], viewModel[1][0], viewModel[2][0]);
+        up = vec3(viewModel[0][1], viewModel[1][1], viewModel[2][1]);
+    } else {
+        // east / north at marker, with fixed axis at poles where east is undefined
+        vec3 normal = normalize(globeCenter);
+        vec3 east = cross(vec3(0.0, 1.0, 0.0), normal);
+        right = length(east) > 1e-6 ? normalize(east) : vec3(1.0, 0.0, 0.0);
+        up = cross(normal, right);
+    }
+    vec3 vertex = center + (corner.x * right + corner.y * up) * size;
+    gl_Position = projMatrix * viewMatrix * modelMatrix * vec4(vertex, 1.0);
+    markerColor = color;
+    markerCoord = corner;
+}
@@ -0,0 +1,9 @@
+precision highp float;
+
+in vec4 pathColor;
+uniform float gamma;
+out vec4 fragColor;
+
+void main() {
+    fragColor = vec4(pow(pathColor.rgb, vec3(1.0 / gamma)), pathColor.a);
+}
@@ -0,0 +1,23 @@
+in vec3 position;
+in vec4 color;
+uniform mat4 projMatrix;
+uniform mat4 viewMatrix;
+uniform mat4 modelMatrix;
+// radial push away from globe surface
+uniform float surfaceOffset;
+// 0 for globe, 1 for flat equirectangular map, between while morphing
+uniform float flatMap;
+out vec4 pathColor;
+
+// flat map position, lon along x and lat along y in radians
+vec3 flatPosition(vec3 p) {
+    float len = max(length(p), 1e-6);
+    return vec3(atan(p.x, p.z), asin(clamp(p.y / len, -1.0, 1.0)), 0.0);
+}
+
+void main() {
+    vec3 globe = position * (1.0 + surfaceOffset / max(length(position), 1e-6));
+    vec3 flatPos = flatPosition(position) + vec3(0.0, 0.0, surfaceOffset);
+    gl_Position = projMatrix * viewMatrix * modelMatrix * vec4(mix(globe, flatPos, flatMap), 1.0);
+    pathColor = color;
+}
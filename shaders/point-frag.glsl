@@ -1,6 +1,17 @@
 precision highp float;
 
-in vec4 testColor;
+in vec4 vertexColor;
+in float vertexPointSize;
+in float vertexSelected;
+uniform float gamma;
+uniform vec4 outlineColor;
+// outline width in physical pixels, 0 disables outline
+uniform float outlineWidth;
+// outline drawn around selected points instead of regular outline
+uniform vec4 selectionColor;
+uniform float selectionWidth;
+// physical pixels alpha fades over at point edge, 0 for hard edge
+uniform float pointSoftness;
 out vec4 fragColor;
 
 void main() {
@@ -9,5 +20,14 @@ void main() {
     if (radius > 1.0) {
         discard;
     }
-    fragColor = vec4(1.0, 1.0, 1.0, 1.0);
+    vec4 color = vertexColor;
+    // outer band of point within outline width of edge
+    float edge = 0.5 * vertexPointSize * (1.0 - sqrt(radius));
+    bool isSelected = vertexSelected > 0.5;
+    if (edge < (isSelected ? selectionWidth : outlineWidth)) {
+        color = isSelected ? selectionColor : outlineColor;
+    }
+    // feather edge by distance to it, antialiasing circle without msaa
+    float coverage = pointSoftness > 0.0 ? smoothstep(0.0, pointSoftness, edge) : 1.0;
+    fragColor = vec4(pow(color.rgb, vec3(1.0 / gamma)), color.a * coverage);
 }
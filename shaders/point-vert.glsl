@@ -1,9 +1,47 @@
 in vec4 position;
+in float time;
+in vec4 color;
+// 1 for selected points, 0 otherwise
+in float selected;
+// per point size in physical pixels, 0 uses pointSize
+in float size;
 uniform mat4 projMatrix;
 uniform mat4 viewMatrix;
 uniform mat4 modelMatrix;
+uniform float pointSize;
+// radial push away from globe surface
+uniform float surfaceOffset;
+// 0 for globe, 1 for flat equirectangular map, between while morphing
+uniform float flatMap;
+uniform float timeStart;
+uniform float timeEnd;
+// size multiplier for selected points
+uniform float selectionScale;
+out vec4 vertexColor;
+out float vertexPointSize;
+out float vertexSelected;
+
+// flat map position, lon along x and lat along y in radians
+vec3 flatPosition(vec3 p) {
+    float len = max(length(p), 1e-6);
+    return vec3(atan(p.x, p.z), asin(clamp(p.y / len, -1.0, 1.0)), 0.0);
+}
 
 void main() {
-    gl_Position = projMatrix * viewMatrix * modelMatrix * position;
-    gl_PointSize = 20.0 / gl_Position.w;
+    vertexColor = color;
+    vertexSelected = selected;
+    // move points outside time window off screen, window is inclusive
+    if (time < timeStart || time > timeEnd) {
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        gl_PointSize = 0.0;
+        vertexPointSize = 0.0;
+        return;
+    }
+    vec3 globeOffset = position.xyz * (1.0 + surfaceOffset / max(length(position.xyz), 1e-6));
+    vec3 flatOffset = flatPosition(position.xyz) + vec3(0.0, 0.0, surfaceOffset);
+    vec3 offset = mix(globeOffset, flatOffset, flatMap);
+    gl_Position = projMatrix * viewMatrix * modelMatrix * vec4(offset, position.w);
+    float baseSize = size > 0.0 ? size : pointSize;
+    gl_PointSize = baseSize * mix(1.0, selectionScale, selected) / gl_Position.w;
+    vertexPointSize = gl_PointSize;
 }
@@ -0,0 +1,71 @@
+use crate::geo::xyz_to_lat_lon;
+use std::collections::BTreeMap;
+
+// equal area lat / lon grid, rows are bands of equal sin(lat) so every cell covers the
+// same surface area, resolution rows by 2 * resolution columns
+
+// grid cell containing lat / lon in degrees, row major from south pole and lon -180
+pub fn cell_for_lat_lon(lat: f32, lon: f32, resolution: usize) -> usize {
+    let rows = resolution.max(1);
+    let cols = 2 * rows;
+    let v = (lat.to_radians().sin() + 1.0) * 0.5;
+    let u = (lon + 180.0).rem_euclid(360.0) / 360.0;
+    let row = ((v * rows as f32) as usize).min(rows - 1);
+    let col = ((u * cols as f32) as usize).min(cols - 1);
+    row * cols + col
+}
+
+// lat / lon in degrees at center of grid cell
+pub fn cell_center(cell: usize, resolution: usize) -> (f32, f32) {
+    let rows = resolution.max(1);
+    let cols = 2 * rows;
+    let (row, col) = (cell / cols, cell % cols);
+    let v = (row as f32 + 0.5) / rows as f32;
+    let u = (col as f32 + 0.5) / cols as f32;
+    let lat = (2.0 * v - 1.0).asin().to_degrees();
+    let lon = u * 360.0 - 180.0;
+    (lat, lon)
+}
+
+// count interleaved xyz points in each grid cell, cells without points are omitted
+// sorted by cell id, use with cell_for_lat_lon and Globe::colors_from_lat_lon for a choropleth
+pub fn bin_points(points: &[f32], resolution: usize) -> Vec<(usize, usize)> {
+    let mut counts = BTreeMap::new();
+    for p in points.chunks_exact(3) {
+        let (lat, lon) = xyz_to_lat_lon([p[0], p[1], p[2]]);
+        *counts
+            .entry(cell_for_lat_lon(lat, lon, resolution))
+            .or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::lat_lon_to_xyz;
+
+    #[test]
+    fn test_bin_points() {
+        // 2 rows split at equator, 4 columns of 90 degrees starting at lon -180
+        let points: Vec<f32> = [(45.0, 10.0), (30.0, 80.0), (-45.0, -170.0), (10.0, 100.0)]
+            .iter()
+            .flat_map(|&(lat, lon)| lat_lon_to_xyz(lat, lon, 1.5))
+            .collect();
+        let bins = bin_points(&points, 2);
+        assert_eq!(bins, vec![(0, 1), (6, 2), (7, 1)]);
+        assert_eq!(bins.iter().map(|b| b.1).sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn test_cell_center_round_trip() {
+        let resolution = 8;
+        for cell in 0..2 * resolution * resolution {
+            let (lat, lon) = cell_center(cell, resolution);
+            assert_eq!(cell_for_lat_lon(lat, lon, resolution), cell);
+        }
+        // poles and antimeridian stay in range
+        assert_eq!(cell_for_lat_lon(90.0, 180.0, 4), 4 * 8 - 8);
+        assert_eq!(cell_for_lat_lon(-90.0, -180.0, 4), 0);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::geo::xyz_to_lat_lon;
+use crate::mouse::{MAX_DISTANCE, MIN_DISTANCE};
+use glam::{Quat, Vec3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// exact camera for saving and restoring views, orientation is globe rotation as xyzw
+// quaternion excluding axial tilt
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CameraState {
+    pub orientation: [f32; 4],
+    pub distance: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub orthographic: bool,
+}
+
+// globe orientation that brings lat / lon to face the camera with north up
+pub fn orientation_for_center(lat: f32, lon: f32) -> Quat {
+    Quat::from_rotation_x(lat.to_radians()) * Quat::from_rotation_y(-lon.to_radians())
+}
+
+// fixed rotation tilting globe pole toward +x, applied under user orientation
+pub fn axial_tilt(degrees: f32) -> Quat {
+    Quat::from_rotation_z(-degrees.to_radians())
+}
+
+// fraction of view half angle fitted points may span, leaves a border around data
+const FIT_MARGIN: f32 = 0.8;
+
+// centroid lat / lon and max angle in radians from centroid to any of interleaved xyz points
+// None if empty or points are spread evenly enough around globe to have no clear center
+pub fn points_extent(points: &[f32]) -> Option<(f32, f32, f32)> {
+    let dirs: Vec<Vec3> = points
+        .chunks_exact(3)
+        .map(|p| Vec3::new(p[0], p[1], p[2]).normalize_or_zero())
+        .collect();
+    let mean = dirs.iter().sum::<Vec3>() / dirs.len().max(1) as f32;
+    if mean.length() < 1e-3 {
+        return None;
+    }
+    let center = mean.normalize();
+    let extent = dirs
+        .iter()
+        .map(|d| d.dot(center).clamp(-1.0, 1.0).acos())
+        .fold(0.0, f32::max);
+    let (lat, lon) = xyz_to_lat_lon(center.to_array());
+    Some((lat, lon, extent))
+}
+
+// camera distance that keeps points within extent of center inside view half angle,
+// never further than max_distance so spread data keeps the default global view
+pub fn fit_distance(extent: f32, half_angle: f32, max_distance: f32) -> f32 {
+    // distance peaks where extent edge is tangent to view, larger extents are past horizon
+    let half_angle = half_angle * FIT_MARGIN;
+    let extent = extent.min(std::f32::consts::FRAC_PI_2 - half_angle);
+    let (sin, cos) = extent.sin_cos();
+    let distance = cos + sin / half_angle.tan();
+    distance.clamp(MIN_DISTANCE, max_distance.max(MIN_DISTANCE))
+}
+
+// animates globe orientation and camera distance over a fixed duration
+// advanced by frame delta so progress doesn't depend on refresh rate or event frequency
+pub struct CameraAnimation {
+    pub from_orientation: Quat,
+    pub to_orientation: Quat,
+    pub from_distance: f32,
+    pub to_distance: f32,
+    pub duration_ms: f32,
+    pub elapsed_ms: f32,
+}
+
+impl CameraAnimation {
+    pub fn new(from: (Quat, f32), to: (Quat, f32), duration_ms: f32) -> Self {
+        Self {
+            from_orientation: from.0,
+            to_orientation: to.0,
+            from_distance: from.1,
+            to_distance: to.1.clamp(MIN_DISTANCE, MAX_DISTANCE),
+            duration_ms: duration_ms.max(0.0),
+            elapsed_ms: 0.0,
+        }
+    }
+
+    // orientation and distance at time since start, eased in and out
+    // returns true once animation has reached its end
+    pub fn sample(&self, elapsed_ms: f32) -> (Quat, f32, bool) {
+        let (eased, done) = eased_progress(elapsed_ms, self.duration_ms);
+        let orientation = self.from_orientation.slerp(self.to_orientation, eased);
+        let distance = self.from_distance + (self.to_distance - self.from_distance) * eased;
+        (orientation, distance, done)
+    }
+
+    // advance by time since last frame and sample at new elapsed time
+    pub fn step(&mut self, delta_ms: f32) -> (Quat, f32, bool) {
+        self.elapsed_ms += delta_ms.max(0.0);
+        self.sample(self.elapsed_ms)
+    }
+}
+
+// animates morph between globe (0) and flat map (1), eased like camera animations
+pub struct MorphAnimation {
+    pub from: f32,
+    pub to: f32,
+    pub duration_ms: f32,
+    pub elapsed_ms: f32,
+}
+
+impl MorphAnimation {
+    pub fn new(from: f32, to: f32, duration_ms: f32) -> Self {
+        Self {
+            from: from.clamp(0.0, 1.0),
+            to: to.clamp(0.0, 1.0),
+            duration_ms: duration_ms.max(0.0),
+            elapsed_ms: 0.0,
+        }
+    }
+
+    // morph at time since start, and true once animation has reached its end
+    pub fn sample(&self, elapsed_ms: f32) -> (f32, bool) {
+        let (eased, done) = eased_progress(elapsed_ms, self.duration_ms);
+        (self.from + (self.to - self.from) * eased, done)
+    }
+
+    pub fn step(&mut self, delta_ms: f32) -> (f32, bool) {
+        self.elapsed_ms += delta_ms.max(0.0);
+        self.sample(self.elapsed_ms)
+    }
+}
+
+// smoothstep eased fraction of duration elapsed, and if duration has passed
+fn eased_progress(elapsed_ms: f32, duration_ms: f32) -> (f32, bool) {
+    let t = if duration_ms > 0.0 {
+        (elapsed_ms / duration_ms).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    (t * t * (3.0 - 2.0 * t), t >= 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::lat_lon_to_xyz;
+
+    fn quat_near(a: Quat, b: Quat) -> bool {
+        a.dot(b).abs() > 1.0 - 1e-6
+    }
+
+    #[test]
+    fn test_orientation_for_center() {
+        for (lat, lon) in [(0.0, 0.0), (45.0, 90.0), (-30.0, -120.0), (60.0, 170.0)] {
+            let pos = Vec3::from(lat_lon_to_xyz(lat, lon, 1.0));
+            let centered = orientation_for_center(lat, lon) * pos;
+            assert!((centered - Vec3::Z).length() < 1e-5);
+        }
+        // north stays up when centered on equator
+        let north = orientation_for_center(0.0, 75.0) * Vec3::Y;
+        assert!((north - Vec3::Y).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_points_extent() {
+        // small cluster around 30, 60
+        let points: Vec<f32> = [
+            (30.0, 60.0),
+            (35.0, 60.0),
+            (25.0, 60.0),
+            (30.0, 65.0),
+            (30.0, 55.0),
+        ]
+        .iter()
+        .flat_map(|&(lat, lon)| lat_lon_to_xyz(lat, lon, 1.2))
+        .collect();
+        let (lat, lon, extent) = points_extent(&points).unwrap();
+        assert!((lat - 30.0).abs() < 0.5 && (lon - 60.0).abs() < 0.5);
+        assert!(extent > 4.0f32.to_radians() && extent < 6.0f32.to_radians());
+
+        // tight cluster fits closer than default view, globe wide extent stays at max
+        let close = fit_distance(extent, 0.6, 2.0);
+        assert!((MIN_DISTANCE..2.0).contains(&close));
+        assert_eq!(fit_distance(1.5, 0.6, 2.0), 2.0);
+
+        // opposite points have no center
+        let spread = [1.0, 0.0, 0.0, -1.0, 0.0, 0.0];
+        assert!(points_extent(&spread).is_none());
+        assert!(points_extent(&[]).is_none());
+    }
+
+    #[test]
+    fn test_animation_endpoints() {
+        let from = (Quat::IDENTITY, 2.0);
+        let to = (orientation_for_center(30.0, 60.0), 1.5);
+        let anim = CameraAnimation::new(from, to, 1000.0);
+
+        let (q, d, done) = anim.sample(0.0);
+        assert!(quat_near(q, from.0));
+        assert!((d - from.1).abs() < 1e-6);
+        assert!(!done);
+
+        let (q, d, done) = anim.sample(1000.0);
+        assert!(quat_near(q, to.0));
+        assert!((d - to.1).abs() < 1e-6);
+        assert!(done);
+
+        // past end stays at end
+        let (q, _, done) = anim.sample(5000.0);
+        assert!(quat_near(q, to.0));
+        assert!(done);
+    }
+
+    #[test]
+    fn test_animation_step_delta() {
+        let to = (orientation_for_center(40.0, -70.0), 1.5);
+        let mut split = CameraAnimation::new((Quat::IDENTITY, 2.0), to, 100.0);
+        let mut single = CameraAnimation::new((Quat::IDENTITY, 2.0), to, 100.0);
+        split.step(8.0);
+        let (q_split, d_split, _) = split.step(8.0);
+        let (q_single, d_single, _) = single.step(16.0);
+        assert!(quat_near(q_split, q_single));
+        assert!((d_split - d_single).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_animation_zero_duration() {
+        let to = (orientation_for_center(10.0, 20.0), 3.0);
+        let anim = CameraAnimation::new((Quat::IDENTITY, 2.0), to, 0.0);
+        let (q, d, done) = anim.sample(0.0);
+        assert!(quat_near(q, to.0));
+        assert_eq!(d, 3.0);
+        assert!(done);
+    }
+
+    #[test]
+    fn test_morph_animation() {
+        let mut morph = MorphAnimation::new(0.0, 1.0, 100.0);
+        assert_eq!(morph.sample(0.0), (0.0, false));
+        let (half, done) = morph.step(50.0);
+        assert!((half - 0.5).abs() < 1e-6 && !done);
+        assert_eq!(morph.step(60.0), (1.0, true));
+
+        // reverse from partway, zero duration jumps to end
+        let back = MorphAnimation::new(0.3, 0.0, 0.0);
+        assert_eq!(back.sample(0.0), (0.0, true));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::binning::cell_for_lat_lon;
+use crate::geo::xyz_to_lat_lon;
+use crate::gl_wrap::{Drop, ProgramCache};
+use crate::markers::{Marker, Markers, MarkersError, MAX_MARKER_SIZE, MIN_MARKER_SIZE};
+use std::collections::BTreeMap;
+
+// grid resolution for each camera distance threshold, furthest first
+// points are drawn individually when camera is closer than the last threshold
+const CLUSTER_LEVELS: [(f32, usize); 3] = [(5.0, 8), (3.0, 16), (1.8, 32)];
+
+// fraction of distance around a level threshold where current level is kept,
+// stops clusters popping in and out while zoom rests near a threshold
+const CLUSTER_HYSTERESIS: f32 = 0.05;
+
+// member count at which cluster markers reach max size, sizes scale with log of count
+const CLUSTER_MAX_COUNT: f32 = 1000.0;
+
+const CLUSTER_COLOR: [f32; 4] = [1.0, 0.45, 0.1, 1.0];
+
+// group of points sharing an equal area grid cell
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    pub cell: usize,
+    // mean member position
+    pub center: [f32; 3],
+    // indices of member points in point data
+    pub members: Vec<usize>,
+}
+
+impl Cluster {
+    // marker at cluster center sized by member count
+    pub fn marker(&self) -> Marker {
+        let (lat, lon) = xyz_to_lat_lon(self.center);
+        let t = ((self.members.len() as f32).ln() / CLUSTER_MAX_COUNT.ln()).clamp(0.0, 1.0);
+        let size = MIN_MARKER_SIZE + (MAX_MARKER_SIZE - MIN_MARKER_SIZE) * t;
+        Marker::from_lat_lon(lat, lon, CLUSTER_COLOR, size)
+    }
+}
+
+// grid resolution to cluster at for camera distance, None to draw individual points
+pub fn resolution_for_distance(distance: f32) -> Option<usize> {
+    CLUSTER_LEVELS
+        .iter()
+        .find(|(threshold, _)| distance >= *threshold)
+        .map(|(_, resolution)| *resolution)
+}
+
+// resolution for distance, keeping current resolution while distance is within
+// hysteresis band of the threshold between it and the new level
+pub fn resolution_with_hysteresis(distance: f32, current: Option<usize>) -> Option<usize> {
+    let near = resolution_for_distance(distance * (1.0 - CLUSTER_HYSTERESIS));
+    let far = resolution_for_distance(distance * (1.0 + CLUSTER_HYSTERESIS));
+    if near != far && (current == near || current == far) {
+        return current;
+    }
+    resolution_for_distance(distance)
+}
+
+// group interleaved xyz points by grid cell, sorted by cell id
+pub fn cluster_points(points: &[f32], resolution: usize) -> Vec<Cluster> {
+    let mut cells: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, p) in points.chunks_exact(3).enumerate() {
+        let (lat, lon) = xyz_to_lat_lon([p[0], p[1], p[2]]);
+        cells
+            .entry(cell_for_lat_lon(lat, lon, resolution))
+            .or_default()
+            .push(i);
+    }
+    cells
+        .into_iter()
+        .map(|(cell, members)| {
+            let mut center = [0.0; 3];
+            for &i in &members {
+                for (c, p) in center.iter_mut().zip(&points[i * 3..i * 3 + 3]) {
+                    *c += p;
+                }
+            }
+            let n = members.len() as f32;
+            let center = center.map(|c| c / n);
+            Cluster {
+                cell,
+                center,
+                members,
+            }
+        })
+        .collect()
+}
+
+// clusters for current zoom level and markers to draw them, cluster identity is its
+// grid cell so clusters keep their place as data changes. recomputed when point data
+// changes or zoom crosses a level threshold
+pub struct ClusterLayer {
+    pub markers: Markers,
+    pub clusters: Vec<Cluster>,
+    // None when zoomed in past clustering levels
+    pub resolution: Option<usize>,
+    // copy of last point data, kept to cluster on zoom and to restore points on zoom in
+    pub source: Vec<f32>,
+    // point data changed while clustered and hasn't been uploaded to points
+    pub points_stale: bool,
+}
+
+impl ClusterLayer {
+    pub fn new(gl: &glow::Context, shader_version: &str) -> Result<Self, MarkersError> {
+        Ok(Self::from_markers(Markers::new(gl, shader_version)?))
+    }
+
+    // cluster markers sharing marker program from cache with other marker layers
+    pub fn new_with_cache(
+        gl: &glow::Context,
+        cache: &mut ProgramCache,
+        shader_version: &str,
+    ) -> Result<Self, MarkersError> {
+        Ok(Self::from_markers(Markers::new_with_cache(
+            gl,
+            cache,
+            shader_version,
+        )?))
+    }
+
+    fn from_markers(markers: Markers) -> Self {
+        Self {
+            markers,
+            clusters: vec![],
+            resolution: None,
+            source: vec![],
+            points_stale: false,
+        }
+    }
+
+    // update clusters for camera distance and new point data, None keeps previous data.
+    // returns true if clustered
+    pub fn update(
+        &mut self,
+        gl: &glow::Context,
+        point_data: Option<&[f32]>,
+        distance: f32,
+    ) -> bool {
+        let resolution = resolution_with_hysteresis(distance, self.resolution);
+        let data_changed = point_data.is_some();
+        if let Some(p) = point_data {
+            self.source.clear();
+            self.source.extend_from_slice(p);
+        }
+        if resolution.is_some() && point_data.is_some() {
+            self.points_stale = true;
+        }
+        if resolution != self.resolution || (data_changed && resolution.is_some()) {
+            self.clusters = match resolution {
+                Some(resolution) => cluster_points(&self.source, resolution),
+                None => vec![],
+            };
+            let markers: Vec<Marker> = self.clusters.iter().map(Cluster::marker).collect();
+            self.markers.set_markers(gl, &markers);
+        }
+        self.resolution = resolution;
+        resolution.is_some()
+    }
+
+    // point data to draw when not clustered, restores data that arrived while clustered
+    pub fn unclustered_data<'a>(&'a mut self, point_data: Option<&'a [f32]>) -> Option<&'a [f32]> {
+        if self.points_stale {
+            self.points_stale = false;
+            return Some(&self.source);
+        }
+        point_data
+    }
+
+    // cluster containing lat / lon at current resolution, None if not clustered or empty
+    pub fn cluster_at(&self, lat: f32, lon: f32) -> Option<&Cluster> {
+        let cell = cell_for_lat_lon(lat, lon, self.resolution?);
+        let i = self.clusters.binary_search_by_key(&cell, |c| c.cell).ok()?;
+        Some(&self.clusters[i])
+    }
+}
+
+impl Drop for ClusterLayer {
+    fn drop(&self, gl: &glow::Context) {
+        self.markers.drop(gl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::lat_lon_to_xyz;
+
+    #[test]
+    fn test_cluster_points() {
+        // 2 rows split at equator, 4 columns of 90 degrees starting at lon -180
+        let points: Vec<f32> = [(45.0, 10.0), (-45.0, -170.0), (30.0, 80.0), (60.0, 20.0)]
+            .iter()
+            .flat_map(|&(lat, lon)| lat_lon_to_xyz(lat, lon, 1.0))
+            .collect();
+        let clusters = cluster_points(&points, 2);
+        let members: Vec<(usize, Vec<usize>)> = clusters
+            .iter()
+            .map(|c| (c.cell, c.members.clone()))
+            .collect();
+        assert_eq!(members, vec![(0, vec![1]), (6, vec![0, 2, 3])]);
+        // single member cluster sits on its point
+        assert_eq!(clusters[0].center, [points[3], points[4], points[5]]);
+        assert!(clusters[1].marker().size > clusters[0].marker().size);
+    }
+
+    #[test]
+    fn test_resolution_for_distance() {
+        assert_eq!(resolution_for_distance(9.0), Some(8));
+        assert_eq!(resolution_for_distance(3.0), Some(16));
+        assert_eq!(resolution_for_distance(2.0), Some(32));
+        assert_eq!(resolution_for_distance(1.2), None);
+    }
+
+    #[test]
+    fn test_resolution_with_hysteresis() {
+        // zooming in just past threshold keeps coarser level, further switches
+        assert_eq!(resolution_with_hysteresis(2.95, Some(16)), Some(16));
+        assert_eq!(resolution_with_hysteresis(2.8, Some(16)), Some(32));
+        // and zooming back out just past it keeps finer level
+        assert_eq!(resolution_with_hysteresis(3.05, Some(32)), Some(32));
+        assert_eq!(resolution_with_hysteresis(3.2, Some(32)), Some(16));
+        // same for switching to individual points
+        assert_eq!(resolution_with_hysteresis(1.75, Some(32)), Some(32));
+        assert_eq!(resolution_with_hysteresis(1.85, None), None);
+        // jumps from a level outside the band go straight to new level
+        assert_eq!(resolution_with_hysteresis(2.95, Some(8)), Some(32));
+        assert_eq!(resolution_with_hysteresis(9.0, None), Some(8));
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+// qualitative palette (tableau 10) as srgb bytes, distinct hues for categorical data
+const CATEGORY_PALETTE: [[u8; 3]; 10] = [
+    [78, 121, 167],
+    [242, 142, 43],
+    [225, 87, 89],
+    [118, 183, 178],
+    [89, 161, 79],
+    [237, 201, 72],
+    [176, 122, 161],
+    [255, 157, 167],
+    [156, 117, 95],
+    [186, 176, 172],
+];
+
+// convert srgb byte to linear float, point colors are linear and gamma corrected on output
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// maps category indices or names to rgba colors for Points::set_colors
+// index i always gets palette[i % len], names are assigned indices in first seen order
+pub struct CategoryColors {
+    palette: Vec<[f32; 4]>,
+    names: HashMap<String, usize>,
+}
+
+impl CategoryColors {
+    pub fn new() -> Self {
+        let palette = CATEGORY_PALETTE
+            .iter()
+            .map(|&[r, g, b]| [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), 1.0])
+            .collect();
+        Self::from_palette(palette)
+    }
+
+    // custom linear rgba palette, falls back to built in palette if empty
+    pub fn from_palette(palette: Vec<[f32; 4]>) -> Self {
+        if palette.is_empty() {
+            return Self::new();
+        }
+        let names = HashMap::new();
+        Self { palette, names }
+    }
+
+    pub fn palette_len(&self) -> usize {
+        self.palette.len()
+    }
+
+    // color for category index, wraps once categories exceed palette size
+    pub fn color(&self, category: usize) -> [f32; 4] {
+        self.palette[category % self.palette.len()]
+    }
+
+    // stable index for category name, new names take next unused index
+    pub fn index_for(&mut self, name: &str) -> usize {
+        let next = self.names.len();
+        *self.names.entry(name.to_string()).or_insert(next)
+    }
+
+    // interleaved rgba per point color buffer from category indices
+    pub fn colors_for(&self, categories: &[usize]) -> Vec<f32> {
+        categories.iter().flat_map(|&c| self.color(c)).collect()
+    }
+
+    // interleaved rgba per point color buffer from category names
+    pub fn colors_for_names<S: AsRef<str>>(&mut self, names: &[S]) -> Vec<f32> {
+        let categories: Vec<usize> = names.iter().map(|n| self.index_for(n.as_ref())).collect();
+        self.colors_for(&categories)
+    }
+}
+
+impl Default for CategoryColors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distinct_colors() {
+        let colors = CategoryColors::new();
+        let n = colors.palette_len();
+        let buffer = colors.colors_for(&(0..n).collect::<Vec<_>>());
+        assert_eq!(buffer.len(), n * 4);
+        let rgba: Vec<&[f32]> = buffer.chunks_exact(4).collect();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                assert_ne!(rgba[i], rgba[j]);
+            }
+        }
+        // wraps past palette size
+        assert_eq!(colors.color(n), colors.color(0));
+    }
+
+    #[test]
+    fn test_stable_names() {
+        let mut colors = CategoryColors::new();
+        let first = colors.colors_for_names(&["quake", "volcano", "quake"]);
+        assert_eq!(first[0..4], first[8..12]);
+        assert_ne!(first[0..4], first[4..8]);
+        // same name keeps its color on later frames
+        let second = colors.colors_for_names(&["volcano", "tsunami", "quake"]);
+        assert_eq!(second[0..4], first[4..8]);
+        assert_eq!(second[8..12], first[0..4]);
+        assert_eq!(colors.index_for("tsunami"), 2);
+    }
+}
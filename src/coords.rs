@@ -0,0 +1,73 @@
+// world coordinate convention shared by globe, points and camera
+//
+// right handed, y up through the north pole, lon 0 (prime meridian) facing +z
+// and lon 90 east facing +x, so east x north = +z. default camera sits on +z
+// looking at globe center with y up (view_from_distance), putting lat 0 / lon 0
+// at screen center, north up and east to the right. flat map lays lon along x
+// and lat along y, so drag directions agree between map modes
+//
+// place point data with lat_lon_to_xyz rather than hand written trig, swapping
+// sin / cos or y / z rotates data 90 degrees or mirrors it
+use glam::Vec3;
+
+pub use crate::geo::{lat_lon_to_xyz, xyz_to_lat_lon};
+
+// unit directions for lat / lon reference points
+pub const NORTH: Vec3 = Vec3::Y;
+pub const PRIME_MERIDIAN: Vec3 = Vec3::Z;
+pub const EAST: Vec3 = Vec3::X;
+
+// local east / north / up unit vectors at lat / lon in degrees, for orienting
+// features along the surface. east is undefined at the poles, +x is used there
+pub fn tangent_frame(lat: f32, lon: f32) -> (Vec3, Vec3, Vec3) {
+    let up = Vec3::from(lat_lon_to_xyz(lat, lon, 1.0));
+    let east = NORTH.cross(up).try_normalize().unwrap_or(EAST);
+    let north = up.cross(east);
+    (east, north, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vis_gl::MvpMatrices;
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_axes() {
+        assert_near(Vec3::from(lat_lon_to_xyz(90.0, 0.0, 1.0)), NORTH);
+        assert_near(Vec3::from(lat_lon_to_xyz(0.0, 0.0, 1.0)), PRIME_MERIDIAN);
+        assert_near(Vec3::from(lat_lon_to_xyz(0.0, 90.0, 1.0)), EAST);
+        assert_near(Vec3::from(lat_lon_to_xyz(0.0, -90.0, 1.0)), -EAST);
+        // right handed
+        assert_near(EAST.cross(NORTH), PRIME_MERIDIAN);
+
+        let (east, north, up) = tangent_frame(30.0, 45.0);
+        assert_near(east.cross(north), up);
+        // moving north / east increases lat / lon
+        let (lat, lon) = xyz_to_lat_lon((up + north * 0.01).to_array());
+        assert!(lat > 30.0 && (lon - 45.0).abs() < 1e-3);
+        let (lat, lon) = xyz_to_lat_lon((up + east * 0.01).to_array());
+        assert!(lon > 45.0 && (lat - 30.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_default_view_handedness() {
+        let mvp = MvpMatrices::new_default(800.0, 600.0).unwrap();
+        let ndc = |lat: f32, lon: f32| {
+            let clip =
+                mvp.view_projection() * Vec3::from(lat_lon_to_xyz(lat, lon, 1.0)).extend(1.0);
+            clip.truncate() / clip.w
+        };
+        // lat 0 / lon 0 at screen center, north up, east right
+        let center = ndc(0.0, 0.0);
+        assert!(center.x.abs() < 1e-5 && center.y.abs() < 1e-5);
+        assert!(ndc(30.0, 0.0).y > 0.0);
+        assert!(ndc(0.0, 30.0).x > 0.0);
+        assert!(ndc(0.0, -30.0).x < 0.0);
+        // antimeridian behind globe center
+        assert!(ndc(0.0, 180.0).z > ndc(0.0, 0.0).z);
+    }
+}
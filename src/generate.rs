@@ -0,0 +1,72 @@
+// point generators for demos / tests
+// randomized generators take an explicit seed, same seed gives same output
+// so screenshots and recordings are reproducible
+
+// small splitmix64 generator, avoids a dependency for a few random helpers
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, 1), from top 24 bits to fill f32 mantissa exactly
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// xyz positions uniformly distributed on sphere surface
+pub fn random_sphere(count: usize, radius: f32, seed: u64) -> Vec<f32> {
+    let mut rng = SeededRng::new(seed);
+    (0..count)
+        .flat_map(|_| {
+            let z = 2.0 * rng.next_f32() - 1.0;
+            let theta = 2.0 * std::f32::consts::PI * rng.next_f32();
+            let r = (1.0 - z * z).sqrt();
+            [
+                radius * r * theta.cos(),
+                radius * r * theta.sin(),
+                radius * z,
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_sphere_seeded() {
+        let a = random_sphere(100, 1.5, 42);
+        let b = random_sphere(100, 1.5, 42);
+        assert_eq!(a, b);
+        assert_ne!(a, random_sphere(100, 1.5, 43));
+
+        assert_eq!(a.len(), 300);
+        for p in a.chunks_exact(3) {
+            let len = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+            assert!((len - 1.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_next_f32_range() {
+        let mut rng = SeededRng::new(0);
+        for _ in 0..1000 {
+            let x = rng.next_f32();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+}
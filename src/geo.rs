@@ -0,0 +1,215 @@
+use crate::icosphere::get_icosphere_mesh;
+use std::f32::consts::PI;
+
+// radial push in globe radii applied in overlay vertex shaders, so features placed on
+// the unit sphere draw over the globe instead of z-fighting with it. the globe mesh is
+// flat between vertices and sits inside the sphere, offset only needs to cover depth precision.
+// default for lines and paths, points and markers default to 0 so data positions are drawn
+// as given, set this on them with set_surface_offset when they're placed on the surface
+pub const DEFAULT_SURFACE_OFFSET: f32 = 0.01;
+
+// lat / lon in degrees, y axis through poles and lon 0 facing +z, see coords
+pub fn lat_lon_to_xyz(lat: f32, lon: f32, radius: f32) -> [f32; 3] {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    [
+        radius * lat.cos() * lon.sin(),
+        radius * lat.sin(),
+        radius * lat.cos() * lon.cos(),
+    ]
+}
+
+// inverse of lat_lon_to_xyz, radius of input position is ignored
+pub fn xyz_to_lat_lon(pos: [f32; 3]) -> (f32, f32) {
+    let [x, y, z] = pos;
+    let len = (x * x + y * y + z * z).sqrt();
+    if len == 0.0 {
+        return (0.0, 0.0); // prevent divide by 0
+    }
+    let lat = (y / len).clamp(-1.0, 1.0).asin();
+    let lon = x.atan2(z);
+    (lat.to_degrees(), lon.to_degrees())
+}
+
+// position on flat equirectangular map, lon along x and lat along y in radians
+// so map spans 2pi by pi with same scale at equator as unit globe. mirrors
+// flatPosition in vertex shaders
+pub fn flat_position(pos: [f32; 3]) -> [f32; 3] {
+    let (lat, lon) = xyz_to_lat_lon(pos);
+    [lon.to_radians(), lat.to_radians(), 0.0]
+}
+
+// mean earth radius, pass as radius to get great circle distances in km
+pub const EARTH_RADIUS_KM: f32 = 6371.0;
+
+// central angle in degrees between two lat / lon points along the great circle,
+// haversine form stays accurate for nearby points. computed in f64 since f32
+// loses meters at earth scale
+pub fn great_circle_degrees(lat1: f32, lon1: f32, lat2: f32, lon2: f32) -> f32 {
+    let (lat1, lat2) = ((lat1 as f64).to_radians(), (lat2 as f64).to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 as f64 - lon1 as f64).to_radians();
+    let h = (dlat * 0.5).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon * 0.5).sin().powi(2);
+    let angle = 2.0 * h.sqrt().clamp(0.0, 1.0).asin();
+    angle.to_degrees() as f32
+}
+
+// surface distance between two lat / lon points on sphere of radius, in radius units.
+// globe and positions from lat_lon_to_xyz(.., 1.0) use unit radius, so radius 1.0 gives
+// world units matching the scene, EARTH_RADIUS_KM gives km
+pub fn great_circle_distance(lat1: f32, lon1: f32, lat2: f32, lon2: f32, radius: f32) -> f32 {
+    great_circle_degrees(lat1, lon1, lat2, lon2).to_radians() * radius
+}
+
+// n interleaved xyz positions spread near evenly over sphere, each point covers about
+// the same area. golden angle spiral from pole to pole, y axis through poles like lat / lon
+pub fn fibonacci_sphere(n: usize, radius: f32) -> Vec<f32> {
+    let golden_angle = PI * (3.0 - 5f32.sqrt());
+    (0..n)
+        .flat_map(|i| {
+            // offset by half a step so poles aren't sampled twice
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / n as f32;
+            let r = (1.0 - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            [
+                radius * r * theta.sin(),
+                radius * y,
+                radius * r * theta.cos(),
+            ]
+        })
+        .collect()
+}
+
+// interleaved xyz icosphere vertices at radius, regular grid with 10 * 4^subdivisions + 2
+// points matching globe mesh vertices at the same subdivision level
+pub fn geodesic_grid(subdivisions: usize, radius: f32) -> Vec<f32> {
+    let (vertices, _) = get_icosphere_mesh(subdivisions);
+    vertices
+        .iter()
+        .flat_map(|v| v.map(|c| c * radius))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_lat_lon_axes() {
+        let expected = [
+            ((0.0, 0.0), [0.0, 0.0, 1.0]),
+            ((0.0, 90.0), [1.0, 0.0, 0.0]),
+            ((90.0, 0.0), [0.0, 1.0, 0.0]),
+            ((-90.0, 0.0), [0.0, -1.0, 0.0]),
+        ];
+        for ((lat, lon), xyz) in expected {
+            let pos = lat_lon_to_xyz(lat, lon, 1.0);
+            for i in 0..3 {
+                assert!((pos[i] - xyz[i]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lat_lon_round_trip() {
+        for lat in [-80.0, -45.0, 0.0, 30.0, 75.0] {
+            for lon in [-170.0, -90.0, 0.0, 45.0, 179.0] {
+                let (lat_out, lon_out) = xyz_to_lat_lon(lat_lon_to_xyz(lat, lon, 2.5));
+                assert!((lat - lat_out).abs() < 1e-3);
+                assert!((lon - lon_out).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_flat_position() {
+        let [x, y, z] = flat_position(lat_lon_to_xyz(45.0, -90.0, 2.0));
+        assert!((x + FRAC_PI_2).abs() < 1e-5);
+        assert!((y - FRAC_PI_2 * 0.5).abs() < 1e-5);
+        assert_eq!(z, 0.0);
+    }
+
+    #[test]
+    fn test_great_circle_distance() {
+        // city pairs with known distances in km, within 0.5%
+        let pairs = [
+            // london, paris
+            ((51.5074, -0.1278), (48.8566, 2.3522), 343.5),
+            // new york, los angeles
+            ((40.7128, -74.006), (34.0522, -118.2437), 3935.7),
+            // sydney, tokyo
+            ((-33.8688, 151.2093), (35.6762, 139.6503), 7823.0),
+        ];
+        for ((lat1, lon1), (lat2, lon2), km) in pairs {
+            let d = great_circle_distance(lat1, lon1, lat2, lon2, EARTH_RADIUS_KM);
+            assert!((d - km).abs() / km < 5e-3, "{d} {km}");
+            // symmetric
+            let back = great_circle_distance(lat2, lon2, lat1, lon1, EARTH_RADIUS_KM);
+            assert!((d - back).abs() < 1e-3);
+        }
+
+        // antipodes and pole to equator in degrees, unit radius gives radians
+        assert!((great_circle_degrees(0.0, 0.0, 0.0, 180.0) - 180.0).abs() < 1e-4);
+        assert!((great_circle_degrees(90.0, 0.0, 0.0, 123.0) - 90.0).abs() < 1e-4);
+        assert!((great_circle_distance(0.0, 0.0, 0.0, 90.0, 1.0) - FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(great_circle_distance(12.0, 34.0, 12.0, 34.0, 1.0), 0.0);
+        // wrapping across antimeridian takes short way
+        assert!((great_circle_degrees(0.0, 179.0, 0.0, -179.0) - 2.0).abs() < 1e-4);
+    }
+
+    // distance from each point to its nearest neighbor, brute force
+    fn nearest_distances(positions: &[f32]) -> Vec<f32> {
+        let points: Vec<&[f32]> = positions.chunks(3).collect();
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                points
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, b)| {
+                        a.iter()
+                            .zip(b.iter())
+                            .map(|(x, y)| (x - y).powi(2))
+                            .sum::<f32>()
+                    })
+                    .fold(f32::MAX, f32::min)
+                    .sqrt()
+            })
+            .collect()
+    }
+
+    // standard deviation over mean
+    fn variation(values: &[f32]) -> f32 {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+        var.sqrt() / mean
+    }
+
+    #[test]
+    fn test_fibonacci_sphere() {
+        let positions = fibonacci_sphere(500, 2.0);
+        assert_eq!(positions.len(), 1500);
+        for p in positions.chunks(3) {
+            let len = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+            assert!((len - 2.0).abs() < 1e-4);
+        }
+        // near uniform spacing, random points vary far more
+        let spacing = variation(&nearest_distances(&positions));
+        assert!(spacing < 0.1, "{spacing}");
+        let random = variation(&nearest_distances(&crate::generate::random_sphere(
+            500, 2.0, 7,
+        )));
+        assert!(random > 2.0 * spacing, "{random} {spacing}");
+        assert!(fibonacci_sphere(0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_geodesic_grid() {
+        let positions = geodesic_grid(2, 0.5);
+        assert_eq!(positions.len(), 3 * (10 * 16 + 2));
+        let spacing = variation(&nearest_distances(&positions));
+        assert!(spacing < 0.15, "{spacing}");
+    }
+}
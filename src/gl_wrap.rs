@@ -1,5 +1,8 @@
-use glam::Mat4;
+use glam::{Mat4, Vec3, Vec4};
 use glow::HasContext;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 
 // free resources
 pub trait Drop {
@@ -11,6 +14,21 @@ pub trait Bind {
     fn bind(&self, gl: &glow::Context);
 }
 
+// vertex / fragment source pair for user supplied shaders
+#[derive(Clone, Debug)]
+pub struct ShaderSources {
+    pub vertex: String,
+    pub fragment: String,
+}
+
+impl ShaderSources {
+    pub fn new(vertex: &str, fragment: &str) -> Self {
+        let vertex = vertex.to_string();
+        let fragment = fragment.to_string();
+        Self { vertex, fragment }
+    }
+}
+
 pub struct Shader {
     pub id: glow::Shader,
 }
@@ -35,16 +53,23 @@ impl Shader {
         unsafe {
             success = gl.get_shader_compile_status(id);
         }
+        let shader = Self { id };
         if success {
-            Ok(Self { id })
-        } else {
-            let log;
-            unsafe {
-                log = gl.get_shader_info_log(id);
+            // drivers may warn on successful compiles, e.g. precision defaults on webgl
+            let log = shader.info_log(gl);
+            if !log.trim().is_empty() {
+                log::debug!("shader compile log: {}", log);
             }
-            Err(ShaderError::Compilation(log))
+            Ok(shader)
+        } else {
+            Err(ShaderError::Compilation(shader.info_log(gl)))
         }
     }
+
+    // compile log regardless of compile status, empty if driver had nothing to report
+    pub fn info_log(&self, gl: &glow::Context) -> String {
+        unsafe { gl.get_shader_info_log(self.id) }
+    }
 }
 
 impl Drop for Shader {
@@ -57,6 +82,8 @@ impl Drop for Shader {
 
 pub struct Program {
     pub id: glow::Program,
+    // locations of uniforms looked up by name, None for names program doesn't declare
+    locations: RefCell<HashMap<String, Option<glow::UniformLocation>>>,
 }
 
 impl Program {
@@ -78,17 +105,41 @@ impl Program {
         unsafe {
             success = gl.get_program_link_status(id);
         }
+        let program = Self {
+            id,
+            locations: RefCell::new(HashMap::new()),
+        };
         if success {
-            Ok(Self { id })
-        } else {
-            let log;
-            unsafe {
-                log = gl.get_program_info_log(id);
+            let log = program.info_log(gl);
+            if !log.trim().is_empty() {
+                log::debug!("program link log: {}", log);
             }
-            Err(ProgramError::Linking(log))
+            Ok(program)
+        } else {
+            Err(ProgramError::Linking(program.info_log(gl)))
         }
     }
 
+    // link log regardless of link status, empty if driver had nothing to report
+    pub fn info_log(&self, gl: &glow::Context) -> String {
+        unsafe { gl.get_program_info_log(self.id) }
+    }
+
+    // cached location of uniform, None if not declared or removed by the compiler as unused.
+    // avoids a driver lookup each frame for uniforms set by name
+    pub fn uniform_location(
+        &self,
+        gl: &glow::Context,
+        name: &str,
+    ) -> Option<glow::UniformLocation> {
+        let mut locations = self.locations.borrow_mut();
+        if !locations.contains_key(name) {
+            let location = unsafe { gl.get_uniform_location(self.id, name) };
+            locations.insert(name.to_string(), location);
+        }
+        locations.get(name).cloned().flatten()
+    }
+
     // constructor from files for convenience
     pub fn new_from_sources(
         gl: &glow::Context,
@@ -119,36 +170,238 @@ impl Drop for Program {
 
 impl Bind for Program {
     fn bind(&self, gl: &glow::Context) {
+        PROGRAM_BINDS.with(|binds| binds.set(binds.get() + 1));
         unsafe {
             gl.use_program(Some(self.id));
         }
     }
 }
 
+thread_local! {
+    static PROGRAM_BINDS: Cell<usize> = const { Cell::new(0) };
+}
+
+// use_program calls made through Program::bind on this thread, difference across a frame
+// counts its program switches
+pub fn program_binds() -> usize {
+    PROGRAM_BINDS.with(|binds| binds.get())
+}
+
+// layer owned handle to possibly shared program, deleted only by its last owner
+// so layers can drop a program from a cache without invalidating the others
+impl Drop for Rc<Program> {
+    fn drop(&self, gl: &glow::Context) {
+        if Rc::strong_count(self) == 1 {
+            self.as_ref().drop(gl);
+        }
+    }
+}
+
+// programs keyed by shader version and sources, layers drawn with the same shaders
+// share one linked program instead of each compiling their own. uniforms are program
+// state, so layers sharing a program must set their own uniforms before drawing.
+// only markers and cluster markers use it, globe, points and lines stay outside on purpose:
+// vis has one of each, so there's nothing to share, and globe / points take user shaders
+// and keep their uniforms set between frames instead of reapplying them every draw
+#[derive(Default)]
+pub struct ProgramCache {
+    programs: HashMap<(String, String, String), Rc<Program>>,
+}
+
+impl ProgramCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // cached program for sources, compiled and linked on first request
+    pub fn get(
+        &mut self,
+        gl: &glow::Context,
+        version: &str,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<Rc<Program>, ProgramError> {
+        let key = (
+            version.to_string(),
+            vertex_source.to_string(),
+            fragment_source.to_string(),
+        );
+        if let Some(program) = self.programs.get(&key) {
+            return Ok(Rc::clone(program));
+        }
+        let program = Rc::new(Program::new_from_sources(
+            gl,
+            version,
+            vertex_source,
+            fragment_source,
+        )?);
+        self.programs.insert(key, Rc::clone(&program));
+        Ok(program)
+    }
+
+    // number of distinct programs linked
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+}
+
+// deletes every cached program, drop after all layers using them
+impl Drop for ProgramCache {
+    fn drop(&self, gl: &glow::Context) {
+        for program in self.programs.values() {
+            program.as_ref().drop(gl);
+        }
+    }
+}
+
+// how often buffer data is expected to change, hint for gpu memory placement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferUsage {
+    // set once, drawn many times
+    Static,
+    // set repeatedly, drawn many times
+    Dynamic,
+    // set once, drawn a few times
+    Stream,
+}
+
+impl BufferUsage {
+    pub fn to_gl(self) -> u32 {
+        match self {
+            BufferUsage::Static => glow::STATIC_DRAW,
+            BufferUsage::Dynamic => glow::DYNAMIC_DRAW,
+            BufferUsage::Stream => glow::STREAM_DRAW,
+        }
+    }
+}
+
 pub struct Buffer {
     pub id: glow::Buffer,
-    pub draw_type: u32,
+    pub usage: BufferUsage,
+    // floats of valid data
     pub len: usize,
+    // floats allocated, at least len
+    pub capacity: usize,
 }
 
 impl Buffer {
-    pub fn new(gl: &glow::Context, draw_type: u32) -> Result<Self, BufferError> {
+    pub fn new(gl: &glow::Context, usage: BufferUsage) -> Result<Self, BufferError> {
         let id;
         unsafe {
             id = gl.create_buffer()?;
         }
         let len: usize = 0;
-        let buffer = Self { id, draw_type, len };
+        let capacity: usize = 0;
+        let buffer = Self {
+            id,
+            usage,
+            len,
+            capacity,
+        };
         Ok(buffer)
     }
 
+    // empty buffer with bytes allocated up front, rounded up to whole floats. uploads
+    // through write that fit reuse the allocation instead of reallocating on first use
+    pub fn with_capacity(
+        gl: &glow::Context,
+        usage: BufferUsage,
+        bytes: usize,
+    ) -> Result<Self, BufferError> {
+        let mut buffer = Self::new(gl, usage)?;
+        buffer.reserve(gl, bytes.div_ceil(std::mem::size_of::<f32>()))?;
+        Ok(buffer)
+    }
+
+    // upload data, overwriting current allocation in place when data fits it
+    // and reallocating to data length otherwise
+    pub fn write(&mut self, gl: &glow::Context, data: &[f32]) {
+        if data.len() > self.capacity {
+            self.set_data(gl, data);
+            return;
+        }
+        if !data.is_empty() {
+            self.set_sub_data(gl, 0, data);
+        }
+        self.len = data.len();
+    }
+
     pub fn set_data(&mut self, gl: &glow::Context, data: &[f32]) {
         self.bind(gl);
         self.len = data.len();
+        self.capacity = data.len();
         unsafe {
             let (_, bytes, _) = data.align_to::<u8>();
-            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytes, self.draw_type);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytes, self.usage.to_gl());
+        }
+    }
+
+    // grow allocation to at least capacity floats, keeping current data and buffer id
+    // so vao attribute pointers stay valid. data is copied through a temporary buffer
+    pub fn reserve(&mut self, gl: &glow::Context, capacity: usize) -> Result<(), BufferError> {
+        if capacity <= self.capacity {
+            return Ok(());
+        }
+        let fsize = std::mem::size_of::<f32>();
+        let bytes = (self.len * fsize) as i32;
+        unsafe {
+            let temp = gl.create_buffer()?;
+            if bytes > 0 {
+                gl.bind_buffer(glow::COPY_WRITE_BUFFER, Some(temp));
+                gl.buffer_data_size(glow::COPY_WRITE_BUFFER, bytes, glow::STREAM_COPY);
+                gl.bind_buffer(glow::COPY_READ_BUFFER, Some(self.id));
+                gl.copy_buffer_sub_data(
+                    glow::COPY_READ_BUFFER,
+                    glow::COPY_WRITE_BUFFER,
+                    0,
+                    0,
+                    bytes,
+                );
+            }
+            gl.bind_buffer(glow::COPY_WRITE_BUFFER, Some(self.id));
+            let size = (capacity * fsize) as i32;
+            gl.buffer_data_size(glow::COPY_WRITE_BUFFER, size, self.usage.to_gl());
+            if bytes > 0 {
+                gl.bind_buffer(glow::COPY_READ_BUFFER, Some(temp));
+                gl.copy_buffer_sub_data(
+                    glow::COPY_READ_BUFFER,
+                    glow::COPY_WRITE_BUFFER,
+                    0,
+                    0,
+                    bytes,
+                );
+            }
+            gl.delete_buffer(temp);
+        }
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    // overwrite part of buffer starting at float offset, keeps existing allocation
+    // offset + data length must be within current buffer capacity
+    pub fn set_sub_data(&self, gl: &glow::Context, offset: usize, data: &[f32]) {
+        self.bind(gl);
+        unsafe {
+            let (_, bytes, _) = data.align_to::<u8>();
+            let byte_offset = (offset * std::mem::size_of::<f32>()) as i32;
+            gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, byte_offset, bytes);
+        }
+    }
+
+    // read current buffer contents back from gpu
+    // slow synchronous operation, meant for tests / debugging not draw loop
+    pub fn read_back(&self, gl: &glow::Context) -> Vec<f32> {
+        self.bind(gl);
+        let mut data = vec![0.0; self.len];
+        unsafe {
+            let (_, bytes, _) = data.align_to_mut::<u8>();
+            gl.get_buffer_sub_data(glow::ARRAY_BUFFER, 0, bytes);
         }
+        data
     }
 }
 
@@ -168,6 +421,195 @@ impl Drop for Buffer {
     }
 }
 
+// alpha blend color over destination, alpha accumulates as coverage so output is
+// premultiplied, needed for compositing over a transparent background
+pub fn blend_over(gl: &glow::Context) {
+    unsafe {
+        gl.blend_func_separate(
+            glow::SRC_ALPHA,
+            glow::ONE_MINUS_SRC_ALPHA,
+            glow::ONE,
+            glow::ONE_MINUS_SRC_ALPHA,
+        );
+    }
+}
+
+// index value that ends current line / triangle strip and starts a new one
+// max u32 to match the fixed restart index webgl2 always uses for u32 elements
+pub const RESTART_INDEX: u32 = u32::MAX;
+
+// glPrimitiveRestartIndex isn't wrapped by glow, loaded with the context's own loader
+#[cfg(not(target_arch = "wasm32"))]
+static RESTART_INDEX_FN: std::sync::OnceLock<unsafe extern "system" fn(u32)> =
+    std::sync::OnceLock::new();
+
+// load glPrimitiveRestartIndex from loader used to create glow context, so restart can
+// use RESTART_INDEX on gl below 4.3, where fixed index restart isn't available
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_primitive_restart(loader: impl FnOnce(&str) -> *const std::ffi::c_void) {
+    let ptr = loader("glPrimitiveRestartIndex");
+    if !ptr.is_null() {
+        type IndexFn = unsafe extern "system" fn(u32);
+        let index_fn = unsafe { std::mem::transmute::<*const std::ffi::c_void, IndexFn>(ptr) };
+        RESTART_INDEX_FN.get_or_init(|| index_fn);
+    }
+}
+
+// break strips on RESTART_INDEX, false if context can't so callers draw without restart.
+// webgl2 always restarts on the fixed index. native sets the index explicitly when
+// load_primitive_restart found it, which works on 4.1 core, else needs gl 4.3
+pub fn enable_primitive_restart(gl: &glow::Context) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = gl;
+        true
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe {
+        if let Some(index_fn) = RESTART_INDEX_FN.get() {
+            gl.enable(glow::PRIMITIVE_RESTART);
+            index_fn(RESTART_INDEX);
+            return true;
+        }
+        let version = gl.version();
+        if (version.major, version.minor) >= (4, 3) {
+            gl.enable(glow::PRIMITIVE_RESTART_FIXED_INDEX);
+            return true;
+        }
+        false
+    }
+}
+
+// get index stream for drawing consecutive strips from one vertex buffer
+// strips are separated by restart index so each layer is a single draw call
+pub fn strip_indices(strip_lengths: &[usize]) -> Vec<u32> {
+    let mut indices = Vec::with_capacity(strip_lengths.iter().sum::<usize>() + strip_lengths.len());
+    let mut vertex = 0;
+    for (i, &len) in strip_lengths.iter().enumerate() {
+        if i > 0 {
+            indices.push(RESTART_INDEX);
+        }
+        indices.extend(vertex..vertex + len as u32);
+        vertex += len as u32;
+    }
+    indices
+}
+
+// index pairs drawing the same strips as separate lines, for contexts without restart
+pub fn strip_line_indices(strip_lengths: &[usize]) -> Vec<u32> {
+    let mut indices = vec![];
+    let mut vertex = 0;
+    for &len in strip_lengths {
+        for i in 1..len as u32 {
+            indices.extend([vertex + i - 1, vertex + i]);
+        }
+        vertex += len as u32;
+    }
+    indices
+}
+
+// buffer of u32 indices for indexed drawing
+pub struct ElementBuffer {
+    pub id: glow::Buffer,
+    pub usage: BufferUsage,
+    pub len: usize,
+}
+
+impl ElementBuffer {
+    pub fn new(gl: &glow::Context, usage: BufferUsage) -> Result<Self, BufferError> {
+        let id;
+        unsafe {
+            id = gl.create_buffer()?;
+        }
+        let len: usize = 0;
+        Ok(Self { id, usage, len })
+    }
+
+    pub fn set_data(&mut self, gl: &glow::Context, data: &[u32]) {
+        self.bind(gl);
+        self.len = data.len();
+        unsafe {
+            let (_, bytes, _) = data.align_to::<u8>();
+            gl.buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, bytes, self.usage.to_gl());
+        }
+    }
+
+    // draw all indices with given primitive type, vao must be bound
+    pub fn draw(&self, gl: &glow::Context, mode: u32) {
+        self.bind(gl);
+        unsafe {
+            gl.draw_elements(mode, self.len as i32, glow::UNSIGNED_INT, 0);
+        }
+    }
+}
+
+impl Bind for ElementBuffer {
+    fn bind(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.id));
+        }
+    }
+}
+
+impl Drop for ElementBuffer {
+    fn drop(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_buffer(self.id);
+        }
+    }
+}
+
+// attribute in interleaved vertex data, size and offset in floats
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexAttrib {
+    pub name: String,
+    pub size: i32,
+    pub offset: i32,
+}
+
+// layout of interleaved float vertex data, stride is floats per vertex
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexLayout {
+    pub stride: i32,
+    pub attribs: Vec<VertexAttrib>,
+}
+
+impl VertexLayout {
+    // tightly packed attributes in order, e.g. [("position", 3), ("color", 4)]
+    pub fn new(attribs: &[(&str, i32)]) -> Self {
+        let mut offset = 0;
+        let attribs = attribs
+            .iter()
+            .map(|&(name, size)| {
+                let attrib = VertexAttrib {
+                    name: name.to_string(),
+                    size,
+                    offset,
+                };
+                offset += size;
+                attrib
+            })
+            .collect();
+        Self {
+            stride: offset,
+            attribs,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.attribs.iter().any(|a| a.name == name)
+    }
+
+    // number of whole vertices in data, None if data isn't a multiple of stride
+    pub fn vertex_count(&self, len: usize) -> Option<usize> {
+        let stride = self.stride as usize;
+        if stride == 0 || !len.is_multiple_of(stride) {
+            return None;
+        }
+        Some(len / stride)
+    }
+}
+
 pub struct VertexArray {
     pub id: glow::VertexArray,
 }
@@ -181,6 +623,23 @@ impl VertexArray {
         Ok(Self { id })
     }
 
+    // location of active vertex attribute
+    // gl reports attributes missing from the shader and ones optimized out as unused
+    // the same way, both are a Location error naming the attribute
+    pub fn attrib_location(
+        gl: &glow::Context,
+        program: &Program,
+        name: &str,
+    ) -> Result<u32, VertexArrayError> {
+        // built in inputs never have a location, caller error rather than shader mismatch
+        if name.starts_with("gl_") {
+            return Err(VertexArrayError::Reserved(name.to_string()));
+        }
+        unsafe { gl.get_attrib_location(program.id, name) }
+            .ok_or_else(|| VertexArrayError::Location(name.to_string()))
+    }
+
+    // point attribute at currently bound buffer and enable, returns attribute location
     pub fn set_attrib(
         gl: &glow::Context,
         program: &Program,
@@ -188,28 +647,58 @@ impl VertexArray {
         size: i32,
         stride: i32,
         offset: i32,
-    ) -> Result<(), VertexArrayError> {
-        let location;
+    ) -> Result<u32, VertexArrayError> {
+        let location = Self::attrib_location(gl, program, name)?;
+        let fsize = std::mem::size_of::<f32>() as i32;
         unsafe {
-            location = gl.get_attrib_location(program.id, name);
+            gl.vertex_attrib_pointer_f32(
+                location,
+                size,
+                glow::FLOAT,
+                false,
+                fsize * stride,
+                fsize * offset,
+            );
+            gl.enable_vertex_attrib_array(location);
         }
-        match location {
-            None => Err(VertexArrayError::Location),
-            Some(location) => unsafe {
-                let fsize = std::mem::size_of::<f32>() as i32;
-                gl.vertex_attrib_pointer_f32(
-                    location,
-                    size,
-                    glow::FLOAT,
-                    false,
-                    fsize * stride,
-                    fsize * offset,
-                );
-                gl.enable_vertex_attrib_array(location);
-                Ok(())
-            },
+        Ok(location)
+    }
+
+    // set attribute that custom shaders may leave out, logs a warning and returns None
+    // if attribute isn't active instead of failing
+    pub fn set_optional_attrib(
+        gl: &glow::Context,
+        program: &Program,
+        name: &str,
+        size: i32,
+        stride: i32,
+        offset: i32,
+    ) -> Result<Option<u32>, VertexArrayError> {
+        match Self::set_attrib(gl, program, name, size, stride, offset) {
+            Ok(location) => Ok(Some(location)),
+            Err(err @ VertexArrayError::Location(_)) => {
+                log::warn!("{}, skipping optional attribute", err);
+                Ok(None)
+            }
+            Err(err) => Err(err),
         }
     }
+
+    // set attribute that advances once per instance instead of once per vertex
+    pub fn set_instance_attrib(
+        gl: &glow::Context,
+        program: &Program,
+        name: &str,
+        size: i32,
+        stride: i32,
+        offset: i32,
+    ) -> Result<u32, VertexArrayError> {
+        let location = Self::set_attrib(gl, program, name, size, stride, offset)?;
+        unsafe {
+            gl.vertex_attrib_divisor(location, 1);
+        }
+        Ok(location)
+    }
 }
 
 impl Bind for VertexArray {
@@ -228,6 +717,29 @@ impl Drop for VertexArray {
     }
 }
 
+// uniform value set by name at runtime, e.g. from VisState::update_uniforms
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UniformValue {
+    Float(f32),
+    Vec3(Vec3),
+    Mat4(Mat4),
+}
+
+impl UniformValue {
+    // set value at location of already bound program
+    pub fn set(&self, gl: &glow::Context, location: &glow::UniformLocation) {
+        unsafe {
+            match self {
+                Self::Float(value) => gl.uniform_1_f32(Some(location), *value),
+                Self::Vec3(value) => gl.uniform_3_f32(Some(location), value.x, value.y, value.z),
+                Self::Mat4(value) => {
+                    gl.uniform_matrix_4_f32_slice(Some(location), false, &value.to_cols_array())
+                }
+            }
+        }
+    }
+}
+
 pub struct UniformMatrix {
     pub name: String,
     pub data: Mat4,
@@ -239,24 +751,553 @@ impl UniformMatrix {
         Self { name, data }
     }
 
-    pub fn apply(
-        &self,
-        gl: &glow::Context,
-        programs: &[&Program],
-    ) -> Result<(), UniformMatrixError> {
+    // bind and set uniform on each program
+    pub fn apply(&self, gl: &glow::Context, programs: &[&Program]) -> Result<(), UniformError> {
         for &program in programs {
             program.bind(gl);
-            unsafe {
-                let location = gl
-                    .get_uniform_location(program.id, &self.name)
-                    .ok_or(UniformMatrixError::Location)?;
-                gl.uniform_matrix_4_f32_slice(Some(&location), false, &self.data.to_cols_array());
-            }
+            self.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program, avoids rebinding when setting many uniforms
+    pub fn apply_bound(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        unsafe {
+            let location = gl
+                .get_uniform_location(program.id, &self.name)
+                .ok_or_else(|| UniformError::Location(self.name.clone()))?;
+            gl.uniform_matrix_4_f32_slice(Some(&location), false, &self.data.to_cols_array());
         }
         Ok(())
     }
 }
 
+pub struct UniformFloat {
+    pub name: String,
+    pub data: f32,
+}
+
+impl UniformFloat {
+    pub fn new(name: &str, data: f32) -> Self {
+        let name = name.to_string();
+        Self { name, data }
+    }
+
+    // bind and set uniform on each program
+    pub fn apply(&self, gl: &glow::Context, programs: &[&Program]) -> Result<(), UniformError> {
+        for &program in programs {
+            program.bind(gl);
+            self.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program, avoids rebinding when setting many uniforms
+    pub fn apply_bound(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        unsafe {
+            let location = gl
+                .get_uniform_location(program.id, &self.name)
+                .ok_or_else(|| UniformError::Location(self.name.clone()))?;
+            gl.uniform_1_f32(Some(&location), self.data);
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program if declared, for optional feature uniforms
+    // that custom shaders may leave out
+    pub fn apply_optional(&self, gl: &glow::Context, program: &Program) {
+        if let Some(location) = program.uniform_location(gl, &self.name) {
+            unsafe { gl.uniform_1_f32(Some(&location), self.data) }
+        }
+    }
+}
+
+pub struct UniformVec3 {
+    pub name: String,
+    pub data: Vec3,
+}
+
+impl UniformVec3 {
+    pub fn new(name: &str, data: Vec3) -> Self {
+        let name = name.to_string();
+        Self { name, data }
+    }
+
+    // bind and set uniform on each program
+    pub fn apply(&self, gl: &glow::Context, programs: &[&Program]) -> Result<(), UniformError> {
+        for &program in programs {
+            program.bind(gl);
+            self.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program, avoids rebinding when setting many uniforms
+    pub fn apply_bound(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        unsafe {
+            let location = gl
+                .get_uniform_location(program.id, &self.name)
+                .ok_or_else(|| UniformError::Location(self.name.clone()))?;
+            gl.uniform_3_f32(Some(&location), self.data.x, self.data.y, self.data.z);
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program if declared, for optional feature uniforms
+    // that custom shaders may leave out
+    pub fn apply_optional(&self, gl: &glow::Context, program: &Program) {
+        if let Some(location) = program.uniform_location(gl, &self.name) {
+            unsafe { gl.uniform_3_f32(Some(&location), self.data.x, self.data.y, self.data.z) }
+        }
+    }
+}
+
+// array of vec3 uniforms, set from element 0 of array named name
+pub struct UniformVec3Array {
+    pub name: String,
+    pub data: Vec<Vec3>,
+}
+
+impl UniformVec3Array {
+    pub fn new(name: &str, data: Vec<Vec3>) -> Self {
+        let name = name.to_string();
+        Self { name, data }
+    }
+
+    // bind and set uniform on each program
+    pub fn apply(&self, gl: &glow::Context, programs: &[&Program]) -> Result<(), UniformError> {
+        for &program in programs {
+            program.bind(gl);
+            self.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program, avoids rebinding when setting many uniforms
+    pub fn apply_bound(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        let flat: Vec<f32> = self.data.iter().flat_map(|v| v.to_array()).collect();
+        unsafe {
+            let location = gl
+                .get_uniform_location(program.id, &self.name)
+                .ok_or_else(|| UniformError::Location(self.name.clone()))?;
+            gl.uniform_3_f32_slice(Some(&location), &flat);
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program if declared, for optional feature uniforms
+    // that custom shaders may leave out
+    pub fn apply_optional(&self, gl: &glow::Context, program: &Program) {
+        let flat: Vec<f32> = self.data.iter().flat_map(|v| v.to_array()).collect();
+        if let Some(location) = program.uniform_location(gl, &self.name) {
+            unsafe { gl.uniform_3_f32_slice(Some(&location), &flat) }
+        }
+    }
+}
+
+pub struct UniformVec4 {
+    pub name: String,
+    pub data: Vec4,
+}
+
+impl UniformVec4 {
+    pub fn new(name: &str, data: Vec4) -> Self {
+        let name = name.to_string();
+        Self { name, data }
+    }
+
+    // bind and set uniform on each program
+    pub fn apply(&self, gl: &glow::Context, programs: &[&Program]) -> Result<(), UniformError> {
+        for &program in programs {
+            program.bind(gl);
+            self.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program, avoids rebinding when setting many uniforms
+    pub fn apply_bound(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        unsafe {
+            let location = gl
+                .get_uniform_location(program.id, &self.name)
+                .ok_or_else(|| UniformError::Location(self.name.clone()))?;
+            let [x, y, z, w] = self.data.to_array();
+            gl.uniform_4_f32(Some(&location), x, y, z, w);
+        }
+        Ok(())
+    }
+
+    // set uniform on already bound program if declared, for optional feature uniforms
+    // that custom shaders may leave out
+    pub fn apply_optional(&self, gl: &glow::Context, program: &Program) {
+        let [x, y, z, w] = self.data.to_array();
+        if let Some(location) = program.uniform_location(gl, &self.name) {
+            unsafe { gl.uniform_4_f32(Some(&location), x, y, z, w) }
+        }
+    }
+}
+
+// single triangle covering the whole viewport for post processing / screen effects
+// effects supply their own program, which must declare
+// `layout(location = 0) in vec2 position;` in clip space,
+// or pair their fragment shader with FULLSCREEN_VERT through FullscreenQuad::program
+pub struct FullscreenQuad {
+    pub buffer: Buffer,
+    pub vao: VertexArray,
+}
+
+// attribute location of fullscreen quad position
+pub const FULLSCREEN_POSITION_LOCATION: u32 = 0;
+
+// passthrough vertex shader for fullscreen quad, outputs `vec2 screenUv` from 0 to 1
+pub const FULLSCREEN_VERT: &str = include_str!("../shaders/fullscreen-vert.glsl");
+
+// oversized triangle, clipped to viewport, avoids diagonal seam of a two triangle quad
+const FULLSCREEN_TRIANGLE: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+
+impl FullscreenQuad {
+    pub fn new(gl: &glow::Context) -> Result<Self, FullscreenQuadError> {
+        let mut buffer = Buffer::new(gl, BufferUsage::Static)?;
+        buffer.set_data(gl, &FULLSCREEN_TRIANGLE);
+        let vao = VertexArray::new(gl)?;
+        vao.bind(gl);
+        buffer.bind(gl);
+        unsafe {
+            gl.vertex_attrib_pointer_f32(FULLSCREEN_POSITION_LOCATION, 2, glow::FLOAT, false, 0, 0);
+            gl.enable_vertex_attrib_array(FULLSCREEN_POSITION_LOCATION);
+        }
+        Ok(Self { buffer, vao })
+    }
+
+    // link screen pass fragment shader with passthrough vertex shader
+    pub fn program(
+        gl: &glow::Context,
+        version: &str,
+        fragment_source: &str,
+    ) -> Result<Program, ProgramError> {
+        Program::new_from_sources(gl, version, FULLSCREEN_VERT, fragment_source)
+    }
+
+    // draw with currently bound program
+    pub fn draw(&self, gl: &glow::Context) {
+        self.vao.bind(gl);
+        unsafe {
+            gl.draw_arrays(glow::TRIANGLES, 0, 3);
+        }
+    }
+}
+
+impl Drop for FullscreenQuad {
+    fn drop(&self, gl: &glow::Context) {
+        self.buffer.drop(gl);
+        self.vao.drop(gl);
+    }
+}
+
+// rgba8 2d texture with linear filtering, wraps horizontally for equirectangular images
+pub struct Texture2D {
+    pub id: glow::Texture,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Texture2D {
+    // upload rgba bytes with first row at top of image
+    // srgb stores color images for linear sampling, masks and data should be linear
+    pub fn new(
+        gl: &glow::Context,
+        width: usize,
+        height: usize,
+        data: &[u8],
+        srgb: bool,
+    ) -> Result<Self, TextureError> {
+        if data.len() != width * height * 4 {
+            return Err(TextureError::Length(data.len(), width * height * 4));
+        }
+        let internal_format = if srgb {
+            glow::SRGB8_ALPHA8
+        } else {
+            glow::RGBA8
+        };
+        let id;
+        unsafe {
+            id = gl.create_texture()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(id));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                Some(data),
+            );
+            // no mipmaps, longitude wrap seam would select the smallest level
+            let params = [
+                (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+                (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+                (glow::TEXTURE_WRAP_S, glow::REPEAT),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ];
+            for (param, value) in params {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, param, value as i32);
+            }
+        }
+        Ok(Self { id, width, height })
+    }
+
+    // bind to texture unit, index matches sampler uniform value
+    pub fn bind_unit(&self, gl: &glow::Context, unit: u32) {
+        unsafe {
+            gl.active_texture(glow::TEXTURE0 + unit);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.id));
+        }
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_texture(self.id);
+        }
+    }
+}
+
+// snapshot of bound objects and fixed function state, for diagnosing custom shaders / passes
+// object fields are raw gl names with 0 for nothing bound. webgl reports bindings
+// as objects rather than names, so they read as 0 on wasm
+#[cfg(feature = "debug_gl")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlStateSnapshot {
+    pub program: u32,
+    pub vertex_array: u32,
+    pub array_buffer: u32,
+    // element buffer is vao state, reported for bound vao
+    pub element_array_buffer: u32,
+    pub framebuffer: u32,
+    // texture unit index, 0 for TEXTURE0
+    pub active_texture: u32,
+    pub blend: bool,
+    pub depth_test: bool,
+    pub cull_face: bool,
+    // x, y, width, height in physical pixels
+    pub viewport: [i32; 4],
+}
+
+// query current gl state, each query is a synchronous round trip so avoid per frame use
+#[cfg(feature = "debug_gl")]
+pub fn dump_state(gl: &glow::Context) -> GlStateSnapshot {
+    unsafe {
+        let name = |parameter| gl.get_parameter_i32(parameter) as u32;
+        let mut viewport = [0; 4];
+        gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+        GlStateSnapshot {
+            program: name(glow::CURRENT_PROGRAM),
+            vertex_array: name(glow::VERTEX_ARRAY_BINDING),
+            array_buffer: name(glow::ARRAY_BUFFER_BINDING),
+            element_array_buffer: name(glow::ELEMENT_ARRAY_BUFFER_BINDING),
+            framebuffer: name(glow::FRAMEBUFFER_BINDING),
+            active_texture: name(glow::ACTIVE_TEXTURE) - glow::TEXTURE0,
+            blend: gl.is_enabled(glow::BLEND),
+            depth_test: gl.is_enabled(glow::DEPTH_TEST),
+            cull_face: gl.is_enabled(glow::CULL_FACE),
+            viewport,
+        }
+    }
+}
+
+#[cfg(feature = "debug_gl")]
+impl std::fmt::Display for GlStateSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |id: u32| match id {
+            0 => "none".to_string(),
+            id => id.to_string(),
+        };
+        let enabled = |on: bool| if on { "on" } else { "off" };
+        let [x, y, width, height] = self.viewport;
+        writeln!(f, "gl state:")?;
+        writeln!(f, "  program:              {}", bound(self.program))?;
+        writeln!(f, "  vertex array:         {}", bound(self.vertex_array))?;
+        writeln!(f, "  array buffer:         {}", bound(self.array_buffer))?;
+        writeln!(
+            f,
+            "  element array buffer: {}",
+            bound(self.element_array_buffer)
+        )?;
+        writeln!(f, "  framebuffer:          {}", bound(self.framebuffer))?;
+        writeln!(f, "  active texture:       TEXTURE{}", self.active_texture)?;
+        writeln!(f, "  blend:                {}", enabled(self.blend))?;
+        writeln!(f, "  depth test:           {}", enabled(self.depth_test))?;
+        writeln!(f, "  cull face:            {}", enabled(self.cull_face))?;
+        write!(f, "  viewport:             {x}, {y} {width}x{height}")
+    }
+}
+
+// color attachment format of offscreen framebuffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorFormat {
+    Rgba8,
+    Rgb8,
+    // half float for hdr / accumulation, needs EXT_color_buffer_float on webgl2
+    Rgba16F,
+}
+
+impl ColorFormat {
+    pub fn to_gl(self) -> u32 {
+        match self {
+            ColorFormat::Rgba8 => glow::RGBA8,
+            ColorFormat::Rgb8 => glow::RGB8,
+            ColorFormat::Rgba16F => glow::RGBA16F,
+        }
+    }
+
+    // read back through read_f32 instead of read_u8
+    pub fn is_float(self) -> bool {
+        self == ColorFormat::Rgba16F
+    }
+
+    // channels returned from read
+    pub fn channels(self) -> usize {
+        match self {
+            ColorFormat::Rgb8 => 3,
+            _ => 4,
+        }
+    }
+
+    // embedded contexts (webgl2 / gles) can't render to float formats without extension
+    fn supported(self, gl: &glow::Context) -> bool {
+        if !self.is_float() || !gl.version().is_embedded {
+            return true;
+        }
+        gl.supported_extensions()
+            .iter()
+            .any(|e| e.ends_with("EXT_color_buffer_float"))
+    }
+}
+
+// offscreen color + depth render target, for headless rendering and screenshots
+pub struct Framebuffer {
+    pub id: glow::Framebuffer,
+    pub color: glow::Renderbuffer,
+    pub depth: glow::Renderbuffer,
+    pub format: ColorFormat,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Framebuffer {
+    // create and bind framebuffer, format is checked against context support and completeness
+    pub fn new(
+        gl: &glow::Context,
+        width: i32,
+        height: i32,
+        format: ColorFormat,
+    ) -> Result<Self, FramebufferError> {
+        if !format.supported(gl) {
+            return Err(FramebufferError::Unsupported(format));
+        }
+        let framebuffer;
+        unsafe {
+            let id = gl.create_framebuffer()?;
+            let color = gl.create_renderbuffer()?;
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(color));
+            gl.renderbuffer_storage(glow::RENDERBUFFER, format.to_gl(), width, height);
+            let depth = gl.create_renderbuffer()?;
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth));
+            gl.renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH_COMPONENT24, width, height);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(id));
+            gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::RENDERBUFFER,
+                Some(color),
+            );
+            gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::DEPTH_ATTACHMENT,
+                glow::RENDERBUFFER,
+                Some(depth),
+            );
+            framebuffer = Self {
+                id,
+                color,
+                depth,
+                format,
+                width,
+                height,
+            };
+            if gl.check_framebuffer_status(glow::FRAMEBUFFER) != glow::FRAMEBUFFER_COMPLETE {
+                framebuffer.drop(gl);
+                return Err(FramebufferError::Incomplete(format));
+            }
+            gl.viewport(0, 0, width, height);
+        }
+        Ok(framebuffer)
+    }
+
+    // bind and set viewport to cover framebuffer
+    pub fn bind(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.id));
+            gl.viewport(0, 0, self.width, self.height);
+        }
+    }
+
+    // rgba or rgb bytes for 8 bit formats, rows from bottom of image
+    pub fn read_u8(&self, gl: &glow::Context) -> Result<Vec<u8>, FramebufferError> {
+        if self.format.is_float() {
+            return Err(FramebufferError::ReadType(self.format));
+        }
+        // rgba / unsigned byte is the read format every context supports
+        let mut rgba = vec![0u8; (self.width * self.height * 4) as usize];
+        self.read(
+            gl,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(&mut rgba),
+        );
+        match self.format.channels() {
+            3 => Ok(rgba
+                .chunks_exact(4)
+                .flat_map(|p| [p[0], p[1], p[2]])
+                .collect()),
+            _ => Ok(rgba),
+        }
+    }
+
+    // rgba floats for float formats, rows from bottom of image
+    pub fn read_f32(&self, gl: &glow::Context) -> Result<Vec<f32>, FramebufferError> {
+        if !self.format.is_float() {
+            return Err(FramebufferError::ReadType(self.format));
+        }
+        let mut rgba = vec![0.0f32; (self.width * self.height * 4) as usize];
+        unsafe {
+            let (_, bytes, _) = rgba.align_to_mut::<u8>();
+            self.read(gl, glow::FLOAT, glow::PixelPackData::Slice(bytes));
+        }
+        Ok(rgba)
+    }
+
+    fn read(&self, gl: &glow::Context, ty: u32, data: glow::PixelPackData) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.id));
+            gl.read_pixels(0, 0, self.width, self.height, glow::RGBA, ty, data);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.delete_framebuffer(self.id);
+            gl.delete_renderbuffer(self.color);
+            gl.delete_renderbuffer(self.depth);
+        }
+    }
+}
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -305,8 +1346,18 @@ impl From<String> for BufferError {
 pub enum VertexArrayError {
     #[error("{0}")]
     String(String),
-    #[error("Attrib location not found")]
-    Location,
+    #[error("Attrib location not found: {0} is not an active attribute, missing from shader or unused and optimized out")]
+    Location(String),
+    #[error("Attrib name reserved: {0} is a built in input without a location")]
+    Reserved(String),
+}
+
+#[derive(Error, Debug)]
+pub enum FullscreenQuadError {
+    #[error("{0}")]
+    Buffer(#[from] BufferError),
+    #[error("{0}")]
+    VertexArray(#[from] VertexArrayError),
 }
 
 impl From<String> for VertexArrayError {
@@ -316,7 +1367,85 @@ impl From<String> for VertexArrayError {
 }
 
 #[derive(Error, Debug)]
-pub enum UniformMatrixError {
-    #[error("Uniform location not found")]
-    Location,
+pub enum TextureError {
+    #[error("{0}")]
+    String(String),
+    #[error("Texture data length {0} doesn't match expected length {1}")]
+    Length(usize, usize),
+}
+
+impl From<String> for TextureError {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FramebufferError {
+    #[error("{0}")]
+    String(String),
+    #[error("Color format {0:?} not supported as render target by this context")]
+    Unsupported(ColorFormat),
+    #[error("Framebuffer incomplete with color format {0:?}")]
+    Incomplete(ColorFormat),
+    #[error("Color format {0:?} can't be read with this pixel type, use read_u8 for 8 bit and read_f32 for float formats")]
+    ReadType(ColorFormat),
+}
+
+impl From<String> for FramebufferError {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+// shared by all uniform wrappers
+#[derive(Error, Debug)]
+pub enum UniformError {
+    #[error("Uniform location not found: {0}")]
+    Location(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertex_layout() {
+        let layout = VertexLayout::new(&[("position", 3), ("color", 4), ("size", 1)]);
+        assert_eq!(layout.stride, 8);
+        let offsets: Vec<i32> = layout.attribs.iter().map(|a| a.offset).collect();
+        assert_eq!(offsets, vec![0, 3, 7]);
+        assert!(layout.contains("color") && !layout.contains("time"));
+
+        assert_eq!(layout.vertex_count(16), Some(2));
+        assert_eq!(layout.vertex_count(15), None);
+        assert_eq!(VertexLayout::new(&[]).vertex_count(0), None);
+    }
+
+    #[test]
+    fn test_buffer_usage_to_gl() {
+        assert_eq!(BufferUsage::Static.to_gl(), glow::STATIC_DRAW);
+        assert_eq!(BufferUsage::Dynamic.to_gl(), glow::DYNAMIC_DRAW);
+        assert_eq!(BufferUsage::Stream.to_gl(), glow::STREAM_DRAW);
+    }
+
+    #[test]
+    fn test_strip_indices() {
+        let indices = strip_indices(&[3, 2, 4]);
+        let r = RESTART_INDEX;
+        let expected = vec![0, 1, 2, r, 3, 4, r, 5, 6, 7, 8];
+        assert_eq!(indices, expected);
+    }
+
+    #[test]
+    fn test_strip_indices_empty() {
+        assert!(strip_indices(&[]).is_empty());
+        assert_eq!(strip_indices(&[2]), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_strip_line_indices() {
+        let indices = strip_line_indices(&[3, 1, 2]);
+        assert_eq!(indices, vec![0, 1, 1, 2, 4, 5]);
+    }
 }
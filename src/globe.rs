@@ -1,56 +1,789 @@
-use crate::gl_wrap::{Bind, Buffer, Drop, Program, VertexArray};
-use crate::icosphere::get_icosphere;
+use crate::gl_wrap::{
+    blend_over, Bind, Buffer, BufferUsage, Drop, ElementBuffer, Program, Texture2D, UniformError,
+    UniformFloat, UniformVec3, UniformVec3Array, VertexArray,
+};
+use crate::points::{logical_to_physical, DEFAULT_POINT_SIZE};
+use crate::render_pass::PolygonOffset;
+use crate::{
+    geo::{lat_lon_to_xyz, xyz_to_lat_lon},
+    icosphere::get_icosphere,
+};
+use glam::Vec3;
 use glow::HasContext;
+use std::collections::{HashMap, HashSet};
+
+// subdivisions of icosphere used for globe mesh
+pub const DEFAULT_SUBDIVISIONS: usize = 4;
+// limit subdivisions, vertex count grows 4x each level
+pub const MAX_SUBDIVISIONS: usize = 7;
+
+// ocean highlight defaults, only visible once a specular mask is set
+pub const DEFAULT_SPECULAR_INTENSITY: f32 = 0.6;
+pub const DEFAULT_SHININESS: f32 = 32.0;
+
+// additional directional lights beyond main and fill, matches MAX_LIGHTS in globe shader
+pub const MAX_LIGHTS: usize = 4;
+
+// lat / lon grid cells used for flat map mesh, 2 degree cells
+const FLAT_COLUMNS: usize = 180;
+const FLAT_ROWS: usize = 90;
+// degrees to keep flat mesh inside map edges, so vertex shader atan doesn't wrap
+// seam vertices to the other side or hit undefined longitude at poles.
+// large enough that f32 positions near poles don't round onto the pole
+const FLAT_EDGE: f32 = 0.1;
+
+// texture unit for specular mask, sampler uniform is left at default unit 0
+const SPECULAR_MASK_UNIT: u32 = 0;
+
+// how globe mesh is drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlobeRenderMode {
+    // filled triangles
+    Filled,
+    // triangle edges as lines, works on webgl2 without polygon mode
+    Wireframe,
+    // mesh vertices as dots
+    Points,
+}
+
+// which globe faces are skipped, icosphere triangles are wound so front faces point outward
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaceCulling {
+    // draw both sides
+    None,
+    // skip far side of globe, only visible through translucent / clipped globes
+    Back,
+    // draw only inside of far side
+    Front,
+}
+
+// second base color for a two tone globe without a texture, multiplied with vertex
+// colors and lit like globe color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlobeTone {
+    // globe color everywhere
+    Solid,
+    // color poleward of lat in degrees, e.g. ice caps
+    Polar { lat: f32, color: [f32; 3] },
+    // color where specular mask red channel is set, e.g. ocean. needs a specular mask
+    Masked { color: [f32; 3] },
+}
 
 // contains gl resources / logic for drawing globe
 pub struct Globe {
     pub data: Vec<f32>,
     pub program: Program,
     pub buffer: Buffer,
+    pub color_buffer: Buffer,
     pub vao: VertexArray,
+    pub subdivisions: usize,
+    pub lighting: Lighting,
+    pub render_mode: GlobeRenderMode,
+    // line indices for each triangle edge, used in wireframe mode
+    pub edge_buffer: ElementBuffer,
+    // one index per distinct vertex position, used in points mode so shared vertices of
+    // the unindexed mesh are drawn once instead of stacking when translucent
+    pub vertex_buffer: ElementBuffer,
+    // dot size in logical pixels for points mode, uniform holds physical size
+    pub point_size: f32,
+    pub point_size_uniform: UniformFloat,
+    // land / ocean mask, specular highlight disabled when None
+    pub specular_mask: Option<Texture2D>,
+    pub specular_intensity: f32,
+    // base color multiplied with vertex colors, white keeps vertex colors unchanged
+    pub color: UniformVec3,
+    pub face_culling: FaceCulling,
+    // 1 to light back faces with flipped normal, 0 lights them as the outside surface
+    pub double_sided: UniformFloat,
+    // alpha multiplier, globe is blended over points drawn before it when below 1
+    pub opacity: UniformFloat,
+    // 1 to fade alpha at silhouette, blended even when opaque
+    pub edge_smoothing: UniformFloat,
+    // second base color replacing globe color by latitude or mask
+    pub tone: GlobeTone,
+    tone_mode: UniformFloat,
+    tone_lat: UniformFloat,
+    tone_color: UniformVec3,
+    // depth offset for filled triangles, pushing globe back lets lines / points on the
+    // surface draw over it without z-fighting
+    pub polygon_offset: Option<PolygonOffset>,
+    // translucent globe writes depth of nearest surface before blending, see set_depth_prepass
+    pub depth_prepass: bool,
+    // mesh is lat / lon grid drawn as flat map by vertex shader, icosphere cached until globe mode
+    pub flat: bool,
+    // meshes from previous subdivision levels, reused when switching back
+    mesh_cache: HashMap<usize, Vec<f32>>,
 }
 
 impl Globe {
     pub fn new(gl: &glow::Context, shader_version: &str) -> Result<Self, GlobeError> {
-        // compile program from strings
-        let program = Program::new_from_sources(
+        Self::new_with_shaders(
             gl,
             shader_version,
             include_str!("../shaders/globe-vert.glsl"),
             include_str!("../shaders/globe-frag.glsl"),
-        )?;
-        // init buffer and set data
-        let data = get_icosphere(4);
-        let mut buffer = Buffer::new(gl, glow::STATIC_DRAW)?;
+        )
+    }
+
+    // create globe with custom shaders, must declare same attributes / uniforms as built in
+    pub fn new_with_shaders(
+        gl: &glow::Context,
+        shader_version: &str,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<Self, GlobeError> {
+        // compile program from strings
+        let program =
+            Program::new_from_sources(gl, shader_version, vertex_source, fragment_source)?;
+        // init buffers and set data
+        let subdivisions = DEFAULT_SUBDIVISIONS;
+        let data = get_icosphere(subdivisions);
+        let mut buffer = Buffer::new(gl, BufferUsage::Static)?;
         buffer.set_data(gl, &data);
+        let mut color_buffer = Buffer::new(gl, BufferUsage::Static)?;
+        color_buffer.set_data(gl, &position_colors(&data));
         // init vao and setup attributes
         let vao = VertexArray::new(gl)?;
         program.bind(gl);
-        buffer.bind(gl);
         vao.bind(gl);
+        buffer.bind(gl);
         VertexArray::set_attrib(gl, &program, "position", 3, 3, 0)?;
+        color_buffer.bind(gl);
+        // custom shaders may color globe without vertex colors
+        VertexArray::set_optional_attrib(gl, &program, "color", 4, 4, 0)?;
+        // element buffer binding is stored in bound vao
+        let mut edge_buffer = ElementBuffer::new(gl, BufferUsage::Static)?;
+        edge_buffer.set_data(gl, &edge_indices(data.len() / 3));
+        let mut vertex_buffer = ElementBuffer::new(gl, BufferUsage::Static)?;
+        vertex_buffer.set_data(gl, &vertex_indices(&data));
+        let point_size = DEFAULT_POINT_SIZE;
+        let point_size_uniform =
+            UniformFloat::new("pointSize", logical_to_physical(point_size, 1.0));
         Ok(Self {
             data,
             program,
             buffer,
+            color_buffer,
             vao,
+            subdivisions,
+            lighting: Lighting::default(),
+            render_mode: GlobeRenderMode::Filled,
+            edge_buffer,
+            vertex_buffer,
+            point_size,
+            point_size_uniform,
+            specular_mask: None,
+            specular_intensity: DEFAULT_SPECULAR_INTENSITY,
+            color: UniformVec3::new("globeColor", Vec3::ONE),
+            face_culling: FaceCulling::None,
+            double_sided: UniformFloat::new("doubleSided", 0.0),
+            opacity: UniformFloat::new("opacity", 1.0),
+            edge_smoothing: UniformFloat::new("edgeSmoothing", 0.0),
+            tone: GlobeTone::Solid,
+            tone_mode: UniformFloat::new("toneMode", 0.0),
+            tone_lat: UniformFloat::new("toneLat", 0.0),
+            tone_color: UniformVec3::new("toneColor", Vec3::ONE),
+            polygon_offset: None,
+            depth_prepass: false,
+            flat: false,
+            mesh_cache: HashMap::new(),
         })
     }
 
+    // set all globe uniforms, call once after program creation
+    pub fn apply_uniforms(&self, gl: &glow::Context) -> Result<(), GlobeError> {
+        self.lighting.apply(gl, &self.program)?;
+        self.point_size_uniform.apply_optional(gl, &self.program);
+        self.color.apply_optional(gl, &self.program);
+        self.double_sided.apply_optional(gl, &self.program);
+        self.opacity.apply_optional(gl, &self.program);
+        self.edge_smoothing.apply_optional(gl, &self.program);
+        self.apply_tone(gl);
+        Ok(())
+    }
+
+    // set linear rgb base color, multiplied with vertex colors before lighting
+    // default vertex colors are a position gradient, set white vertex colors for a solid globe
+    pub fn set_color(&mut self, gl: &glow::Context, color: [f32; 3]) -> Result<(), GlobeError> {
+        self.color.data = Vec3::from(color);
+        self.program.bind(gl);
+        self.color.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // set second base color and where it replaces globe color
+    pub fn set_tone(&mut self, gl: &glow::Context, tone: GlobeTone) -> Result<(), GlobeError> {
+        self.tone = tone;
+        (self.tone_mode.data, self.tone_lat.data) = match tone {
+            GlobeTone::Solid => (0.0, 0.0),
+            GlobeTone::Polar { lat, color } => {
+                self.tone_color.data = Vec3::from(color);
+                (1.0, lat.clamp(-90.0, 90.0).to_radians().sin().abs())
+            }
+            GlobeTone::Masked { color } => {
+                self.tone_color.data = Vec3::from(color);
+                (2.0, 0.0)
+            }
+        };
+        self.program.bind(gl);
+        self.apply_tone(gl);
+        Ok(())
+    }
+
+    fn apply_tone(&self, gl: &glow::Context) {
+        self.tone_mode.apply_optional(gl, &self.program);
+        self.tone_lat.apply_optional(gl, &self.program);
+        self.tone_color.apply_optional(gl, &self.program);
+    }
+
+    // set which faces are skipped, culling is only enabled while drawing globe
+    pub fn set_face_culling(&mut self, face_culling: FaceCulling) {
+        self.face_culling = face_culling;
+    }
+
+    // light inside of globe as its own surface, for back faces seen through
+    // translucent or clipped globes
+    pub fn set_double_sided(
+        &mut self,
+        gl: &glow::Context,
+        double_sided: bool,
+    ) -> Result<(), GlobeError> {
+        self.double_sided.data = if double_sided { 1.0 } else { 0.0 };
+        self.program.bind(gl);
+        self.double_sided.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // set globe alpha from 0 (invisible) to 1 (opaque). translucent globes are drawn with
+    // blending and without depth writes, back faces then front faces so the far side shows
+    // through. anything meant to show through must be drawn before the globe, and overlays
+    // drawn after it aren't hidden by it. only filled mode with no face culling gets the
+    // two pass ordering, other modes blend in mesh order
+    pub fn set_opacity(&mut self, gl: &glow::Context, opacity: f32) -> Result<(), GlobeError> {
+        self.opacity.data = opacity.clamp(0.0, 1.0);
+        self.program.bind(gl);
+        self.opacity.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    pub fn is_translucent(&self) -> bool {
+        self.opacity.data < 1.0
+    }
+
+    // anti alias globe outline by fading alpha over about a pixel at the silhouette,
+    // independent of msaa. rim is blended over whatever is drawn before the globe
+    pub fn set_edge_smoothing(
+        &mut self,
+        gl: &glow::Context,
+        edge_smoothing: bool,
+    ) -> Result<(), GlobeError> {
+        self.edge_smoothing.data = if edge_smoothing { 1.0 } else { 0.0 };
+        self.program.bind(gl);
+        self.edge_smoothing.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // offset depth of filled globe, e.g. PolygonOffset::AWAY so surface overlays win
+    // depth tests against it. None draws at true depth
+    pub fn set_polygon_offset(&mut self, offset: Option<PolygonOffset>) {
+        self.polygon_offset = offset;
+    }
+
+    // draw translucent globe in two passes, depth only then color with depth test and no
+    // depth write, so only the nearest surface blends over what was drawn before it.
+    // the far side no longer shows through, and since globe depth is written, anything
+    // drawn after the globe behind its near surface is hidden. VisGl draws points before
+    // a translucent globe so they aren't, see VisBuilder::with_depth_prepass
+    pub fn set_depth_prepass(&mut self, depth_prepass: bool) {
+        self.depth_prepass = depth_prepass;
+    }
+
+    pub fn has_edge_smoothing(&self) -> bool {
+        self.edge_smoothing.data > 0.0
+    }
+
+    // set ambient light color, added to all surfaces regardless of direction
+    pub fn set_ambient(&mut self, gl: &glow::Context, color: Vec3) -> Result<(), GlobeError> {
+        self.lighting.ambient.data = color;
+        self.lighting.apply(gl, &self.program)?;
+        Ok(())
+    }
+
+    // set main directional light, direction points from globe toward light in world space
+    pub fn set_light(
+        &mut self,
+        gl: &glow::Context,
+        direction: Vec3,
+        color: Vec3,
+    ) -> Result<(), GlobeError> {
+        self.lighting.light_dir.data = direction.normalize_or_zero();
+        self.lighting.light_color.data = color;
+        self.lighting.apply(gl, &self.program)?;
+        Ok(())
+    }
+
+    // set second directional light, usually dim and opposite main light
+    // to keep detail on unlit side, black color disables
+    pub fn set_fill_light(
+        &mut self,
+        gl: &glow::Context,
+        direction: Vec3,
+        color: Vec3,
+    ) -> Result<(), GlobeError> {
+        self.lighting.fill_dir.data = direction.normalize_or_zero();
+        self.lighting.fill_color.data = color;
+        self.lighting.apply(gl, &self.program)?;
+        Ok(())
+    }
+
+    // add directional light on top of main and fill lights, for presentation lighting.
+    // at most MAX_LIGHTS can be added, contributions are summed then clamped with the rest
+    pub fn add_light(
+        &mut self,
+        gl: &glow::Context,
+        light: DirectionalLight,
+    ) -> Result<(), GlobeError> {
+        if self.lighting.lights.len() >= MAX_LIGHTS {
+            return Err(GlobeError::MaxLights);
+        }
+        self.lighting.lights.push(light);
+        self.lighting.apply(gl, &self.program)?;
+        Ok(())
+    }
+
+    // remove lights added with add_light, main and fill lights are unchanged
+    pub fn clear_lights(&mut self, gl: &glow::Context) -> Result<(), GlobeError> {
+        if self.lighting.lights.is_empty() {
+            return Ok(());
+        }
+        self.lighting.lights.clear();
+        self.program.bind(gl);
+        self.lighting.apply_lights(gl, &self.program)?;
+        Ok(())
+    }
+
+    // set equirectangular land / ocean mask, red channel scales specular highlight
+    // from main light, e.g. 1 over oceans and 0 over land, replaces any previous mask
+    pub fn set_specular_mask(
+        &mut self,
+        gl: &glow::Context,
+        mask: Texture2D,
+    ) -> Result<(), GlobeError> {
+        if let Some(prev) = self.specular_mask.replace(mask) {
+            prev.drop(gl);
+        }
+        self.apply_specular(gl)
+    }
+
+    // remove specular mask, disabling highlight
+    pub fn clear_specular_mask(&mut self, gl: &glow::Context) -> Result<(), GlobeError> {
+        if let Some(prev) = self.specular_mask.take() {
+            prev.drop(gl);
+        }
+        self.apply_specular(gl)
+    }
+
+    // set highlight strength and blinn phong exponent, higher shininess is a tighter glint
+    pub fn set_specular(
+        &mut self,
+        gl: &glow::Context,
+        intensity: f32,
+        shininess: f32,
+    ) -> Result<(), GlobeError> {
+        self.specular_intensity = intensity.max(0.0);
+        self.lighting.shininess.data = shininess.max(1.0);
+        self.apply_specular(gl)
+    }
+
+    // upload zero intensity without a mask so unbound sampler never contributes
+    fn apply_specular(&mut self, gl: &glow::Context) -> Result<(), GlobeError> {
+        self.lighting.specular_intensity.data = match self.specular_mask {
+            Some(_) => self.specular_intensity,
+            None => 0.0,
+        };
+        self.lighting.apply(gl, &self.program)?;
+        Ok(())
+    }
+
+    // regenerate mesh at new subdivision level and upload to existing buffers
+    // buffer ids are reused so vao attribute pointers remain valid,
+    // vertex colors are reset to default since vertex count changes.
+    // flat map mesh doesn't change, level is used when switching back to globe
+    pub fn set_subdivisions(
+        &mut self,
+        gl: &glow::Context,
+        subdivisions: usize,
+    ) -> Result<(), GlobeError> {
+        if subdivisions > MAX_SUBDIVISIONS {
+            return Err(GlobeError::Subdivisions(subdivisions));
+        }
+        if subdivisions == self.subdivisions {
+            return Ok(());
+        }
+        if self.flat {
+            self.subdivisions = subdivisions;
+            return Ok(());
+        }
+        let data = self.icosphere(subdivisions);
+        let prev = self.upload_mesh(gl, data);
+        self.mesh_cache.insert(self.subdivisions, prev);
+        self.subdivisions = subdivisions;
+        Ok(())
+    }
+
+    // swap icosphere for lat / lon grid that the vertex shader lays out as a flat map
+    // when flatMap uniform is set, vertex colors are reset to default
+    pub fn set_flat(&mut self, gl: &glow::Context, flat: bool) {
+        if flat == self.flat {
+            return;
+        }
+        if flat {
+            let prev = self.upload_mesh(gl, flat_mesh());
+            self.mesh_cache.insert(self.subdivisions, prev);
+        } else {
+            let data = self.icosphere(self.subdivisions);
+            self.upload_mesh(gl, data);
+        }
+        self.flat = flat;
+    }
+
+    // cached icosphere at subdivision level, generated if not cached
+    fn icosphere(&mut self, subdivisions: usize) -> Vec<f32> {
+        self.mesh_cache
+            .remove(&subdivisions)
+            .unwrap_or_else(|| get_icosphere(subdivisions))
+    }
+
+    // upload mesh with default colors and edges, returning previous mesh
+    fn upload_mesh(&mut self, gl: &glow::Context, data: Vec<f32>) -> Vec<f32> {
+        self.buffer.set_data(gl, &data);
+        self.color_buffer.set_data(gl, &position_colors(&data));
+        self.vao.bind(gl);
+        self.edge_buffer.set_data(gl, &edge_indices(data.len() / 3));
+        self.vertex_buffer.set_data(gl, &vertex_indices(&data));
+        std::mem::replace(&mut self.data, data)
+    }
+
+    // number of vertices in globe mesh
+    pub fn vertex_count(&self) -> usize {
+        self.data.len() / 3
+    }
+
+    // set rgba color for each vertex, mesh isn't indexed so
+    // colors apply to each triangle corner in draw order
+    pub fn set_vertex_colors(
+        &mut self,
+        gl: &glow::Context,
+        colors: &[f32],
+    ) -> Result<(), GlobeError> {
+        if colors.len() != self.vertex_count() * 4 {
+            return Err(GlobeError::ColorLength(
+                colors.len(),
+                self.vertex_count() * 4,
+            ));
+        }
+        self.color_buffer.set_data(gl, colors);
+        Ok(())
+    }
+
+    // build vertex color buffer from function of vertex lat / lon
+    pub fn colors_from_lat_lon(&self, color_fn: impl Fn(f32, f32) -> [f32; 4]) -> Vec<f32> {
+        self.data
+            .chunks_exact(3)
+            .flat_map(|p| {
+                let (lat, lon) = xyz_to_lat_lon([p[0], p[1], p[2]]);
+                color_fn(lat, lon)
+            })
+            .collect()
+    }
+
+    // upload mesh, styling and lighting of globe from a lost context. vertex colors and
+    // specular mask were only kept in lost gl resources and must be set again
+    pub fn restore_from(
+        &mut self,
+        gl: &glow::Context,
+        lost: &Globe,
+        dpi: f64,
+    ) -> Result<(), GlobeError> {
+        self.set_subdivisions(gl, lost.subdivisions)?;
+        self.set_flat(gl, lost.flat);
+        self.set_render_mode(lost.render_mode);
+        self.set_point_size(gl, lost.point_size, dpi)?;
+        self.set_color(gl, lost.color.data.to_array())?;
+        self.set_tone(gl, lost.tone)?;
+        self.set_face_culling(lost.face_culling);
+        self.set_double_sided(gl, lost.double_sided.data > 0.0)?;
+        self.set_opacity(gl, lost.opacity.data)?;
+        self.set_edge_smoothing(gl, lost.has_edge_smoothing())?;
+        self.set_polygon_offset(lost.polygon_offset);
+        self.set_depth_prepass(lost.depth_prepass);
+        let lighting = &lost.lighting;
+        self.set_ambient(gl, lighting.ambient.data)?;
+        self.set_light(gl, lighting.light_dir.data, lighting.light_color.data)?;
+        self.set_fill_light(gl, lighting.fill_dir.data, lighting.fill_color.data)?;
+        self.clear_lights(gl)?;
+        for light in &lighting.lights {
+            self.add_light(gl, *light)?;
+        }
+        self.set_specular(gl, lost.specular_intensity, lighting.shininess.data)?;
+        Ok(())
+    }
+
+    pub fn set_render_mode(&mut self, render_mode: GlobeRenderMode) {
+        self.render_mode = render_mode;
+    }
+
+    // set dot size for points render mode in logical pixels, uploaded as physical pixels
+    pub fn set_point_size(
+        &mut self,
+        gl: &glow::Context,
+        size: f32,
+        dpi: f64,
+    ) -> Result<(), GlobeError> {
+        self.point_size = size;
+        self.point_size_uniform.data = logical_to_physical(size, dpi);
+        self.program.bind(gl);
+        self.point_size_uniform.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
     pub fn draw(&self, gl: &glow::Context) {
         self.program.bind(gl);
         self.buffer.bind(gl);
         self.vao.bind(gl);
+        if let Some(mask) = &self.specular_mask {
+            mask.bind_unit(gl, SPECULAR_MASK_UNIT);
+        }
+        let cull = match self.face_culling {
+            FaceCulling::None => None,
+            FaceCulling::Back => Some(glow::BACK),
+            FaceCulling::Front => Some(glow::FRONT),
+        };
+        if !self.is_translucent() {
+            // opaque globe still writes depth, only rim fragments are partly transparent
+            if self.has_edge_smoothing() {
+                unsafe {
+                    gl.enable(glow::BLEND);
+                    blend_over(gl);
+                }
+            }
+            self.draw_culled(gl, cull);
+            if self.has_edge_smoothing() {
+                unsafe {
+                    gl.disable(glow::BLEND);
+                }
+            }
+            return;
+        }
+        if self.depth_prepass {
+            self.draw_prepassed(gl, cull);
+            return;
+        }
+        // blend over existing color without writing depth so globe faces don't hide
+        // each other, filled globe draws far side first so near side blends over it
+        unsafe {
+            gl.enable(glow::BLEND);
+            blend_over(gl);
+            gl.depth_mask(false);
+        }
+        if self.render_mode == GlobeRenderMode::Filled && cull.is_none() {
+            self.draw_culled(gl, Some(glow::FRONT));
+            self.draw_culled(gl, Some(glow::BACK));
+        } else {
+            self.draw_culled(gl, cull);
+        }
+        unsafe {
+            gl.depth_mask(true);
+            gl.disable(glow::BLEND);
+        }
+    }
+
+    // translucent draw with depth prepass, nearest fragments pass equal depth test
+    fn draw_prepassed(&self, gl: &glow::Context, cull: Option<u32>) {
         unsafe {
-            gl.draw_arrays(glow::TRIANGLES, 0, (self.buffer.len / 3) as i32);
+            gl.color_mask(false, false, false, false);
+        }
+        self.draw_culled(gl, cull);
+        unsafe {
+            gl.color_mask(true, true, true, true);
+            gl.depth_func(glow::LEQUAL);
+            gl.depth_mask(false);
+            gl.enable(glow::BLEND);
+            blend_over(gl);
+        }
+        self.draw_culled(gl, cull);
+        unsafe {
+            gl.disable(glow::BLEND);
+            gl.depth_mask(true);
+            gl.depth_func(glow::LESS);
+        }
+    }
+
+    // draw mesh in render mode, culling only enabled for this draw
+    fn draw_culled(&self, gl: &glow::Context, cull: Option<u32>) {
+        let count = (self.buffer.len / 3) as i32;
+        if let Some(face) = cull {
+            unsafe {
+                gl.enable(glow::CULL_FACE);
+                gl.cull_face(face);
+            }
+        }
+        match self.render_mode {
+            GlobeRenderMode::Filled => {
+                if let Some(offset) = &self.polygon_offset {
+                    offset.enable(gl);
+                }
+                unsafe {
+                    gl.draw_arrays(glow::TRIANGLES, 0, count);
+                }
+                if self.polygon_offset.is_some() {
+                    PolygonOffset::disable(gl);
+                }
+            }
+            GlobeRenderMode::Wireframe => self.edge_buffer.draw(gl, glow::LINES),
+            GlobeRenderMode::Points => self.vertex_buffer.draw(gl, glow::POINTS),
+        }
+        if cull.is_some() {
+            unsafe {
+                gl.disable(glow::CULL_FACE);
+            }
         }
     }
 }
 
+// line index pairs for each edge of unindexed triangles
+fn edge_indices(vertex_count: usize) -> Vec<u32> {
+    (0..vertex_count as u32 / 3)
+        .flat_map(|t| {
+            let i = 3 * t;
+            [i, i + 1, i + 1, i + 2, i + 2, i]
+        })
+        .collect()
+}
+
+// index of first vertex at each distinct position in unindexed mesh data
+fn vertex_indices(data: &[f32]) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    data.chunks_exact(3)
+        .enumerate()
+        .filter(|(_, p)| seen.insert([p[0].to_bits(), p[1].to_bits(), p[2].to_bits()]))
+        .map(|(i, _)| i as u32)
+        .collect()
+}
+
+// unit sphere triangles on lat / lon grid, two per cell wound counter clockwise
+// seen from outside the sphere and in flat map lon / lat plane
+fn flat_mesh() -> Vec<f32> {
+    let max_lat = 90.0 - FLAT_EDGE;
+    let max_lon = 180.0 - FLAT_EDGE;
+    let lat = |row: usize| -max_lat + 2.0 * max_lat * row as f32 / FLAT_ROWS as f32;
+    let lon = |col: usize| -max_lon + 2.0 * max_lon * col as f32 / FLAT_COLUMNS as f32;
+    let mut data = Vec::with_capacity(FLAT_ROWS * FLAT_COLUMNS * 18);
+    for row in 0..FLAT_ROWS {
+        for col in 0..FLAT_COLUMNS {
+            let corner = |r: usize, c: usize| lat_lon_to_xyz(lat(r), lon(c), 1.0);
+            let (a, b) = (corner(row, col), corner(row, col + 1));
+            let (c, d) = (corner(row + 1, col + 1), corner(row + 1, col));
+            for vertex in [a, b, c, a, c, d] {
+                data.extend(vertex);
+            }
+        }
+    }
+    data
+}
+
+// default vertex colors, gradient from position
+fn position_colors(data: &[f32]) -> Vec<f32> {
+    data.chunks_exact(3)
+        .flat_map(|p| {
+            [
+                (p[0] + 1.0) * 0.5,
+                (p[1] + 1.0) * 0.5,
+                (p[2] + 1.0) * 0.5,
+                1.0,
+            ]
+        })
+        .collect()
+}
+
+// directional light added with Globe::add_light, direction points from globe toward light
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    pub direction: Vec3,
+    // linear rgb, scaled by intensity
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+// ambient term plus main, fill and up to MAX_LIGHTS additional directional lights
+// combined light is clamped in shader to avoid over saturation,
+// masked specular highlight from main light is added after clamping
+pub struct Lighting {
+    pub ambient: UniformVec3,
+    pub light_dir: UniformVec3,
+    pub light_color: UniformVec3,
+    pub fill_dir: UniformVec3,
+    pub fill_color: UniformVec3,
+    pub specular_intensity: UniformFloat,
+    pub shininess: UniformFloat,
+    pub lights: Vec<DirectionalLight>,
+}
+
+impl Default for Lighting {
+    // full ambient with lights off, vertex colors drawn unlit
+    fn default() -> Self {
+        Self {
+            ambient: UniformVec3::new("ambient", Vec3::ONE),
+            light_dir: UniformVec3::new("lightDir", Vec3::Z),
+            light_color: UniformVec3::new("lightColor", Vec3::ZERO),
+            fill_dir: UniformVec3::new("fillDir", Vec3::NEG_Z),
+            fill_color: UniformVec3::new("fillColor", Vec3::ZERO),
+            specular_intensity: UniformFloat::new("specularIntensity", 0.0),
+            shininess: UniformFloat::new("shininess", DEFAULT_SHININESS),
+            lights: vec![],
+        }
+    }
+}
+
+impl Lighting {
+    // bind program and set all lighting uniforms
+    pub fn apply(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        program.bind(gl);
+        self.ambient.apply_optional(gl, program);
+        self.light_dir.apply_optional(gl, program);
+        self.light_color.apply_optional(gl, program);
+        self.fill_dir.apply_optional(gl, program);
+        self.fill_color.apply_optional(gl, program);
+        self.specular_intensity.apply_optional(gl, program);
+        self.shininess.apply_optional(gl, program);
+        self.apply_lights(gl, program)?;
+        Ok(())
+    }
+
+    // set additional light arrays on bound program, unused slots are black
+    pub fn apply_lights(&self, gl: &glow::Context, program: &Program) -> Result<(), UniformError> {
+        let mut dirs = vec![Vec3::Z; MAX_LIGHTS];
+        let mut colors = vec![Vec3::ZERO; MAX_LIGHTS];
+        for (i, light) in self.lights.iter().take(MAX_LIGHTS).enumerate() {
+            dirs[i] = light.direction.normalize_or_zero();
+            colors[i] = light.color * light.intensity;
+        }
+        UniformVec3Array::new("lightDirs", dirs).apply_optional(gl, program);
+        UniformVec3Array::new("lightColors", colors).apply_optional(gl, program);
+        Ok(())
+    }
+}
+
 impl Drop for Globe {
     fn drop(&self, gl: &glow::Context) {
         self.program.drop(gl);
         self.buffer.drop(gl);
+        self.color_buffer.drop(gl);
+        self.edge_buffer.drop(gl);
+        self.vertex_buffer.drop(gl);
+        if let Some(mask) = &self.specular_mask {
+            mask.drop(gl);
+        }
     }
 }
 
@@ -63,4 +796,51 @@ pub enum GlobeError {
     Buffer(#[from] crate::gl_wrap::BufferError),
     #[error("{0}")]
     VertexArray(#[from] crate::gl_wrap::VertexArrayError),
+    #[error("Color data length {0} doesn't match expected length {1}")]
+    ColorLength(usize, usize),
+    #[error("Subdivisions {0} above maximum of {}", MAX_SUBDIVISIONS)]
+    Subdivisions(usize),
+    #[error("Can't add more than {} lights", MAX_LIGHTS)]
+    MaxLights,
+    #[error("{0}")]
+    Uniform(#[from] UniformError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertex_indices() {
+        // two triangles sharing an edge have four distinct vertices
+        let data = [
+            0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, //
+            1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0,
+        ];
+        assert_eq!(vertex_indices(&data), vec![0, 1, 2, 5]);
+        // icosphere vertex count matches geodesic grid
+        let mesh = crate::icosphere::get_icosphere(2);
+        assert_eq!(vertex_indices(&mesh).len(), 10 * 4usize.pow(2) + 2);
+    }
+
+    #[test]
+    fn test_edge_indices() {
+        assert_eq!(edge_indices(6), vec![0, 1, 1, 2, 2, 0, 3, 4, 4, 5, 5, 3]);
+        assert!(edge_indices(0).is_empty());
+    }
+
+    #[test]
+    fn test_flat_mesh() {
+        let data = flat_mesh();
+        assert_eq!(data.len(), FLAT_ROWS * FLAT_COLUMNS * 18);
+        // flat positions stay inside map and triangles face +z
+        for tri in data.chunks_exact(9) {
+            let [a, b, c] = [0, 3, 6].map(|i| {
+                let (lat, lon) = xyz_to_lat_lon([tri[i], tri[i + 1], tri[i + 2]]);
+                glam::Vec2::new(lon, lat)
+            });
+            assert!(a.x.abs() < 180.0 && a.y.abs() < 90.0);
+            assert!((b - a).perp_dot(c - a) > 0.0);
+        }
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 fn normalize(v: [f32; 3]) -> [f32; 3] {
     let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
     let inv_len = if len == 0.0 { 0.0 } else { 1.0 / len }; // prevent divide by 0
@@ -13,34 +15,35 @@ fn midpoint(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
 }
 
 // subdivide icosphere, convert each triangle into 4 new
+// midpoints are shared between triangles on either side of an edge,
+// keeping the mesh indexed without duplicate vertices
 fn subdivide_icosphere(
-    vert: Vec<[f32; 3]>,
+    mut vert: Vec<[f32; 3]>,
     tri: Vec<[usize; 3]>,
 ) -> (Vec<[f32; 3]>, Vec<[usize; 3]>) {
-    // known final vec lengths, set capacity to prevent realloc
-    let mut next_vert: Vec<[f32; 3]> = Vec::with_capacity(tri.len() * 6);
+    // each edge is shared by two triangles, adding one vertex per edge
+    vert.reserve(tri.len() * 3 / 2);
     let mut next_tri: Vec<[usize; 3]> = Vec::with_capacity(tri.len() * 4);
-    for t in tri {
-        let curr_ind = next_vert.len();
-        next_vert.append(&mut vec![
-            vert[t[0]],
-            vert[t[1]],
-            vert[t[2]],
-            normalize(midpoint(vert[t[0]], vert[t[1]])),
-            normalize(midpoint(vert[t[1]], vert[t[2]])),
-            normalize(midpoint(vert[t[2]], vert[t[0]])),
-        ]);
-        next_tri.append(&mut vec![
-            [curr_ind, 3 + curr_ind, 5 + curr_ind],
-            [3 + curr_ind, 1 + curr_ind, 4 + curr_ind],
-            [4 + curr_ind, 2 + curr_ind, 5 + curr_ind],
-            [3 + curr_ind, 4 + curr_ind, 5 + curr_ind],
-        ])
+    let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut midpoint_index = |a: usize, b: usize, vert: &mut Vec<[f32; 3]>| {
+        *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+            vert.push(normalize(midpoint(vert[a], vert[b])));
+            vert.len() - 1
+        })
+    };
+    for [a, b, c] in tri {
+        let ab = midpoint_index(a, b, &mut vert);
+        let bc = midpoint_index(b, c, &mut vert);
+        let ca = midpoint_index(c, a, &mut vert);
+        next_tri.extend_from_slice(&[[a, ab, ca], [ab, b, bc], [bc, c, ca], [ab, bc, ca]]);
     }
-    (next_vert, next_tri)
+    (vert, next_tri)
 }
 
-pub fn get_icosphere(iterations: usize) -> Vec<f32> {
+// indexed icosphere with vertices on unit sphere and triangles as vertex indices,
+// each triangle wound counter clockwise seen from outside
+// vertex count is 10 * 4^iterations + 2, triangle count is 20 * 4^iterations
+pub fn get_icosphere_mesh(iterations: usize) -> (Vec<[f32; 3]>, Vec<[usize; 3]>) {
     // precalculated values for normalized vertices
     const A: f32 = 0.5257311;
     const B: f32 = 0.8506508;
@@ -84,13 +87,18 @@ pub fn get_icosphere(iterations: usize) -> Vec<f32> {
     for _ in 0..iterations {
         (vertices, triangles) = subdivide_icosphere(vertices, triangles);
     }
+    (vertices, triangles)
+}
+
+// unindexed triangle positions on unit sphere, each triangle wound counter clockwise
+// seen from outside, so gl default front face is the outer surface
+pub fn get_icosphere(iterations: usize) -> Vec<f32> {
+    let (vertices, triangles) = get_icosphere_mesh(iterations);
     // create buffer from triangle / vertex sets
-    let mut buffer: Vec<f32> = vec![];
+    let mut buffer: Vec<f32> = Vec::with_capacity(triangles.len() * 9);
     for tri in triangles {
         for ind in tri {
-            for val in vertices[ind] {
-                buffer.push(val);
-            }
+            buffer.extend_from_slice(&vertices[ind]);
         }
     }
     buffer
@@ -133,6 +141,20 @@ mod tests {
         assert_eq!(subdivided_triangles.len(), 4);
     }
 
+    #[test]
+    fn test_icosphere_winding() {
+        // every triangle counter clockwise seen from outside, normal points away from center
+        for subdivisions in 0..3 {
+            let data = get_icosphere(subdivisions);
+            for t in data.chunks_exact(9) {
+                let a = glam::Vec3::from_slice(&t[0..3]);
+                let b = glam::Vec3::from_slice(&t[3..6]);
+                let c = glam::Vec3::from_slice(&t[6..9]);
+                assert!((b - a).cross(c - a).dot(a + b + c) > 0.0);
+            }
+        }
+    }
+
     #[test]
     fn test_get_icosphere() {
         let iterations = 1;
@@ -144,6 +166,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_icosphere_mesh() {
+        for iterations in 0..4 {
+            let (vertices, triangles) = get_icosphere_mesh(iterations);
+            let scale = 4usize.pow(iterations as u32);
+            assert_eq!(vertices.len(), 10 * scale + 2);
+            assert_eq!(triangles.len(), 20 * scale);
+            for v in &vertices {
+                let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+                assert!((len - 1.0).abs() < 1e-6);
+            }
+            assert!(triangles.iter().flatten().all(|&i| i < vertices.len()));
+        }
+        // flattened buffer matches indexed mesh
+        let (vertices, triangles) = get_icosphere_mesh(2);
+        let buffer = get_icosphere(2);
+        assert_eq!(&buffer[0..3], &vertices[triangles[0][0]]);
+        assert_eq!(buffer.len(), triangles.len() * 9);
+    }
+
     #[test]
     fn test_icosphere_vertex_normalized() {
         let iterations = 2;
@@ -0,0 +1,115 @@
+use crate::mouse::{MAX_DISTANCE, MIN_DISTANCE};
+
+// keys vis can respond to, mapped from platform key codes in vis_ctx
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Plus,
+    Equals,
+    Minus,
+    NumpadAdd,
+    NumpadSubtract,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    ZoomIn,
+    ZoomOut,
+}
+
+// camera distance ratio for each zoom key press, and duration of animated step
+const KEY_ZOOM_FACTOR: f32 = 1.2;
+pub const KEY_ZOOM_MS: f32 = 150.0;
+
+// key to camera action mapping, held keys repeat their action at os key repeat rate
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    pub bindings: Vec<(Key, KeyAction)>,
+}
+
+impl KeyBindings {
+    // no keys bound, add bindings with bind
+    pub fn empty() -> Self {
+        let bindings = vec![];
+        Self { bindings }
+    }
+
+    // bind key to action, replacing any previous action for key
+    pub fn bind(mut self, key: Key, action: KeyAction) -> Self {
+        self.bindings.retain(|(k, _)| *k != key);
+        self.bindings.push((key, action));
+        self
+    }
+
+    pub fn action(&self, key: Key) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, action)| *action)
+    }
+}
+
+impl Default for KeyBindings {
+    // + / = and numpad + zoom in, - and numpad - zoom out
+    fn default() -> Self {
+        Self::empty()
+            .bind(Key::Plus, KeyAction::ZoomIn)
+            .bind(Key::Equals, KeyAction::ZoomIn)
+            .bind(Key::NumpadAdd, KeyAction::ZoomIn)
+            .bind(Key::Minus, KeyAction::ZoomOut)
+            .bind(Key::NumpadSubtract, KeyAction::ZoomOut)
+    }
+}
+
+// camera distance after one zoom step, within zoom limits
+pub fn key_zoom_distance(distance: f32, action: KeyAction) -> f32 {
+    let distance = match action {
+        KeyAction::ZoomIn => distance / KEY_ZOOM_FACTOR,
+        KeyAction::ZoomOut => distance * KEY_ZOOM_FACTOR,
+    };
+    distance.clamp(MIN_DISTANCE, MAX_DISTANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_bindings() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.action(Key::Equals), Some(KeyAction::ZoomIn));
+        assert_eq!(
+            bindings.action(Key::NumpadSubtract),
+            Some(KeyAction::ZoomOut)
+        );
+        assert_eq!(bindings.action(Key::PageUp), None);
+
+        // remapping replaces previous binding
+        let bindings = bindings
+            .bind(Key::PageUp, KeyAction::ZoomIn)
+            .bind(Key::Minus, KeyAction::ZoomIn);
+        assert_eq!(bindings.action(Key::PageUp), Some(KeyAction::ZoomIn));
+        assert_eq!(bindings.action(Key::Minus), Some(KeyAction::ZoomIn));
+        assert!(KeyBindings::empty().action(Key::Plus).is_none());
+    }
+
+    #[test]
+    fn test_key_zoom_distance() {
+        let zoomed = key_zoom_distance(2.4, KeyAction::ZoomIn);
+        assert!((zoomed - 2.0).abs() < 1e-6);
+        assert!((key_zoom_distance(zoomed, KeyAction::ZoomOut) - 2.4).abs() < 1e-6);
+        assert_eq!(
+            key_zoom_distance(MIN_DISTANCE, KeyAction::ZoomIn),
+            MIN_DISTANCE
+        );
+        assert_eq!(
+            key_zoom_distance(MAX_DISTANCE, KeyAction::ZoomOut),
+            MAX_DISTANCE
+        );
+    }
+}
@@ -0,0 +1,33 @@
+pub mod binning;
+pub mod camera;
+pub mod cluster;
+pub mod colors;
+pub mod coords;
+pub mod generate;
+pub mod geo;
+pub mod gl_wrap;
+pub mod globe;
+pub mod icosphere;
+pub mod keyboard;
+pub mod lines;
+pub mod markers;
+pub mod mouse;
+pub mod pick;
+pub mod points;
+pub mod render_pass;
+pub mod route;
+#[cfg(feature = "serde")]
+pub mod scene;
+pub mod state;
+pub mod touch;
+pub mod vis_build;
+pub mod vis_ctx;
+pub mod vis_gl;
+
+pub use gl_wrap::Drop;
+pub use markers::Marker;
+pub use points::Point;
+pub use render_pass::{CameraView, DepthFunc, PassDepth, PassStage, PolygonOffset, RenderPass};
+pub use state::VisState;
+pub use vis_build::VisBuilder;
+pub use vis_ctx::Backend;
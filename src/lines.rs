@@ -0,0 +1,431 @@
+use crate::geo::{lat_lon_to_xyz, DEFAULT_SURFACE_OFFSET};
+use crate::gl_wrap::{
+    blend_over, enable_primitive_restart, strip_indices, strip_line_indices, Bind, Buffer,
+    BufferUsage, Drop, ElementBuffer, Program, UniformFloat, VertexArray,
+};
+use crate::points::logical_to_physical;
+use glam::{Quat, Vec3};
+use glow::HasContext;
+
+// colored line segment between two positions, globe radius is 1
+// drawn as a screen space quad so width is the same on every platform,
+// gl line width above 1 isn't supported on core profile or webgl2
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineSegment {
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+    pub color: [f32; 4],
+}
+
+// connected positions drawn as one line strip, globe radius is 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinePath {
+    pub positions: Vec<[f32; 3]>,
+    pub color: [f32; 4],
+}
+
+const LINE_VERT: &str = include_str!("../shaders/line-vert.glsl");
+const LINE_FRAG: &str = include_str!("../shaders/line-frag.glsl");
+const PATH_VERT: &str = include_str!("../shaders/path-vert.glsl");
+const PATH_FRAG: &str = include_str!("../shaders/path-frag.glsl");
+
+// floats per segment in instance buffer
+const LINE_STRIDE: i32 = 10;
+
+// floats per path vertex, xyz then rgba
+const PATH_STRIDE: i32 = 7;
+
+// line width in logical pixels
+pub const DEFAULT_LINE_WIDTH: f32 = 1.5;
+
+// view segments as flat floats for upload, segment is repr(C) with only f32 fields
+fn segment_floats(segments: &[LineSegment]) -> &[f32] {
+    let (_, floats, _) = unsafe { segments.align_to::<f32>() };
+    floats
+}
+
+// great circle between two lat / lon points in degrees, split into segments so it
+// follows the sphere instead of cutting through it
+pub fn great_circle_arc(
+    from: (f32, f32),
+    to: (f32, f32),
+    segments: usize,
+    color: [f32; 4],
+) -> Vec<LineSegment> {
+    let path = great_circle_path(from, to, segments, color);
+    path.positions
+        .windows(2)
+        .map(|w| LineSegment {
+            start: w[0],
+            end: w[1],
+            color,
+        })
+        .collect()
+}
+
+// great circle arc as a path of segments + 1 positions, for drawing many arcs with Paths
+pub fn great_circle_path(
+    from: (f32, f32),
+    to: (f32, f32),
+    segments: usize,
+    color: [f32; 4],
+) -> LinePath {
+    let from = Vec3::from(lat_lon_to_xyz(from.0, from.1, 1.0));
+    let to = Vec3::from(lat_lon_to_xyz(to.0, to.1, 1.0));
+    // antipodal endpoints have no unique great circle, rotation arc picks one
+    let arc = Quat::from_rotation_arc(from, to);
+    let segments = segments.max(1);
+    let positions = (0..=segments)
+        .map(|i| {
+            let t = i as f32 / segments as f32;
+            (Quat::IDENTITY.slerp(arc, t) * from).to_array()
+        })
+        .collect();
+    LinePath { positions, color }
+}
+
+// lat / lon grid lines every step degrees, split into segments of at most step / 4 degrees.
+// meridians stop short of poles and parallels skip them, where they'd collapse to a point
+pub fn graticule(step: f32, color: [f32; 4]) -> Vec<LineSegment> {
+    let step = step.clamp(1.0, 90.0);
+    let detail = (step / 4.0).max(1.0);
+    // multiples of step from min up to max
+    let range = |min: f32, max: f32, step: f32| {
+        let count = ((max - min) / step).floor() as usize;
+        (0..=count).map(move |i| min + i as f32 * step)
+    };
+    // min to max inclusive, evenly split into parts no longer than max step
+    let split = |min: f32, max: f32, max_step: f32| -> Vec<f32> {
+        let count = ((max - min) / max_step).ceil().max(1.0) as usize;
+        (0..=count)
+            .map(|i| min + (max - min) * i as f32 / count as f32)
+            .collect()
+    };
+    let segment = |a: (f32, f32), b: (f32, f32)| LineSegment {
+        start: lat_lon_to_xyz(a.0, a.1, 1.0),
+        end: lat_lon_to_xyz(b.0, b.1, 1.0),
+        color,
+    };
+    let max_lat = 90.0 - step.min(10.0);
+    let mut segments = vec![];
+    for lon in range(-180.0, 180.0 - step, step) {
+        let lats = split(-max_lat, max_lat, detail);
+        segments.extend(lats.windows(2).map(|w| segment((w[0], lon), (w[1], lon))));
+    }
+    for lat in range(-90.0, 90.0, step).filter(|lat| lat.abs() < 90.0) {
+        let lons = split(-180.0, 180.0, detail);
+        segments.extend(lons.windows(2).map(|w| segment((lat, w[0]), (lat, w[1]))));
+    }
+    segments
+}
+
+// contains gl resources for drawing instanced anti aliased lines
+pub struct Lines {
+    pub program: Program,
+    pub quad_buffer: Buffer,
+    pub instance_buffer: Buffer,
+    pub vao: VertexArray,
+    // line width in logical pixels, uniform holds physical width
+    pub width: f32,
+    pub width_uniform: UniformFloat,
+    // radial push in globe radii so lines on the surface aren't hidden by globe
+    pub surface_offset: UniformFloat,
+    // copy of uploaded segments, uploaded again after context restore
+    pub data: Vec<LineSegment>,
+}
+
+impl Lines {
+    pub fn new(gl: &glow::Context, shader_version: &str) -> Result<Self, LinesError> {
+        let program = Program::new_from_sources(gl, shader_version, LINE_VERT, LINE_FRAG)?;
+        // corners as (end, side), end 0 at start and 1 at end, drawn as triangle strip
+        let mut quad_buffer = Buffer::new(gl, BufferUsage::Static)?;
+        quad_buffer.set_data(gl, &[0.0, -1.0, 1.0, -1.0, 0.0, 1.0, 1.0, 1.0]);
+        let instance_buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        let vao = VertexArray::new(gl)?;
+        program.bind(gl);
+        vao.bind(gl);
+        quad_buffer.bind(gl);
+        VertexArray::set_attrib(gl, &program, "corner", 2, 2, 0)?;
+        instance_buffer.bind(gl);
+        VertexArray::set_instance_attrib(gl, &program, "start", 3, LINE_STRIDE, 0)?;
+        VertexArray::set_instance_attrib(gl, &program, "end", 3, LINE_STRIDE, 3)?;
+        VertexArray::set_instance_attrib(gl, &program, "color", 4, LINE_STRIDE, 6)?;
+        let width = DEFAULT_LINE_WIDTH;
+        let width_uniform = UniformFloat::new("lineWidth", logical_to_physical(width, 1.0));
+        width_uniform.apply_bound(gl, &program)?;
+        let surface_offset = UniformFloat::new("surfaceOffset", DEFAULT_SURFACE_OFFSET);
+        surface_offset.apply_bound(gl, &program)?;
+        Ok(Self {
+            program,
+            quad_buffer,
+            instance_buffer,
+            vao,
+            width,
+            width_uniform,
+            surface_offset,
+            data: vec![],
+        })
+    }
+
+    // set line width in logical pixels, scaled by dpi for upload
+    pub fn set_width(
+        &mut self,
+        gl: &glow::Context,
+        width: f32,
+        dpi: f64,
+    ) -> Result<(), LinesError> {
+        self.width = width.max(0.0);
+        self.width_uniform.data = logical_to_physical(self.width, dpi);
+        self.width_uniform.apply(gl, &[&self.program])?;
+        Ok(())
+    }
+
+    // set radial push away from globe in globe radii, 0 draws lines at their positions
+    pub fn set_surface_offset(
+        &mut self,
+        gl: &glow::Context,
+        offset: f32,
+    ) -> Result<(), LinesError> {
+        self.surface_offset.data = offset;
+        self.surface_offset.apply(gl, &[&self.program])?;
+        Ok(())
+    }
+
+    // number of segments currently uploaded
+    pub fn len(&self) -> usize {
+        self.instance_buffer.len / LINE_STRIDE as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // upload segments without drawing
+    pub fn set_segments(&mut self, gl: &glow::Context, segments: &[LineSegment]) {
+        self.instance_buffer.write(gl, segment_floats(segments));
+        self.data.clear();
+        self.data.extend_from_slice(segments);
+    }
+
+    // upload segments and settings of lines from a lost context
+    pub fn restore_from(
+        &mut self,
+        gl: &glow::Context,
+        lost: &Lines,
+        dpi: f64,
+    ) -> Result<(), LinesError> {
+        self.set_width(gl, lost.width, dpi)?;
+        self.set_surface_offset(gl, lost.surface_offset.data)?;
+        self.set_segments(gl, &lost.data);
+        Ok(())
+    }
+
+    // draw uploaded segments, blended so anti aliased edges fade into what's behind them.
+    // viewport is the physical pixel size of the current gl viewport, passed by the caller
+    // that set it rather than queried. segments crossing the antimeridian stretch across
+    // the flat map
+    pub fn draw(&self, gl: &glow::Context, viewport: [f32; 2]) {
+        if self.is_empty() {
+            return;
+        }
+        self.program.bind(gl);
+        if let Some(location) = self.program.uniform_location(gl, "viewportSize") {
+            unsafe { gl.uniform_2_f32(Some(&location), viewport[0], viewport[1]) }
+        }
+        self.vao.bind(gl);
+        unsafe {
+            gl.enable(glow::BLEND);
+            blend_over(gl);
+            gl.draw_arrays_instanced(glow::TRIANGLE_STRIP, 0, 4, self.len() as i32);
+            gl.disable(glow::BLEND);
+        }
+    }
+}
+
+impl Drop for Lines {
+    fn drop(&self, gl: &glow::Context) {
+        self.program.drop(gl);
+        self.quad_buffer.drop(gl);
+        self.instance_buffer.drop(gl);
+    }
+}
+
+// one pixel wide paths drawn as line strips, cheaper than segment quads for dense data
+// like coastlines. every path is drawn in a single call, strips broken by RESTART_INDEX
+pub struct Paths {
+    pub program: Program,
+    pub buffer: Buffer,
+    pub indices: ElementBuffer,
+    pub vao: VertexArray,
+    // radial push in globe radii so paths on the surface aren't hidden by globe
+    pub surface_offset: UniformFloat,
+    // copy of uploaded paths, uploaded again after context restore
+    pub data: Vec<LinePath>,
+    // strips are split into separate lines where primitive restart is unavailable
+    restart: bool,
+}
+
+impl Paths {
+    pub fn new(gl: &glow::Context, shader_version: &str) -> Result<Self, LinesError> {
+        let program = Program::new_from_sources(gl, shader_version, PATH_VERT, PATH_FRAG)?;
+        let buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        let indices = ElementBuffer::new(gl, BufferUsage::Dynamic)?;
+        let vao = VertexArray::new(gl)?;
+        program.bind(gl);
+        vao.bind(gl);
+        buffer.bind(gl);
+        VertexArray::set_attrib(gl, &program, "position", 3, PATH_STRIDE, 0)?;
+        VertexArray::set_attrib(gl, &program, "color", 4, PATH_STRIDE, 3)?;
+        let surface_offset = UniformFloat::new("surfaceOffset", DEFAULT_SURFACE_OFFSET);
+        surface_offset.apply_bound(gl, &program)?;
+        Ok(Self {
+            program,
+            buffer,
+            indices,
+            vao,
+            surface_offset,
+            data: vec![],
+            restart: enable_primitive_restart(gl),
+        })
+    }
+
+    // set radial push away from globe in globe radii, 0 draws paths at their positions
+    pub fn set_surface_offset(
+        &mut self,
+        gl: &glow::Context,
+        offset: f32,
+    ) -> Result<(), LinesError> {
+        self.surface_offset.data = offset;
+        self.surface_offset.apply(gl, &[&self.program])?;
+        Ok(())
+    }
+
+    // upload paths without drawing, paths with less than 2 positions draw nothing
+    pub fn set_paths(&mut self, gl: &glow::Context, paths: &[LinePath]) {
+        let mut vertices = vec![];
+        let mut lengths = vec![];
+        for path in paths.iter().filter(|p| p.positions.len() > 1) {
+            for position in &path.positions {
+                vertices.extend_from_slice(position);
+                vertices.extend_from_slice(&path.color);
+            }
+            lengths.push(path.positions.len());
+        }
+        self.buffer.write(gl, &vertices);
+        // element buffer binding is stored in bound vao
+        self.vao.bind(gl);
+        if self.restart {
+            self.indices.set_data(gl, &strip_indices(&lengths));
+        } else {
+            self.indices.set_data(gl, &strip_line_indices(&lengths));
+        }
+        self.data.clear();
+        self.data.extend_from_slice(paths);
+    }
+
+    // upload paths and settings of paths from a lost context
+    pub fn restore_from(&mut self, gl: &glow::Context, lost: &Paths) -> Result<(), LinesError> {
+        self.set_surface_offset(gl, lost.surface_offset.data)?;
+        self.set_paths(gl, &lost.data);
+        Ok(())
+    }
+
+    // draw all uploaded paths in one call
+    pub fn draw(&self, gl: &glow::Context) {
+        if self.indices.len == 0 {
+            return;
+        }
+        self.program.bind(gl);
+        self.vao.bind(gl);
+        unsafe {
+            gl.enable(glow::BLEND);
+            blend_over(gl);
+        }
+        let mode = if self.restart {
+            glow::LINE_STRIP
+        } else {
+            glow::LINES
+        };
+        self.indices.draw(gl, mode);
+        unsafe {
+            gl.disable(glow::BLEND);
+        }
+    }
+}
+
+impl Drop for Paths {
+    fn drop(&self, gl: &glow::Context) {
+        self.program.drop(gl);
+        self.buffer.drop(gl);
+        self.indices.drop(gl);
+        self.vao.drop(gl);
+    }
+}
+
+use thiserror::Error;
+#[derive(Error, Debug)]
+pub enum LinesError {
+    #[error("{0}")]
+    Program(#[from] crate::gl_wrap::ProgramError),
+    #[error("{0}")]
+    Buffer(#[from] crate::gl_wrap::BufferError),
+    #[error("{0}")]
+    VertexArray(#[from] crate::gl_wrap::VertexArrayError),
+    #[error("{0}")]
+    Uniform(#[from] crate::gl_wrap::UniformError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::{great_circle_degrees, xyz_to_lat_lon};
+
+    #[test]
+    fn test_great_circle_arc() {
+        let color = [1.0; 4];
+        let arc = great_circle_arc((0.0, 0.0), (0.0, 90.0), 9, color);
+        assert_eq!(arc.len(), 9);
+        // segments connect end to start and follow the equator
+        for pair in arc.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        let (lat, lon) = xyz_to_lat_lon(arc[4].end);
+        assert!(lat.abs() < 1e-4 && (lon - 50.0).abs() < 1e-3);
+        let (lat, lon) = xyz_to_lat_lon(arc[8].end);
+        assert!(lat.abs() < 1e-4 && (lon - 90.0).abs() < 1e-3);
+        assert_eq!(segment_floats(&arc).len(), 9 * LINE_STRIDE as usize);
+    }
+
+    #[test]
+    fn test_great_circle_path() {
+        let color = [1.0; 4];
+        let path = great_circle_path((0.0, 0.0), (0.0, 90.0), 9, color);
+        assert_eq!(path.positions.len(), 10);
+        // arc segments are consecutive path positions
+        let arc = great_circle_arc((0.0, 0.0), (0.0, 90.0), 9, color);
+        for (segment, pair) in arc.iter().zip(path.positions.windows(2)) {
+            assert_eq!((segment.start, segment.end), (pair[0], pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_graticule() {
+        let lines = graticule(30.0, [1.0; 4]);
+        // no segment is longer than detail step, and none touch the poles
+        for segment in &lines {
+            let (lat1, lon1) = xyz_to_lat_lon(segment.start);
+            let (lat2, lon2) = xyz_to_lat_lon(segment.end);
+            assert!(great_circle_degrees(lat1, lon1, lat2, lon2) <= 7.5 + 1e-3);
+            assert!(lat1.abs() < 90.0 && lat2.abs() < 90.0);
+        }
+        // 12 meridians and 5 parallels, equator included
+        let equator = lines
+            .iter()
+            .filter(|s| {
+                xyz_to_lat_lon(s.start).0.abs() < 1e-4 && xyz_to_lat_lon(s.end).0.abs() < 1e-4
+            })
+            .count();
+        assert_eq!(equator, 48);
+    }
+}
@@ -1,17 +1,6 @@
-mod gl_wrap;
-mod globe;
-mod icosphere;
-mod mouse;
-mod points;
-mod state;
-mod vis_build;
-mod vis_ctx;
-mod vis_gl;
-
 #[cfg(target_arch = "wasm32")]
 use console_error_panic_hook::set_once as set_console_panic_hook;
-use state::VisState;
-use vis_build::VisBuilder;
+use globe_vis::{coords::lat_lon_to_xyz, VisBuilder, VisState};
 
 pub struct TestState {
     offsets: Vec<f32>,
@@ -37,16 +26,22 @@ impl TestState {
 
 const SPEED: f32 = std::f32::consts::PI / 10000.0;
 const RADIUS: f32 = 1.5;
+// degrees either side of equator
+const LAT_RANGE: f32 = 40.0;
 impl VisState for TestState {
     fn update_points(&mut self, ms: f32) -> Vec<f32> {
-        (0..self.length)
-            .map(|i| match i % 3 {
-                0 => RADIUS * (self.offsets[i] + ms * SPEED).cos(),
-                1 => self.positions[i],
-                2 => RADIUS * (self.offsets[i] + ms * SPEED).sin(),
-                _ => -1.0,
-            })
-            .collect()
+        let mut points = Vec::with_capacity(self.length);
+        self.update_points_into(ms, &mut points);
+        points
+    }
+
+    fn update_points_into(&mut self, ms: f32, points: &mut Vec<f32>) {
+        // orbit westward in a band around the equator
+        points.extend((0..self.length / 3).flat_map(|i| {
+            let lon = -(self.offsets[i] + ms * SPEED).to_degrees();
+            let lat = self.positions[i] * LAT_RANGE;
+            lat_lon_to_xyz(lat, lon, RADIUS)
+        }));
     }
 }
 
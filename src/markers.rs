@@ -0,0 +1,245 @@
+use crate::geo::lat_lon_to_xyz;
+use crate::gl_wrap::{
+    Bind, Buffer, BufferUsage, Drop, Program, ProgramCache, UniformFloat, VertexArray,
+};
+use glow::HasContext;
+use std::rc::Rc;
+
+// colored circular marker, drawn as instanced camera facing quad
+// size is marker radius in world units, globe radius is 1
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Marker {
+    pub pos: [f32; 3],
+    pub color: [f32; 4],
+    pub size: f32,
+}
+
+const MARKER_VERT: &str = include_str!("../shaders/marker-vert.glsl");
+const MARKER_FRAG: &str = include_str!("../shaders/marker-frag.glsl");
+
+// floats per marker in instance buffer
+const MARKER_STRIDE: i32 = 8;
+
+// marker size range used for values mapped in from_lat_lon_value
+pub const MIN_MARKER_SIZE: f32 = 0.005;
+pub const MAX_MARKER_SIZE: f32 = 0.03;
+
+impl Marker {
+    pub fn from_lat_lon(lat: f32, lon: f32, color: [f32; 4], size: f32) -> Self {
+        // lifted above globe by surface offset in shader
+        let pos = lat_lon_to_xyz(lat, lon, 1.0);
+        Self { pos, color, size }
+    }
+
+    // map value within range to marker size and a blue to red color
+    pub fn from_lat_lon_value(lat: f32, lon: f32, value: f32, range: (f32, f32)) -> Self {
+        let (min, max) = range;
+        let t = if max > min {
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let color = [t, 0.2, 1.0 - t, 1.0];
+        let size = MIN_MARKER_SIZE + (MAX_MARKER_SIZE - MIN_MARKER_SIZE) * t;
+        Self::from_lat_lon(lat, lon, color, size)
+    }
+}
+
+// view markers as flat floats for upload, marker is repr(C) with only f32 fields
+fn marker_floats(markers: &[Marker]) -> &[f32] {
+    let (_, floats, _) = unsafe { markers.align_to::<f32>() };
+    floats
+}
+
+// contains gl resources for drawing instanced markers
+pub struct Markers {
+    // shared with other marker layers when created from a program cache
+    pub program: Rc<Program>,
+    pub quad_buffer: Buffer,
+    pub instance_buffer: Buffer,
+    pub vao: VertexArray,
+    // radial push in globe radii so markers aren't clipped by globe,
+    // 0 by default so positions aren't moved
+    pub surface_offset: UniformFloat,
+    // 1 when quads face camera, 0 when flat on globe surface
+    pub billboard: UniformFloat,
+    // copy of uploaded markers, uploaded again after context restore
+    pub data: Vec<Marker>,
+}
+
+impl Markers {
+    pub fn new(gl: &glow::Context, shader_version: &str) -> Result<Self, MarkersError> {
+        let program = Rc::new(Program::new_from_sources(
+            gl,
+            shader_version,
+            MARKER_VERT,
+            MARKER_FRAG,
+        )?);
+        Self::new_with_program(gl, program)
+    }
+
+    // markers using cached marker program, marker layers from the same cache share it
+    pub fn new_with_cache(
+        gl: &glow::Context,
+        cache: &mut ProgramCache,
+        shader_version: &str,
+    ) -> Result<Self, MarkersError> {
+        let program = cache.get(gl, shader_version, MARKER_VERT, MARKER_FRAG)?;
+        Self::new_with_program(gl, program)
+    }
+
+    fn new_with_program(gl: &glow::Context, program: Rc<Program>) -> Result<Self, MarkersError> {
+        // quad corners shared by all instances, drawn as triangle strip
+        let mut quad_buffer = Buffer::new(gl, BufferUsage::Static)?;
+        quad_buffer.set_data(gl, &[-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0]);
+        let instance_buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        // init vao and setup attributes
+        let vao = VertexArray::new(gl)?;
+        program.bind(gl);
+        vao.bind(gl);
+        quad_buffer.bind(gl);
+        VertexArray::set_attrib(gl, &program, "corner", 2, 2, 0)?;
+        instance_buffer.bind(gl);
+        VertexArray::set_instance_attrib(gl, &program, "position", 3, MARKER_STRIDE, 0)?;
+        VertexArray::set_instance_attrib(gl, &program, "color", 4, MARKER_STRIDE, 3)?;
+        VertexArray::set_instance_attrib(gl, &program, "size", 1, MARKER_STRIDE, 7)?;
+        let surface_offset = UniformFloat::new("surfaceOffset", 0.0);
+        surface_offset.apply_bound(gl, &program)?;
+        let billboard = UniformFloat::new("billboard", 1.0);
+        billboard.apply_bound(gl, &program)?;
+        Ok(Self {
+            program,
+            quad_buffer,
+            instance_buffer,
+            vao,
+            surface_offset,
+            billboard,
+            data: vec![],
+        })
+    }
+
+    // face quads toward camera at every globe rotation, or lay them flat on the
+    // globe surface oriented east / north so they foreshorten toward the horizon
+    pub fn set_billboard(
+        &mut self,
+        gl: &glow::Context,
+        billboard: bool,
+    ) -> Result<(), MarkersError> {
+        self.billboard.data = if billboard { 1.0 } else { 0.0 };
+        self.billboard.apply(gl, &[&self.program])?;
+        Ok(())
+    }
+
+    // set radial push away from globe in globe radii, 0 draws markers at their positions
+    pub fn set_surface_offset(
+        &mut self,
+        gl: &glow::Context,
+        offset: f32,
+    ) -> Result<(), MarkersError> {
+        self.surface_offset.data = offset;
+        self.surface_offset.apply(gl, &[&self.program])?;
+        Ok(())
+    }
+
+    // number of markers currently uploaded
+    pub fn len(&self) -> usize {
+        self.instance_buffer.len / MARKER_STRIDE as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // upload markers without drawing
+    pub fn set_markers(&mut self, gl: &glow::Context, markers: &[Marker]) {
+        self.instance_buffer.set_data(gl, marker_floats(markers));
+        self.data.clear();
+        self.data.extend_from_slice(markers);
+    }
+
+    // upload markers and settings of layer from a lost context
+    pub fn restore_from(&mut self, gl: &glow::Context, lost: &Markers) -> Result<(), MarkersError> {
+        self.set_billboard(gl, lost.billboard.data > 0.0)?;
+        self.set_surface_offset(gl, lost.surface_offset.data)?;
+        self.set_markers(gl, &lost.data);
+        Ok(())
+    }
+
+    // upload new markers if provided and draw, None keeps previous markers
+    pub fn draw(&mut self, gl: &glow::Context, markers: Option<&[Marker]>) {
+        if let Some(markers) = markers {
+            self.set_markers(gl, markers);
+        }
+        if self.is_empty() {
+            return;
+        }
+        self.program.bind(gl);
+        // shared program holds uniforms of whichever layer set them last,
+        // locations were checked in new so setting them can't fail
+        if Rc::strong_count(&self.program) > 1 {
+            self.surface_offset.apply_bound(gl, &self.program).ok();
+            self.billboard.apply_bound(gl, &self.program).ok();
+        }
+        self.vao.bind(gl);
+        unsafe {
+            gl.draw_arrays_instanced(glow::TRIANGLE_STRIP, 0, 4, self.len() as i32);
+        }
+    }
+}
+
+impl Drop for Markers {
+    fn drop(&self, gl: &glow::Context) {
+        self.program.drop(gl);
+        self.quad_buffer.drop(gl);
+        self.instance_buffer.drop(gl);
+    }
+}
+
+use thiserror::Error;
+#[derive(Error, Debug)]
+pub enum MarkersError {
+    #[error("{0}")]
+    Program(#[from] crate::gl_wrap::ProgramError),
+    #[error("{0}")]
+    Buffer(#[from] crate::gl_wrap::BufferError),
+    #[error("{0}")]
+    VertexArray(#[from] crate::gl_wrap::VertexArrayError),
+    #[error("{0}")]
+    Uniform(#[from] crate::gl_wrap::UniformError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_floats_layout() {
+        let markers = [
+            Marker {
+                pos: [1.0, 2.0, 3.0],
+                color: [0.1, 0.2, 0.3, 0.4],
+                size: 5.0,
+            },
+            Marker {
+                pos: [6.0, 7.0, 8.0],
+                color: [0.5, 0.6, 0.7, 0.8],
+                size: 9.0,
+            },
+        ];
+        let floats = marker_floats(&markers);
+        assert_eq!(floats.len(), 2 * MARKER_STRIDE as usize);
+        assert_eq!(&floats[..8], &[1.0, 2.0, 3.0, 0.1, 0.2, 0.3, 0.4, 5.0]);
+        assert_eq!(floats[15], 9.0);
+    }
+
+    #[test]
+    fn test_marker_from_value() {
+        let low = Marker::from_lat_lon_value(0.0, 0.0, -5.0, (0.0, 10.0));
+        let high = Marker::from_lat_lon_value(0.0, 0.0, 10.0, (0.0, 10.0));
+        assert_eq!(low.size, MIN_MARKER_SIZE);
+        assert!((high.size - MAX_MARKER_SIZE).abs() < 1e-6);
+        assert_eq!(low.color[0], 0.0);
+        assert_eq!(high.color[0], 1.0);
+    }
+}
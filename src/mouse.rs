@@ -1,9 +1,20 @@
-use glam::{Mat4, Quat, Vec3};
+use crate::touch::TouchState;
+use glam::{Quat, Vec3};
+use instant::Instant;
 
 pub struct MouseState {
     pub x: f64,
     pub y: f64,
     pub dragging: bool,
+    // position left button was pressed at, for telling clicks from drags
+    pub press: Option<(f64, f64)>,
+    pub last_hover: Instant,
+    // cursor moved inside hover interval, reported once interval passes
+    pub hover_pending: bool,
+    // held keyboard modifiers, updated from platform modifier events
+    pub modifiers: Modifiers,
+    // active touch points, single touch drives the mouse state above
+    pub touches: TouchState,
 }
 
 impl MouseState {
@@ -12,10 +23,113 @@ impl MouseState {
             x: 0.0,
             y: 0.0,
             dragging: false,
+            press: None,
+            last_hover: Instant::now(),
+            hover_pending: false,
+            modifiers: Modifiers::default(),
+            touches: TouchState::new(),
         }
     }
 }
 
+// keyboard modifiers, mapped from platform modifier state in vis_ctx
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        shift: false,
+        ctrl: false,
+        alt: false,
+    };
+    pub const SHIFT: Self = Self {
+        shift: true,
+        ..Self::NONE
+    };
+    pub const CTRL: Self = Self {
+        ctrl: true,
+        ..Self::NONE
+    };
+    pub const ALT: Self = Self {
+        alt: true,
+        ..Self::NONE
+    };
+}
+
+// camera motion from left drag
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DragAction {
+    // rotate freely about screen x / y axes
+    Rotate,
+    // rotate about the screen axis of larger motion only
+    ConstrainAxis,
+    // rotate about view axis, following angle of cursor around view center
+    Roll,
+    // move camera in screen space, keeping globe center under cursor motion
+    Pan,
+}
+
+// modifier to drag action mapping, drags with unbound modifiers rotate
+#[derive(Clone, Debug, PartialEq)]
+pub struct DragBindings {
+    pub bindings: Vec<(Modifiers, DragAction)>,
+}
+
+impl DragBindings {
+    // every drag rotates, add bindings with bind
+    pub fn empty() -> Self {
+        let bindings = vec![];
+        Self { bindings }
+    }
+
+    // bind exact modifier combination to action, replacing any previous action for it
+    pub fn bind(mut self, modifiers: Modifiers, action: DragAction) -> Self {
+        self.bindings.retain(|(m, _)| *m != modifiers);
+        self.bindings.push((modifiers, action));
+        self
+    }
+
+    pub fn action(&self, modifiers: Modifiers) -> DragAction {
+        self.bindings
+            .iter()
+            .find(|(m, _)| *m == modifiers)
+            .map_or(DragAction::Rotate, |(_, action)| *action)
+    }
+}
+
+impl Default for DragBindings {
+    // shift pans, ctrl rolls, alt constrains to one axis
+    fn default() -> Self {
+        Self::empty()
+            .bind(Modifiers::SHIFT, DragAction::Pan)
+            .bind(Modifiers::CTRL, DragAction::Roll)
+            .bind(Modifiers::ALT, DragAction::ConstrainAxis)
+    }
+}
+
+// max movement in logical pixels between press and release for a click
+pub const CLICK_DISTANCE: f64 = 4.0;
+
+// logical pixels around drawn point radius that clicks still pick a point from
+pub const DEFAULT_PICK_RADIUS: f32 = 4.0;
+
+// check if release at x / y ends a click started at press, not a drag
+// positions in physical pixels like mouse state
+pub fn is_click(press: (f64, f64), x: f64, y: f64, dpi: f64) -> bool {
+    let (dx, dy) = (x - press.0, y - press.1);
+    (dx * dx + dy * dy).sqrt() <= CLICK_DISTANCE * dpi
+}
+
+impl Default for MouseState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub enum MouseButtons {
     Left,
     Right,
@@ -24,36 +138,83 @@ pub enum MouseButtons {
 
 pub const SCROLL_LINE_HEIGHT: f64 = 25.0;
 
+// min time between hover lat / lon updates
+pub const HOVER_INTERVAL_MS: u128 = 50;
+
 const ROT_SPEED: f64 = 0.005;
 const ZOOM_SPEED: f64 = 0.0005;
 
-pub fn rotate_from_mouse(mat: Mat4, dx: f64, dy: f64) -> Mat4 {
+pub fn rotate_from_mouse(orientation: Quat, dx: f64, dy: f64) -> Quat {
     let x_rad = (dy * ROT_SPEED) as f32;
     let y_rad = (dx * ROT_SPEED) as f32;
 
-    // transform x / y axis by inverse of current rotation
-    // to get rotation axis perpendicular to current view
+    // rotate about screen x / y axes by applying rotation after current orientation,
+    // avoids inverting model matrix to find view aligned axes in model space
     // normalize to prevent accumulating scaling errors
-    let inv_mat = mat.inverse();
-    let x_axis = inv_mat.transform_vector3(Vec3::X).normalize();
-    let y_axis = inv_mat.transform_vector3(Vec3::Y).normalize();
+    let x_rot = Quat::from_axis_angle(Vec3::X, x_rad);
+    let y_rot = Quat::from_axis_angle(Vec3::Y, y_rad);
+
+    (x_rot * y_rot * orientation).normalize()
+}
 
-    let x_rot = Mat4::from_quat(Quat::from_axis_angle(x_axis, x_rad));
-    let y_rot = Mat4::from_quat(Quat::from_axis_angle(y_axis, y_rad));
+// keep only the larger of the drag deltas, for rotation about a single screen axis
+pub fn constrain_drag(dx: f64, dy: f64) -> (f64, f64) {
+    if dx.abs() >= dy.abs() {
+        (dx, 0.0)
+    } else {
+        (0.0, dy)
+    }
+}
 
-    mat.mul_mat4(&x_rot.mul_mat4(&y_rot))
+// rotate about view axis by angle cursor swept around center between positions,
+// screen y points down so clockwise drags on screen turn the globe clockwise.
+// applied after current orientation like rotate_from_mouse
+pub fn roll_from_mouse(
+    orientation: Quat,
+    center: (f64, f64),
+    from: (f64, f64),
+    to: (f64, f64),
+) -> Quat {
+    let angle = |(x, y): (f64, f64)| (y - center.1).atan2(x - center.0);
+    let mut swept = angle(to) - angle(from);
+    // shortest way around, atan2 jumps by 2pi crossing the negative x axis
+    if swept > std::f64::consts::PI {
+        swept -= std::f64::consts::TAU;
+    } else if swept < -std::f64::consts::PI {
+        swept += std::f64::consts::TAU;
+    }
+    // positive z rotation is counter clockwise on screen
+    let roll = Quat::from_axis_angle(Vec3::Z, -swept as f32);
+    (roll * orientation).normalize()
 }
 
-pub fn zoom_from_scroll(mat: Mat4, delta: f64) -> Mat4 {
-    let zoom = (delta * ZOOM_SPEED) as f32;
-    let scale = Mat4::from_scale(Vec3::splat(1.0 + zoom));
-    mat.mul_mat4(&scale)
+// camera distance limits, keeps camera outside globe and inside far plane
+pub const MIN_DISTANCE: f32 = 1.1;
+pub const MAX_DISTANCE: f32 = 9.0;
+
+// smallest scene scale from a single scroll, large negative deltas would otherwise
+// flip the sign of 1 + zoom and land on the wrong distance limit
+const MIN_SCROLL_SCALE: f64 = 0.05;
+
+// get new camera distance from scroll, equivalent to scaling the scene by 1 + zoom
+pub fn zoom_from_scroll(distance: f32, delta: f64) -> f32 {
+    let zoom = (delta * ZOOM_SPEED).max(MIN_SCROLL_SCALE - 1.0) as f32;
+    (distance / (1.0 + zoom)).clamp(MIN_DISTANCE, MAX_DISTANCE)
+}
+
+// scroll delta zooming by the same ratio pinch distance changed by,
+// so content under a pinch keeps its size relative to the fingers
+pub fn pinch_scroll_delta(from: f64, to: f64) -> f64 {
+    if from <= 0.0 || to <= 0.0 {
+        return 0.0;
+    }
+    (to / from - 1.0) / ZOOM_SPEED
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use glam::{Mat4, Vec3};
+    use glam::{Mat4, Quat};
 
     fn assert_matrix_elements_near_eq(a: Mat4, b: Mat4, epsilon: f32) {
         let a = a.to_cols_array();
@@ -75,7 +236,7 @@ mod tests {
         let dx = 10.0;
         let dy = 20.0;
 
-        let rotated_mat = rotate_from_mouse(mat, dx, dy);
+        let rotated_mat = Mat4::from_quat(rotate_from_mouse(Quat::IDENTITY, dx, dy));
         let x_rad = (dy * ROT_SPEED) as f32;
         let y_rad = (dx * ROT_SPEED) as f32;
         let expected_x_rotation = Mat4::from_rotation_x(x_rad);
@@ -86,16 +247,114 @@ mod tests {
         assert_matrix_elements_near_eq(rotated_mat, expected_mat, 1e-6);
     }
 
+    #[test]
+    fn test_rotate_from_mouse_screen_axes() {
+        // rotation about screen x axis should match regardless of current orientation
+        let start = Quat::from_rotation_y(1.0) * Quat::from_rotation_x(0.5);
+        let rotated = Mat4::from_quat(rotate_from_mouse(start, 0.0, 100.0));
+        let x_rad = (100.0 * ROT_SPEED) as f32;
+        let expected = Mat4::from_rotation_x(x_rad) * Mat4::from_quat(start);
+
+        assert_matrix_elements_near_eq(rotated, expected, 1e-5);
+    }
+
+    #[test]
+    fn test_rotate_from_mouse_orthonormal() {
+        let mut orientation = Quat::IDENTITY;
+        for i in 0..10000 {
+            let dx = ((i % 7) as f64 - 3.0) * 0.1;
+            let dy = ((i % 5) as f64 - 2.0) * 0.1;
+            orientation = rotate_from_mouse(orientation, dx, dy);
+        }
+        let mat = Mat4::from_quat(orientation);
+        let product = mat.transpose() * mat;
+
+        assert_matrix_elements_near_eq(product, Mat4::IDENTITY, 1e-5);
+    }
+
+    #[test]
+    fn test_roll_from_mouse() {
+        // quarter turn clockwise on screen, right of center to below center
+        let center = (100.0, 100.0);
+        let rolled = roll_from_mouse(Quat::IDENTITY, center, (150.0, 100.0), (100.0, 150.0));
+        let expected = Mat4::from_rotation_z(-std::f32::consts::FRAC_PI_2);
+        assert_matrix_elements_near_eq(Mat4::from_quat(rolled), expected, 1e-6);
+
+        // applied in view space after current orientation
+        let start = Quat::from_rotation_x(0.7);
+        let rolled = roll_from_mouse(start, center, (150.0, 100.0), (100.0, 150.0));
+        let expected = expected * Mat4::from_quat(start);
+        assert_matrix_elements_near_eq(Mat4::from_quat(rolled), expected, 1e-6);
+
+        // crossing negative x axis takes short way around
+        let rolled = roll_from_mouse(Quat::IDENTITY, center, (50.0, 99.0), (50.0, 101.0));
+        let (_, angle) = rolled.to_axis_angle();
+        assert!(angle < 0.1);
+
+        // radial motion doesn't roll
+        let rolled = roll_from_mouse(Quat::IDENTITY, center, (150.0, 100.0), (180.0, 100.0));
+        assert_matrix_elements_near_eq(Mat4::from_quat(rolled), Mat4::IDENTITY, 1e-6);
+    }
+
+    #[test]
+    fn test_drag_bindings() {
+        let bindings = DragBindings::default();
+        assert_eq!(bindings.action(Modifiers::NONE), DragAction::Rotate);
+        assert_eq!(bindings.action(Modifiers::SHIFT), DragAction::Pan);
+        assert_eq!(bindings.action(Modifiers::CTRL), DragAction::Roll);
+        assert_eq!(bindings.action(Modifiers::ALT), DragAction::ConstrainAxis);
+        // unbound combination falls back to rotate
+        let both = Modifiers {
+            shift: true,
+            ctrl: true,
+            alt: false,
+        };
+        assert_eq!(bindings.action(both), DragAction::Rotate);
+
+        let bindings = bindings.bind(Modifiers::SHIFT, DragAction::Roll);
+        assert_eq!(bindings.action(Modifiers::SHIFT), DragAction::Roll);
+        assert_eq!(bindings.bindings.len(), 3);
+        assert_eq!(constrain_drag(3.0, -5.0), (0.0, -5.0));
+        assert_eq!(constrain_drag(4.0, 2.0), (4.0, 0.0));
+    }
+
+    #[test]
+    fn test_is_click() {
+        assert!(is_click((100.0, 100.0), 103.0, 102.0, 1.0));
+        assert!(!is_click((100.0, 100.0), 106.0, 100.0, 1.0));
+        // slop scales with dpi
+        assert!(is_click((100.0, 100.0), 106.0, 100.0, 2.0));
+    }
+
     #[test]
     fn test_zoom_from_scroll() {
-        let mat = Mat4::IDENTITY;
+        let distance = 2.0;
         let delta = 30.0;
 
-        let zoomed_mat = zoom_from_scroll(mat, delta);
+        let zoomed = zoom_from_scroll(distance, delta);
         let expected_zoom = 1.0 + (delta * ZOOM_SPEED) as f32;
-        let expected_scale = Mat4::from_scale(Vec3::splat(expected_zoom));
-        let expected_mat = mat.mul_mat4(&expected_scale);
+        let expected_distance = distance / expected_zoom;
+
+        assert!((zoomed - expected_distance).abs() < 1e-6);
+    }
 
-        assert_matrix_elements_near_eq(zoomed_mat, expected_mat, 1e-6);
+    #[test]
+    fn test_pinch_scroll_delta() {
+        // spreading fingers to double distance halves camera distance
+        let delta = pinch_scroll_delta(100.0, 200.0);
+        assert!((zoom_from_scroll(4.0, delta) - 2.0).abs() < 1e-5);
+        let delta = pinch_scroll_delta(100.0, 80.0);
+        assert!((zoom_from_scroll(4.0, delta) - 5.0).abs() < 1e-5);
+        assert_eq!(pinch_scroll_delta(0.0, 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_zoom_from_scroll_clamped() {
+        assert_eq!(zoom_from_scroll(2.0, 1e6), MIN_DISTANCE);
+        // zooming out past the point where 1 + zoom would go negative
+        assert_eq!(zoom_from_scroll(2.0, -2000.0), MAX_DISTANCE);
+        assert_eq!(zoom_from_scroll(2.0, -1e6), MAX_DISTANCE);
+        assert!((zoom_from_scroll(0.2, -1e6) - 4.0).abs() < 1e-4);
+        assert_eq!(zoom_from_scroll(2.0, -1900.0), MAX_DISTANCE);
     }
 }
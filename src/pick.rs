@@ -0,0 +1,242 @@
+use glam::{DVec4, Mat4, Vec3, Vec4};
+
+// convert position in window pixels to normalized device coordinates
+pub fn screen_to_ndc(x: f64, y: f64, width: f64, height: f64) -> (f32, f32) {
+    let nx = 2.0 * x / width - 1.0;
+    let ny = 1.0 - 2.0 * y / height;
+    (nx as f32, ny as f32)
+}
+
+// get ray origin and direction in model space from ndc position
+// unprojects near and far plane points through inverse of mvp
+// inverted in f64, small near / far ratio makes f32 inverse lose ~1e-3 at far zoom
+pub fn ndc_to_ray(mvp: Mat4, nx: f32, ny: f32) -> (Vec3, Vec3) {
+    let inv = mvp.as_dmat4().inverse();
+    let (nx, ny) = (nx as f64, ny as f64);
+    let near = inv * DVec4::new(nx, ny, -1.0, 1.0);
+    let far = inv * DVec4::new(nx, ny, 1.0, 1.0);
+    let near = near.truncate() / near.w;
+    let far = far.truncate() / far.w;
+    (near.as_vec3(), (far - near).normalize().as_vec3())
+}
+
+// nearest intersection of ray with sphere centered at origin
+pub fn ray_sphere_intersect(origin: Vec3, dir: Vec3, radius: f32) -> Option<Vec3> {
+    // solve |origin + t * dir| = radius for t, dir is normalized
+    let b = origin.dot(dir);
+    let c = origin.length_squared() - radius * radius;
+    let disc = b * b - c;
+    if disc < 0.0 {
+        return None;
+    }
+    let sqrt_disc = disc.sqrt();
+    let t = if -b - sqrt_disc >= 0.0 {
+        -b - sqrt_disc
+    } else {
+        -b + sqrt_disc
+    };
+    if t < 0.0 {
+        return None;
+    }
+    Some(origin + dir * t)
+}
+
+// intersection of ray with z = 0 plane of flat map, None if ray is parallel or points away
+pub fn ray_plane_intersect(origin: Vec3, dir: Vec3) -> Option<Vec3> {
+    if dir.z.abs() < 1e-9 {
+        return None;
+    }
+    let t = -origin.z / dir.z;
+    if t < 0.0 {
+        return None;
+    }
+    Some(origin + dir * t)
+}
+
+// index of candidate (index, x, y, radius) nearest to pixel x / y within its own radius
+pub fn nearest_within(
+    candidates: impl IntoIterator<Item = (usize, f32, f32, f32)>,
+    x: f32,
+    y: f32,
+) -> Option<usize> {
+    candidates
+        .into_iter()
+        .map(|(i, px, py, radius)| (i, (px - x).powi(2) + (py - y).powi(2), radius))
+        .filter(|&(_, dist, radius)| dist <= radius * radius)
+        .map(|(i, dist, _)| (i, dist))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+// project model space position to window pixels, inverse of screen_to_ndc + unprojection
+// None if position is behind the camera
+pub fn project(mvp: Mat4, pos: Vec3, width: f64, height: f64) -> Option<(f32, f32)> {
+    let clip = mvp * pos.extend(1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let (nx, ny) = (clip.x / clip.w, clip.y / clip.w);
+    let x = (nx + 1.0) * 0.5 * width as f32;
+    let y = (1.0 - ny) * 0.5 * height as f32;
+    Some((x, y))
+}
+
+// check if sphere at origin blocks line of sight from eye to position
+pub fn is_occluded(eye: Vec3, pos: Vec3, radius: f32) -> bool {
+    let to_pos = pos - eye;
+    let dist = to_pos.length();
+    if dist == 0.0 {
+        return false;
+    }
+    match ray_sphere_intersect(eye, to_pos / dist, radius) {
+        // small epsilon so positions on the surface aren't hidden by themselves
+        Some(hit) => (hit - eye).length() < dist - 1e-4,
+        None => false,
+    }
+}
+
+// left, right, bottom, top, near and far planes of clip space volume in source space of matrix
+// normals point inward and are unit length, so plane dot point is signed distance
+pub fn frustum_planes(matrix: Mat4) -> [Vec4; 6] {
+    let (x, y, z, w) = (matrix.row(0), matrix.row(1), matrix.row(2), matrix.row(3));
+    [w + x, w - x, w + y, w - y, w + z, w - z].map(|p| p / p.truncate().length())
+}
+
+// false only when sphere is entirely outside one plane, spheres near frustum corners
+// may pass while not visible, which is fine for skipping draws
+pub fn sphere_in_frustum(planes: &[Vec4; 6], center: Vec3, radius: f32) -> bool {
+    planes
+        .iter()
+        .all(|p| p.truncate().dot(center) + p.w >= -radius)
+}
+
+// bounding sphere of unit sphere mesh under transform, center and max axis scale
+pub fn bounding_sphere(transform: Mat4, radius: f32) -> (Vec3, f32) {
+    let center = transform.transform_point3(Vec3::ZERO);
+    let scale = transform
+        .x_axis
+        .truncate()
+        .length()
+        .max(transform.y_axis.truncate().length())
+        .max(transform.z_axis.truncate().length());
+    (center, radius * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_to_ndc() {
+        assert_eq!(screen_to_ndc(0.0, 0.0, 200.0, 100.0), (-1.0, 1.0));
+        assert_eq!(screen_to_ndc(100.0, 50.0, 200.0, 100.0), (0.0, 0.0));
+        assert_eq!(screen_to_ndc(200.0, 100.0, 200.0, 100.0), (1.0, -1.0));
+    }
+
+    #[test]
+    fn test_ray_sphere_intersect() {
+        let hit = ray_sphere_intersect(Vec3::new(0.0, 0.0, 2.0), -Vec3::Z, 1.0).unwrap();
+        assert!((hit - Vec3::Z).length() < 1e-6);
+
+        let miss = ray_sphere_intersect(Vec3::new(0.0, 2.0, 2.0), -Vec3::Z, 1.0);
+        assert!(miss.is_none());
+
+        let behind = ray_sphere_intersect(Vec3::new(0.0, 0.0, 2.0), Vec3::Z, 1.0);
+        assert!(behind.is_none());
+    }
+
+    #[test]
+    fn test_ray_plane_intersect() {
+        let hit = ray_plane_intersect(Vec3::new(0.5, 0.25, 2.0), -Vec3::Z).unwrap();
+        assert!((hit - Vec3::new(0.5, 0.25, 0.0)).length() < 1e-6);
+
+        assert!(ray_plane_intersect(Vec3::new(0.0, 0.0, 2.0), Vec3::Z).is_none());
+        assert!(ray_plane_intersect(Vec3::new(0.0, 0.0, 2.0), Vec3::X).is_none());
+    }
+
+    #[test]
+    fn test_nearest_within() {
+        let candidates = [
+            (0, 10.0, 10.0, 5.0),
+            (3, 14.0, 10.0, 5.0),
+            (5, 40.0, 40.0, 5.0),
+        ];
+        assert_eq!(nearest_within(candidates, 13.0, 10.0), Some(3));
+        assert_eq!(nearest_within(candidates, 9.0, 9.0), Some(0));
+        assert_eq!(nearest_within(candidates, 25.0, 25.0), None);
+
+        // larger candidate radius reaches farther
+        let candidates = [(0, 10.0, 10.0, 2.0), (1, 30.0, 10.0, 12.0)];
+        assert_eq!(nearest_within(candidates, 19.0, 10.0), Some(1));
+        assert_eq!(nearest_within(candidates, 13.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_project() {
+        let proj = Mat4::perspective_rh_gl(1.25, 1.0, 0.1, 10.0);
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 2.0), Vec3::ZERO, Vec3::Y);
+        let mvp = proj * view;
+
+        // globe center projects to window center
+        let (x, y) = project(mvp, Vec3::ZERO, 200.0, 100.0).unwrap();
+        assert!((x - 100.0).abs() < 1e-4 && (y - 50.0).abs() < 1e-4);
+
+        // +y is up on screen, +x is right
+        let (x, y) = project(mvp, Vec3::new(0.5, 0.5, 0.0), 200.0, 100.0).unwrap();
+        assert!(x > 100.0 && y < 50.0);
+
+        // behind camera
+        assert!(project(mvp, Vec3::new(0.0, 0.0, 3.0), 200.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_is_occluded() {
+        let eye = Vec3::new(0.0, 0.0, 2.0);
+        assert!(!is_occluded(eye, Vec3::Z, 1.0));
+        assert!(is_occluded(eye, -Vec3::Z, 1.0));
+        assert!(!is_occluded(eye, Vec3::new(0.0, 0.0, 1.5), 1.0));
+    }
+
+    #[test]
+    fn test_ndc_to_ray_center() {
+        let proj = Mat4::perspective_rh_gl(1.25, 1.0, 0.1, 10.0);
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 2.0), Vec3::ZERO, Vec3::Y);
+        let (origin, dir) = ndc_to_ray(proj * view, 0.0, 0.0);
+        let hit = ray_sphere_intersect(origin, dir, 1.0).unwrap();
+        assert!((hit - Vec3::Z).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_sphere_in_frustum() {
+        // identity planes bound the clip space cube from -1 to 1
+        let planes = frustum_planes(Mat4::IDENTITY);
+        assert_eq!(planes[0], Vec4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(planes[5], Vec4::new(0.0, 0.0, -1.0, 1.0));
+        assert!(sphere_in_frustum(&planes, Vec3::ZERO, 0.1));
+        assert!(sphere_in_frustum(&planes, Vec3::new(1.5, 0.0, 0.0), 1.0));
+        assert!(!sphere_in_frustum(&planes, Vec3::new(3.0, 0.0, 0.0), 1.0));
+        assert!(!sphere_in_frustum(&planes, Vec3::new(0.0, 0.0, -2.5), 1.0));
+
+        // camera at z = 2 looking toward origin
+        let proj = Mat4::perspective_rh_gl(1.25, 1.0, 0.1, 10.0);
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 2.0), Vec3::ZERO, Vec3::Y);
+        let planes = frustum_planes(proj * view);
+        assert!(sphere_in_frustum(&planes, Vec3::ZERO, 1.0));
+        // behind camera, past far plane and far off to the side
+        assert!(!sphere_in_frustum(&planes, Vec3::new(0.0, 0.0, 4.0), 1.0));
+        assert!(!sphere_in_frustum(&planes, Vec3::new(0.0, 0.0, -12.0), 1.0));
+        assert!(!sphere_in_frustum(&planes, Vec3::new(20.0, 0.0, 0.0), 1.0));
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let transform = Mat4::from_scale_rotation_translation(
+            Vec3::new(1.0, 3.0, 2.0),
+            glam::Quat::from_rotation_y(0.7),
+            Vec3::new(5.0, 0.0, -1.0),
+        );
+        let (center, radius) = bounding_sphere(transform, 0.5);
+        assert!((center - Vec3::new(5.0, 0.0, -1.0)).length() < 1e-5);
+        assert!((radius - 1.5).abs() < 1e-5);
+    }
+}
@@ -1,53 +1,974 @@
-use crate::gl_wrap::{Bind, Buffer, Drop, Program, VertexArray};
+use crate::geo::lat_lon_to_xyz;
+use crate::gl_wrap::{
+    blend_over, Bind, Buffer, BufferUsage, Drop, ElementBuffer, Program, UniformFloat, UniformVec4,
+    VertexArray, VertexLayout,
+};
+use glam::{Vec3, Vec4};
 use glow::HasContext;
+use std::collections::HashSet;
 
 pub struct Points {
     pub program: Program,
     pub buffer: Buffer,
     pub vao: VertexArray,
+    // point size in logical pixels, uniform holds physical size for current dpi
+    pub size: f32,
+    pub size_uniform: UniformFloat,
+    // contrasting ring around each point, width in logical pixels like size
+    pub outline_width: f32,
+    pub outline_width_uniform: UniformFloat,
+    pub outline_color: UniformVec4,
+    // width of feathered edge in logical pixels, drawn with blending when above 0
+    pub softness: f32,
+    pub softness_uniform: UniformFloat,
+    // radial push in globe radii so points on the surface draw over globe,
+    // 0 by default so positions aren't moved
+    pub surface_offset: UniformFloat,
+    // per point times, points outside time window aren't drawn
+    pub time_buffer: Buffer,
+    // copy of uploaded times, uploaded again after context restore
+    pub times: Vec<f32>,
+    pub time_start: UniformFloat,
+    pub time_end: UniformFloat,
+    time_location: Option<u32>,
+    // rgba per point, separate from positions so either can change alone
+    pub color_buffer: Buffer,
+    color_location: Option<u32>,
+    // physical pixel size per point, points use size uniform until set for every point
+    pub size_buffer: Buffer,
+    size_location: Option<u32>,
+    // colors and sizes came from set_typed, cleared when flat data replaces typed points
+    typed: bool,
+    // set_typed uploaded colors and sizes for the positions passed to next draw
+    typed_pending: bool,
+    position_location: u32,
+    // layout of data from upload_raw, None for xyz positions from draw / set_lat_lon
+    pub raw_layout: Option<VertexLayout>,
+    // cap on appended point count, oldest points are overwritten once reached
+    pub max_points: Option<usize>,
+    // point index next append writes to once buffer is full at max_points
+    ring_head: usize,
+    // smooth motion between point updates, None draws each update as is
+    pub interpolation: Option<Interpolation>,
+    // draw back to front for blending translucent points, None draws in buffer order
+    pub alpha_sort: Option<AlphaSort>,
+    // blended points test depth without writing it, set by VisGl while an opaque globe
+    // with depth prepass is drawn before them, see VisBuilder::with_depth_prepass
+    pub depth_prepass: bool,
+    // indices of selected points, drawn larger with selection outline
+    pub selected: HashSet<usize>,
+    // per point 0 / 1 flags, rebuilt from selected set when it or point count changes
+    selection_buffer: Buffer,
+    selection_location: Option<u32>,
+    selection_dirty: bool,
+    pub selection_scale: UniformFloat,
+    pub selection_color: UniformVec4,
+    // logical pixels like outline width, uniform holds physical width
+    pub selection_width: f32,
+    pub selection_width_uniform: UniformFloat,
+}
+
+// single point from VisState::update_points_typed, an alternative to flat xyz data
+// size is in logical pixels like point size, 0 uses point size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    pub pos: [f32; 3],
+    pub color: [f32; 4],
+    pub size: f32,
+}
+
+impl Point {
+    // point at position in default color and size
+    pub fn new(pos: [f32; 3]) -> Self {
+        Self {
+            pos,
+            color: DEFAULT_POINT_COLOR,
+            size: 0.0,
+        }
+    }
+}
+
+// per frame depth sort of points through an index buffer, so translucent points
+// blend correctly with non additive blending. sorts on cpu and re-uploads all indices
+// every frame, prefer additive blending (order independent) for large point sets
+pub struct AlphaSort {
+    pub indices: ElementBuffer,
+    // camera position in model space, points are sorted by distance from it
+    pub camera: Vec3,
+    // cpu copy of uploaded xyz positions, None when stale and read back before next sort
+    positions: Option<Vec<f32>>,
+    order: Vec<u32>,
+}
+
+impl AlphaSort {
+    pub fn new(gl: &glow::Context) -> Result<Self, PointsError> {
+        Ok(Self {
+            indices: ElementBuffer::new(gl, BufferUsage::Stream)?,
+            camera: Vec3::ZERO,
+            positions: None,
+            order: vec![],
+        })
+    }
+}
+
+// last two distinct point updates (keyframes) and when they arrived, drawn positions
+// are lerped from previous to current keyframe over the interval between them.
+// motion lags one keyframe behind data so there's always a target to move towards
+pub struct Interpolation {
+    prev: Vec<f32>,
+    current: Vec<f32>,
+    prev_ms: f32,
+    current_ms: f32,
+    // lerped positions for current frame, reused to avoid allocating each frame
+    frame: Vec<f32>,
+    // ms since interpolation was enabled, clock for keyframe times
+    ms: f32,
+}
+
+impl Interpolation {
+    pub fn new() -> Self {
+        Self {
+            prev: vec![],
+            current: vec![],
+            prev_ms: 0.0,
+            current_ms: 0.0,
+            frame: vec![],
+            ms: 0.0,
+        }
+    }
+
+    // advance clock by frame delta, keeps keyframe times in step with other animations
+    pub fn step(&mut self, delta_ms: f32) {
+        self.ms += delta_ms;
+    }
+
+    pub fn elapsed_ms(&self) -> f32 {
+        self.ms
+    }
+
+    // still between keyframes, drawn positions change each frame until current is reached
+    pub fn is_moving(&self) -> bool {
+        let interval = self.current_ms - self.prev_ms;
+        !self.prev.is_empty()
+            && self.prev.len() == self.current.len()
+            && interval > 0.0
+            && self.ms < self.current_ms + interval
+    }
+
+    // positions to draw at ms. data starts a new keyframe, callers only pass data when it
+    // changed. None keeps moving towards current keyframe. point count changes can't be
+    // matched up, so the new keyframe is drawn as is until the next one arrives
+    pub fn frame(&mut self, data: Option<&[f32]>, ms: f32) -> Option<&[f32]> {
+        if let Some(d) = data {
+            std::mem::swap(&mut self.prev, &mut self.current);
+            self.current.clear();
+            self.current.extend_from_slice(d);
+            self.prev_ms = self.current_ms;
+            self.current_ms = ms;
+        }
+        if self.prev.len() != self.current.len() {
+            return Some(&self.current);
+        }
+        let interval = self.current_ms - self.prev_ms;
+        let t = if interval > 0.0 {
+            ((ms - self.current_ms) / interval).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.frame.clear();
+        self.frame.extend(
+            self.prev
+                .iter()
+                .zip(&self.current)
+                .map(|(a, b)| a + (b - a) * t),
+        );
+        Some(&self.frame)
+    }
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// color of points without per point colors
+pub const DEFAULT_POINT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+// point size in logical pixels at unit clip space depth
+pub const DEFAULT_POINT_SIZE: f32 = 20.0;
+
+// outline color when none given, dark to stand out on light globes
+pub const DEFAULT_OUTLINE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+// selected points are drawn larger with a yellow outline in logical pixels
+pub const DEFAULT_SELECTION_SCALE: f32 = 1.5;
+pub const DEFAULT_SELECTION_COLOR: [f32; 4] = [1.0, 0.8, 0.0, 1.0];
+pub const DEFAULT_SELECTION_WIDTH: f32 = 3.0;
+
+// sizes passed to setters are in logical pixels so features look the same across dpis,
+// scaled to physical pixels by device pixel ratio when uploaded
+pub fn logical_to_physical(size: f32, dpi: f64) -> f32 {
+    (size as f64 * dpi) as f32
 }
 
 impl Points {
     pub fn new(gl: &glow::Context, shader_version: &str) -> Result<Self, PointsError> {
-        // compile program from strings
-        let program = Program::new_from_sources(
+        Self::new_with_shaders(
             gl,
             shader_version,
             include_str!("../shaders/point-vert.glsl"),
             include_str!("../shaders/point-frag.glsl"),
-        )?;
+        )
+    }
+
+    // create points with custom shaders, must declare same attributes / uniforms as built in
+    pub fn new_with_shaders(
+        gl: &glow::Context,
+        shader_version: &str,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<Self, PointsError> {
+        // compile program from strings
+        let program =
+            Program::new_from_sources(gl, shader_version, vertex_source, fragment_source)?;
         // init empty buffer
-        let buffer = Buffer::new(gl, glow::DYNAMIC_DRAW)?;
+        let buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
         // init vao and setup attributes
         let vao = VertexArray::new(gl)?;
         program.bind(gl);
         buffer.bind(gl);
         vao.bind(gl);
-        VertexArray::set_attrib(gl, &program, "position", 3, 3, 0)?;
+        let position_location = VertexArray::set_attrib(gl, &program, "position", 3, 3, 0)?;
+        // custom shaders may leave out time and color
+        let time_buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        time_buffer.bind(gl);
+        let time_location = VertexArray::set_optional_attrib(gl, &program, "time", 1, 1, 0)?;
+        let color_buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        color_buffer.bind(gl);
+        let color_location = VertexArray::set_optional_attrib(gl, &program, "color", 4, 4, 0)?;
+        let size_buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        size_buffer.bind(gl);
+        let size_location = VertexArray::set_optional_attrib(gl, &program, "size", 1, 1, 0)?;
+        let selection_buffer = Buffer::new(gl, BufferUsage::Dynamic)?;
+        selection_buffer.bind(gl);
+        let selection_location =
+            VertexArray::set_optional_attrib(gl, &program, "selected", 1, 1, 0)?;
+        // default window includes all times
+        let time_start = UniformFloat::new("timeStart", f32::MIN);
+        let time_end = UniformFloat::new("timeEnd", f32::MAX);
+        let size = DEFAULT_POINT_SIZE;
+        let size_uniform = UniformFloat::new("pointSize", logical_to_physical(size, 1.0));
+        // no outline until set
+        let outline_width_uniform = UniformFloat::new("outlineWidth", 0.0);
+        let outline_color = UniformVec4::new("outlineColor", Vec4::from(DEFAULT_OUTLINE_COLOR));
+        let surface_offset = UniformFloat::new("surfaceOffset", 0.0);
+        let selection_width = DEFAULT_SELECTION_WIDTH;
         Ok(Self {
             program,
             buffer,
             vao,
+            size,
+            size_uniform,
+            outline_width: 0.0,
+            outline_width_uniform,
+            outline_color,
+            softness: 0.0,
+            softness_uniform: UniformFloat::new("pointSoftness", 0.0),
+            surface_offset,
+            time_buffer,
+            times: vec![],
+            time_start,
+            time_end,
+            time_location,
+            color_buffer,
+            color_location,
+            size_buffer,
+            size_location,
+            typed: false,
+            typed_pending: false,
+            position_location,
+            raw_layout: None,
+            max_points: None,
+            ring_head: 0,
+            interpolation: None,
+            alpha_sort: None,
+            depth_prepass: false,
+            selected: HashSet::new(),
+            selection_buffer,
+            selection_location,
+            selection_dirty: false,
+            selection_scale: UniformFloat::new("selectionScale", DEFAULT_SELECTION_SCALE),
+            selection_color: UniformVec4::new(
+                "selectionColor",
+                Vec4::from(DEFAULT_SELECTION_COLOR),
+            ),
+            selection_width,
+            selection_width_uniform: UniformFloat::new(
+                "selectionWidth",
+                logical_to_physical(selection_width, 1.0),
+            ),
         })
     }
 
+    // bind program and set all point uniforms
+    pub fn apply_uniforms(&self, gl: &glow::Context) -> Result<(), PointsError> {
+        self.program.bind(gl);
+        self.size_uniform.apply_optional(gl, &self.program);
+        self.time_start.apply_optional(gl, &self.program);
+        self.time_end.apply_optional(gl, &self.program);
+        self.outline_width_uniform.apply_optional(gl, &self.program);
+        self.outline_color.apply_optional(gl, &self.program);
+        self.surface_offset.apply_optional(gl, &self.program);
+        self.selection_scale.apply_optional(gl, &self.program);
+        self.selection_color.apply_optional(gl, &self.program);
+        self.selection_width_uniform
+            .apply_optional(gl, &self.program);
+        self.softness_uniform.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // set time for each point, in same order as positions
+    // points without a time are treated as time 0
+    pub fn set_times(&mut self, gl: &glow::Context, times: &[f32]) {
+        self.time_buffer.set_data(gl, times);
+        self.times.clear();
+        self.times.extend_from_slice(times);
+    }
+
+    // upload times, styling and selection of points from a lost context.
+    // positions, colors and sizes were only kept in lost buffers and must be set again
+    pub fn restore_from(
+        &mut self,
+        gl: &glow::Context,
+        lost: &Points,
+        dpi: f64,
+    ) -> Result<(), PointsError> {
+        self.outline_width = lost.outline_width;
+        self.outline_color.data = lost.outline_color.data;
+        self.softness = lost.softness;
+        self.surface_offset.data = lost.surface_offset.data;
+        self.time_start.data = lost.time_start.data;
+        self.time_end.data = lost.time_end.data;
+        self.selection_scale.data = lost.selection_scale.data;
+        self.selection_color.data = lost.selection_color.data;
+        self.selection_width = lost.selection_width;
+        // rescales logical widths for dpi along with size
+        self.set_point_size(gl, lost.size, dpi)?;
+        self.apply_uniforms(gl)?;
+        self.set_times(gl, &lost.times);
+        self.set_usage(lost.buffer.usage);
+        self.max_points = lost.max_points;
+        self.set_interpolation(lost.interpolation.is_some());
+        self.set_alpha_sort(gl, lost.alpha_sort.is_some())?;
+        self.selected = lost.selected.clone();
+        self.selection_dirty = true;
+        Ok(())
+    }
+
+    // set rgba color for each point without touching position buffer
+    // updates in place when colors fit current allocation, avoiding reallocation
+    pub fn set_colors(&mut self, gl: &glow::Context, colors: &[f32]) {
+        self.color_buffer.write(gl, colors);
+    }
+
+    // set size in logical pixels for each point, uploaded as physical pixels for dpi.
+    // sizes of 0 use point size, as do all points until every point has a size
+    pub fn set_sizes(&mut self, gl: &glow::Context, sizes: &[f32], dpi: f64) {
+        let sizes: Vec<f32> = sizes.iter().map(|s| logical_to_physical(*s, dpi)).collect();
+        self.size_buffer.write(gl, &sizes);
+    }
+
+    // upload colors and sizes of typed points, appending their positions for draw_slice
+    pub fn set_typed(
+        &mut self,
+        gl: &glow::Context,
+        points: &[Point],
+        dpi: f64,
+        positions: &mut Vec<f32>,
+    ) {
+        let (mut colors, mut sizes) = (vec![], vec![]);
+        flatten_points(points, positions, &mut colors, &mut sizes);
+        self.set_colors(gl, &colors);
+        self.set_sizes(gl, &sizes, dpi);
+        self.typed_pending = true;
+    }
+
+    // only draw points with start <= time <= end
+    pub fn set_time_window(
+        &mut self,
+        gl: &glow::Context,
+        start: f32,
+        end: f32,
+    ) -> Result<(), PointsError> {
+        self.time_start.data = start;
+        self.time_end.data = end;
+        self.apply_uniforms(gl)
+    }
+
+    // set point size in logical pixels, uploaded as physical pixels for dpi
+    pub fn set_point_size(
+        &mut self,
+        gl: &glow::Context,
+        size: f32,
+        dpi: f64,
+    ) -> Result<(), PointsError> {
+        self.size = size;
+        self.size_uniform.data = logical_to_physical(size, dpi);
+        // outlines are also in logical pixels, keep them in step with dpi changes
+        self.outline_width_uniform.data = logical_to_physical(self.outline_width, dpi);
+        self.selection_width_uniform.data = logical_to_physical(self.selection_width, dpi);
+        self.softness_uniform.data = logical_to_physical(self.softness, dpi);
+        self.program.bind(gl);
+        self.size_uniform.apply_optional(gl, &self.program);
+        self.outline_width_uniform.apply_optional(gl, &self.program);
+        self.selection_width_uniform
+            .apply_optional(gl, &self.program);
+        self.softness_uniform.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // fade point alpha over width in logical pixels at edge, antialiasing circles
+    // independent of msaa. points are blended over what's drawn before them while
+    // above 0, 0 draws hard edged points without blending
+    pub fn set_softness(
+        &mut self,
+        gl: &glow::Context,
+        softness: f32,
+        dpi: f64,
+    ) -> Result<(), PointsError> {
+        self.softness = softness.max(0.0);
+        self.softness_uniform.data = logical_to_physical(self.softness, dpi);
+        self.program.bind(gl);
+        self.softness_uniform.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // selected points are drawn scaled by scale with outline of color and width
+    // in logical pixels, replacing regular outline
+    pub fn set_selection_style(
+        &mut self,
+        gl: &glow::Context,
+        color: [f32; 4],
+        scale: f32,
+        width: f32,
+        dpi: f64,
+    ) -> Result<(), PointsError> {
+        self.selection_color.data = Vec4::from(color);
+        self.selection_scale.data = scale.max(0.0);
+        self.selection_width = width.max(0.0);
+        self.selection_width_uniform.data = logical_to_physical(self.selection_width, dpi);
+        self.apply_uniforms(gl)
+    }
+
+    // add point index to selection, indices past point count are kept
+    // and drawn selected once enough points are uploaded
+    pub fn select(&mut self, index: usize) {
+        self.selection_dirty |= self.selected.insert(index);
+    }
+
+    pub fn deselect(&mut self, index: usize) {
+        self.selection_dirty |= self.selected.remove(&index);
+    }
+
+    // flip selection of point, returns true if point is now selected
+    pub fn toggle(&mut self, index: usize) -> bool {
+        if self.selected.remove(&index) {
+            self.selection_dirty = true;
+            return false;
+        }
+        self.select(index);
+        true
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection_dirty |= !self.selected.is_empty();
+        self.selected.clear();
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.contains(&index)
+    }
+
+    // upload selection flags for count points if selection or count changed
+    fn upload_selection(&mut self, gl: &glow::Context, count: usize) {
+        if !self.selection_dirty && self.selection_buffer.len == count {
+            return;
+        }
+        self.selection_buffer
+            .set_data(gl, &selection_flags(&self.selected, count));
+        self.selection_dirty = false;
+    }
+
+    // draw ring of color around each point, width in logical pixels
+    // None removes outline
+    pub fn set_outline(
+        &mut self,
+        gl: &glow::Context,
+        color: Option<[f32; 4]>,
+        width: f32,
+        dpi: f64,
+    ) -> Result<(), PointsError> {
+        match color {
+            Some(color) => {
+                self.outline_color.data = Vec4::from(color);
+                self.outline_width = width;
+            }
+            None => self.outline_width = 0.0,
+        }
+        self.outline_width_uniform.data = logical_to_physical(self.outline_width, dpi);
+        self.program.bind(gl);
+        self.outline_width_uniform.apply_optional(gl, &self.program);
+        self.outline_color.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
+    // set radial push away from globe in globe radii, 0 draws points at their positions
+    pub fn set_surface_offset(
+        &mut self,
+        gl: &glow::Context,
+        offset: f32,
+    ) -> Result<(), PointsError> {
+        self.surface_offset.data = offset;
+        self.program.bind(gl);
+        self.surface_offset.apply_optional(gl, &self.program);
+        Ok(())
+    }
+
     pub fn draw(&mut self, gl: &glow::Context, data: Option<Vec<f32>>) {
+        self.draw_slice(gl, data.as_deref());
+    }
+
+    // allocate position and color buffers for count points up front, so updates up to that
+    // many points overwrite in place instead of reallocating, including the first one
+    pub fn reserve(&mut self, gl: &glow::Context, count: usize) -> Result<(), PointsError> {
+        self.buffer.reserve(gl, 3 * count)?;
+        self.color_buffer.reserve(gl, 4 * count)?;
+        Ok(())
+    }
+
+    // usage hint for position buffer, takes effect on next upload
+    pub fn set_usage(&mut self, usage: BufferUsage) {
+        if usage != self.buffer.usage {
+            // forget allocation so next write reallocates with new hint instead of writing in place
+            self.buffer.capacity = 0;
+        }
+        self.buffer.usage = usage;
+    }
+
+    // smoothly move points between updates instead of jumping when point data updates
+    // slower than frame rate, disabling draws each update as is.
+    // keyframe times come from step_interpolation
+    pub fn set_interpolation(&mut self, interpolation: bool) {
+        self.interpolation = interpolation.then(Interpolation::new);
+    }
+
+    // points are moving between interpolation keyframes
+    pub fn is_interpolating(&self) -> bool {
+        self.interpolation.as_ref().is_some_and(|i| i.is_moving())
+    }
+
+    // advance interpolation clock, called once per frame with frame delta
+    pub fn step_interpolation(&mut self, delta_ms: f32) {
+        if let Some(interp) = self.interpolation.as_mut() {
+            interp.step(delta_ms);
+        }
+    }
+
+    // sort points back to front from camera each frame and blend them over what's drawn
+    // before them, for translucent point colors. expensive for large point sets.
+    // interleaved data from upload_raw is drawn unsorted
+    pub fn set_alpha_sort(&mut self, gl: &glow::Context, sort: bool) -> Result<(), PointsError> {
+        match (sort, self.alpha_sort.take()) {
+            (true, None) => self.alpha_sort = Some(AlphaSort::new(gl)?),
+            (true, Some(alpha_sort)) => self.alpha_sort = Some(alpha_sort),
+            (false, Some(alpha_sort)) => alpha_sort.indices.drop(gl),
+            (false, None) => {}
+        }
+        Ok(())
+    }
+
+    // camera position in model space used for alpha sort, set before each draw
+    pub fn set_sort_camera(&mut self, camera: Vec3) {
+        if let Some(alpha_sort) = self.alpha_sort.as_mut() {
+            alpha_sort.camera = camera;
+        }
+    }
+
+    // positions changed without a cpu copy, read back on next sorted draw
+    fn invalidate_sort(&mut self) {
+        if let Some(alpha_sort) = self.alpha_sort.as_mut() {
+            alpha_sort.positions = None;
+        }
+    }
+
+    // draw from borrowed point data, avoids passing ownership of a new vec each frame
+    pub fn draw_slice(&mut self, gl: &glow::Context, data: Option<&[f32]>) {
         self.program.bind(gl);
         self.buffer.bind(gl);
         self.vao.bind(gl);
+        // flat data after typed points would otherwise draw with their stale colors and sizes
+        if data.is_some() {
+            if self.typed && !self.typed_pending {
+                self.color_buffer.len = 0;
+                self.size_buffer.len = 0;
+            }
+            self.typed = std::mem::take(&mut self.typed_pending);
+        }
+        // taken out while in use so buffer can be updated from its frame data
+        let mut interpolation = self.interpolation.take();
+        let data = match interpolation.as_mut() {
+            Some(interp) => {
+                let ms = interp.elapsed_ms();
+                interp.frame(data, ms)
+            }
+            None => data,
+        };
         if let Some(d) = data {
-            self.buffer.set_data(gl, &d);
+            self.reset_layout(gl);
+            self.buffer.write(gl, d);
+            self.ring_head = 0;
+            if let Some(alpha_sort) = self.alpha_sort.as_mut() {
+                let positions = alpha_sort.positions.get_or_insert_with(Vec::new);
+                positions.clear();
+                positions.extend_from_slice(d);
+            }
         }
+        self.interpolation = interpolation;
+        let (count, raw_time, raw_color, raw_size) = match &self.raw_layout {
+            Some(layout) => (
+                layout.vertex_count(self.buffer.len).unwrap_or(0),
+                layout.contains("time"),
+                layout.contains("color"),
+                layout.contains("size"),
+            ),
+            None => (self.buffer.len / 3, false, false, false),
+        };
         unsafe {
-            gl.draw_arrays(glow::POINTS, 0, (self.buffer.len / 3) as i32);
+            // read times from buffer only if every point has one, else use constant 0
+            if let Some(location) = self.time_location {
+                if raw_time || self.time_buffer.len >= count {
+                    gl.enable_vertex_attrib_array(location);
+                } else {
+                    gl.disable_vertex_attrib_array(location);
+                    gl.vertex_attrib_1_f32(location, 0.0);
+                }
+            }
+            // same for colors, using default color
+            if let Some(location) = self.color_location {
+                if raw_color || self.color_buffer.len >= count * 4 {
+                    gl.enable_vertex_attrib_array(location);
+                } else {
+                    gl.disable_vertex_attrib_array(location);
+                    let [r, g, b, a] = DEFAULT_POINT_COLOR;
+                    gl.vertex_attrib_4_f32(location, r, g, b, a);
+                }
+            }
+            // and sizes, where 0 falls back to size uniform
+            if let Some(location) = self.size_location {
+                if raw_size || self.size_buffer.len >= count {
+                    gl.enable_vertex_attrib_array(location);
+                } else {
+                    gl.disable_vertex_attrib_array(location);
+                    gl.vertex_attrib_1_f32(location, 0.0);
+                }
+            }
+        }
+        // no flag buffer needed while nothing is selected
+        if let Some(location) = self.selection_location {
+            if self.selected.is_empty() {
+                unsafe {
+                    gl.disable_vertex_attrib_array(location);
+                    gl.vertex_attrib_1_f32(location, 0.0);
+                }
+            } else {
+                self.upload_selection(gl, count);
+                unsafe {
+                    gl.enable_vertex_attrib_array(location);
+                }
+            }
         }
+        let blend = self.softness > 0.0 || self.alpha_sort.is_some();
+        if blend {
+            unsafe {
+                gl.enable(glow::BLEND);
+                // translucent points don't hide what's drawn after them with a prepass
+                if self.depth_prepass {
+                    gl.depth_mask(false);
+                }
+            }
+            blend_over(gl);
+        }
+        match self.alpha_sort.as_mut() {
+            Some(alpha_sort) if self.raw_layout.is_none() => {
+                let positions = alpha_sort
+                    .positions
+                    .get_or_insert_with(|| self.buffer.read_back(gl));
+                back_to_front(positions, alpha_sort.camera, &mut alpha_sort.order);
+                // element buffer binding is stored in bound vao
+                alpha_sort.indices.set_data(gl, &alpha_sort.order);
+                alpha_sort.indices.draw(gl, glow::POINTS);
+            }
+            _ => unsafe {
+                gl.draw_arrays(glow::POINTS, 0, count as i32);
+            },
+        }
+        if blend {
+            unsafe {
+                gl.disable(glow::BLEND);
+                gl.depth_mask(true);
+            }
+        }
+    }
+
+    // add xyz positions after existing points without re-uploading them, for streaming data
+    // buffer grows by doubling. with max_points set, once full each new point overwrites the
+    // oldest, so buffer order is no longer arrival order. per point times / colors aren't
+    // appended, they fall back to defaults unless set for every point
+    pub fn append(&mut self, gl: &glow::Context, positions: &[f32]) -> Result<(), PointsError> {
+        if !positions.len().is_multiple_of(3) {
+            return Err(PointsError::AppendLength(positions.len()));
+        }
+        // interleaved raw data can't be extended with xyz, start over from appended points
+        if self.raw_layout.is_some() {
+            self.buffer.len = 0;
+            self.ring_head = 0;
+        }
+        self.reset_layout(gl);
+        self.invalidate_sort();
+        let Some(max) = self.max_points else {
+            let len = self.buffer.len + positions.len();
+            if len > self.buffer.capacity {
+                self.buffer.reserve(gl, len.max(2 * self.buffer.capacity))?;
+            }
+            self.buffer.set_sub_data(gl, self.buffer.len, positions);
+            self.buffer.len = len;
+            return Ok(());
+        };
+        if max == 0 {
+            return Ok(());
+        }
+        // only last max points survive a large append
+        let skip = (positions.len() / 3).saturating_sub(max);
+        let positions = &positions[3 * skip..];
+        self.buffer.reserve(gl, 3 * max)?;
+        // fill free space, then overwrite oldest points in at most two runs
+        let mut count = self.buffer.len / 3;
+        let fill = (max - count).min(positions.len() / 3);
+        self.buffer
+            .set_sub_data(gl, 3 * count, &positions[..3 * fill]);
+        count += fill;
+        let mut rest = &positions[3 * fill..];
+        while !rest.is_empty() {
+            let run = (max - self.ring_head).min(rest.len() / 3);
+            self.buffer
+                .set_sub_data(gl, 3 * self.ring_head, &rest[..3 * run]);
+            self.ring_head = (self.ring_head + run) % max;
+            rest = &rest[3 * run..];
+        }
+        self.buffer.len = 3 * count;
+        Ok(())
+    }
+
+    // cap appended points at max, None for unbounded. oldest points roll off a lower cap,
+    // and kept points are moved into arrival order so appends continue from the newest.
+    // reads positions back from gpu when points must be reordered
+    pub fn set_max_points(&mut self, gl: &glow::Context, max: Option<usize>) {
+        self.max_points = max;
+        let count = self.buffer.len / 3;
+        let keep = max.map_or(count, |max| count.min(max));
+        if self.raw_layout.is_some() || (keep == count && self.ring_head == 0) {
+            return;
+        }
+        // ring only wraps once full, so oldest point is at ring head
+        let mut positions = self.buffer.read_back(gl);
+        positions.rotate_left(3 * self.ring_head);
+        self.buffer
+            .set_sub_data(gl, 0, &positions[3 * (count - keep)..]);
+        self.buffer.len = 3 * keep;
+        self.ring_head = 0;
+        self.invalidate_sort();
     }
+
+    // upload interleaved vertex data with custom attribute layout in one call, bypassing
+    // the xyz only format. layout attribute names must be declared in the points shader,
+    // built in shader has position (vec3), color (vec4), time (float) and physical
+    // pixel size (float). data length must
+    // be a whole number of vertices, each stride floats with attributes at float offsets.
+    // built in attributes left out of layout use their separate buffers / defaults,
+    // next xyz upload through draw / set_lat_lon restores the default layout
+    pub fn upload_raw(
+        &mut self,
+        gl: &glow::Context,
+        data: &[f32],
+        layout: &VertexLayout,
+    ) -> Result<(), PointsError> {
+        if layout.vertex_count(data.len()).is_none() {
+            return Err(PointsError::RawLength(data.len(), layout.stride));
+        }
+        // check all attributes before changing vao so a bad layout leaves points unchanged
+        for attrib in &layout.attribs {
+            VertexArray::attrib_location(gl, &self.program, &attrib.name)?;
+        }
+        self.reset_layout(gl);
+        self.program.bind(gl);
+        self.vao.bind(gl);
+        self.buffer.set_data(gl, data);
+        self.ring_head = 0;
+        self.invalidate_sort();
+        for attrib in &layout.attribs {
+            VertexArray::set_attrib(
+                gl,
+                &self.program,
+                &attrib.name,
+                attrib.size,
+                layout.stride,
+                attrib.offset,
+            )?;
+        }
+        self.raw_layout = Some(layout.clone());
+        Ok(())
+    }
+
+    // point attributes back at separate position / time / color buffers after raw upload
+    fn reset_layout(&mut self, gl: &glow::Context) {
+        if self.raw_layout.take().is_none() {
+            return;
+        }
+        let fsize = std::mem::size_of::<f32>() as i32;
+        self.vao.bind(gl);
+        let attribs = [
+            (&self.buffer, Some(self.position_location), 3),
+            (&self.time_buffer, self.time_location, 1),
+            (&self.color_buffer, self.color_location, 4),
+            (&self.size_buffer, self.size_location, 1),
+        ];
+        for (buffer, location, size) in attribs {
+            let Some(location) = location else {
+                continue;
+            };
+            buffer.bind(gl);
+            unsafe {
+                gl.vertex_attrib_pointer_f32(location, size, glow::FLOAT, false, fsize * size, 0);
+            }
+        }
+        unsafe {
+            gl.enable_vertex_attrib_array(self.position_location);
+        }
+    }
+
+    // upload xyz positions without drawing
+    pub fn set_positions(&mut self, gl: &glow::Context, positions: &[f32]) {
+        self.reset_layout(gl);
+        self.buffer.write(gl, positions);
+        self.ring_head = 0;
+        self.invalidate_sort();
+    }
+
+    // convert lat / lon degrees to positions at radius and upload in one call
+    pub fn set_lat_lon(
+        &mut self,
+        gl: &glow::Context,
+        lats: &[f32],
+        lons: &[f32],
+        radius: f32,
+    ) -> Result<(), PointsError> {
+        let positions = lat_lon_positions(lats, lons, radius)?;
+        self.set_positions(gl, &positions);
+        Ok(())
+    }
+
+    // get point data currently uploaded to gpu
+    // slow synchronous readback, only intended for tests / debugging and picking on click
+    pub fn read_back(&self, gl: &glow::Context) -> Vec<f32> {
+        self.buffer.read_back(gl)
+    }
+
+    // logical pixel size of each of count points as drawn, from per point sizes
+    // when set for every point and scaled up for selected points. reads sizes back
+    // from gpu, so like read_back only intended for picking on click
+    pub fn drawn_sizes(&self, gl: &glow::Context, count: usize, dpi: f64) -> Vec<f32> {
+        let sizes = match self.size_location {
+            Some(_) if self.size_buffer.len >= count => self.size_buffer.read_back(gl),
+            _ => vec![],
+        };
+        (0..count)
+            .map(|i| {
+                let size = match sizes.get(i) {
+                    Some(&s) if s > 0.0 => (s as f64 / dpi) as f32,
+                    _ => self.size,
+                };
+                if self.selected.contains(&i) {
+                    size * self.selection_scale.data
+                } else {
+                    size
+                }
+            })
+            .collect()
+    }
+}
+
+// split typed points into xyz positions, rgba colors and logical pixel sizes,
+// appending to each so buffers can be reused across frames
+pub fn flatten_points(
+    points: &[Point],
+    positions: &mut Vec<f32>,
+    colors: &mut Vec<f32>,
+    sizes: &mut Vec<f32>,
+) {
+    positions.reserve(3 * points.len());
+    colors.reserve(4 * points.len());
+    sizes.reserve(points.len());
+    for point in points {
+        positions.extend_from_slice(&point.pos);
+        colors.extend_from_slice(&point.color);
+        sizes.push(point.size);
+    }
+}
+
+// 1 for selected indices below count, 0 for all others
+pub fn selection_flags(selected: &HashSet<usize>, count: usize) -> Vec<f32> {
+    let mut flags = vec![0.0; count];
+    for &index in selected.iter().filter(|&&i| i < count) {
+        flags[index] = 1.0;
+    }
+    flags
+}
+
+// fill order with indices of xyz positions sorted farthest from camera first
+pub fn back_to_front(positions: &[f32], camera: Vec3, order: &mut Vec<u32>) {
+    let distance = |i: u32| {
+        let i = 3 * i as usize;
+        Vec3::from_slice(&positions[i..i + 3]).distance_squared(camera)
+    };
+    order.clear();
+    order.extend(0..(positions.len() / 3) as u32);
+    order.sort_by(|&a, &b| distance(b).total_cmp(&distance(a)));
+}
+
+// interleaved xyz positions from separate lat / lon arrays
+pub fn lat_lon_positions(lats: &[f32], lons: &[f32], radius: f32) -> Result<Vec<f32>, PointsError> {
+    if lats.len() != lons.len() {
+        return Err(PointsError::LatLonLength(lats.len(), lons.len()));
+    }
+    Ok(lats
+        .iter()
+        .zip(lons)
+        .flat_map(|(&lat, &lon)| lat_lon_to_xyz(lat, lon, radius))
+        .collect())
 }
 
 impl Drop for Points {
     fn drop(&self, gl: &glow::Context) {
         self.program.drop(gl);
         self.buffer.drop(gl);
+        self.time_buffer.drop(gl);
+        self.color_buffer.drop(gl);
+        self.size_buffer.drop(gl);
+        self.selection_buffer.drop(gl);
+        if let Some(alpha_sort) = &self.alpha_sort {
+            alpha_sort.indices.drop(gl);
+        }
     }
 }
 
@@ -60,4 +981,123 @@ pub enum PointsError {
     Buffer(#[from] crate::gl_wrap::BufferError),
     #[error("{0}")]
     VertexArray(#[from] crate::gl_wrap::VertexArrayError),
+    #[error("{0}")]
+    Uniform(#[from] crate::gl_wrap::UniformError),
+    #[error("Latitude count {0} doesn't match longitude count {1}")]
+    LatLonLength(usize, usize),
+    #[error("Raw data length {0} isn't a multiple of layout stride {1}")]
+    RawLength(usize, i32),
+    #[error("Appended data length {0} isn't a multiple of 3")]
+    AppendLength(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_points() {
+        let points = [
+            Point::new([1.0, 0.0, 0.0]),
+            Point {
+                pos: [0.0, 2.0, 3.0],
+                color: [0.5, 0.0, 1.0, 0.25],
+                size: 8.0,
+            },
+        ];
+        let mut positions = vec![9.0; 3];
+        let (mut colors, mut sizes) = (vec![], vec![]);
+        flatten_points(&points, &mut positions, &mut colors, &mut sizes);
+        assert_eq!(positions, [9.0, 9.0, 9.0, 1.0, 0.0, 0.0, 0.0, 2.0, 3.0]);
+        assert_eq!(colors, [1.0, 1.0, 1.0, 1.0, 0.5, 0.0, 1.0, 0.25]);
+        assert_eq!(sizes, [0.0, 8.0]);
+    }
+
+    #[test]
+    fn test_logical_to_physical() {
+        assert_eq!(logical_to_physical(20.0, 1.0), 20.0);
+        assert_eq!(logical_to_physical(20.0, 2.0), 40.0);
+        assert!((logical_to_physical(10.0, 1.5) - 15.0).abs() < 1e-6);
+        assert_eq!(logical_to_physical(0.0, 3.0), 0.0);
+    }
+
+    #[test]
+    fn test_interpolation_frame() {
+        let mut interp = Interpolation::new();
+        // first keyframe drawn as is
+        assert_eq!(
+            interp.frame(Some(&[0.0, 0.0, 0.0]), 0.0),
+            Some(&[0.0, 0.0, 0.0][..])
+        );
+        // second keyframe 100ms later starts at previous, reaching it after another 100ms
+        assert_eq!(
+            interp.frame(Some(&[2.0, 4.0, 0.0]), 100.0),
+            Some(&[0.0, 0.0, 0.0][..])
+        );
+        assert_eq!(interp.frame(None, 150.0), Some(&[1.0, 2.0, 0.0][..]));
+        assert_eq!(interp.frame(None, 200.0), Some(&[2.0, 4.0, 0.0][..]));
+        assert_eq!(interp.frame(None, 500.0), Some(&[2.0, 4.0, 0.0][..]));
+
+        // point count change falls back to drawing new data directly
+        let grown = [1.0, 1.0, 1.0, 5.0, 5.0, 5.0];
+        assert_eq!(interp.frame(Some(&grown), 600.0), Some(&grown[..]));
+        assert_eq!(interp.frame(None, 650.0), Some(&grown[..]));
+    }
+
+    #[test]
+    fn test_interpolation_moving() {
+        let mut interp = Interpolation::new();
+        interp.frame(Some(&[0.0, 0.0, 0.0]), interp.elapsed_ms());
+        assert!(!interp.is_moving());
+        // moves for one keyframe interval after second keyframe, then rests
+        interp.step(100.0);
+        interp.frame(Some(&[1.0, 0.0, 0.0]), interp.elapsed_ms());
+        assert!(interp.is_moving());
+        interp.step(99.0);
+        assert!(interp.is_moving());
+        interp.step(1.0);
+        assert!(!interp.is_moving());
+        // same keyframe time can't be lerped, drawn as is
+        interp.frame(Some(&[2.0, 0.0, 0.0]), interp.elapsed_ms());
+        interp.frame(Some(&[3.0, 0.0, 0.0]), interp.elapsed_ms());
+        assert!(!interp.is_moving());
+    }
+
+    #[test]
+    fn test_selection_flags() {
+        let selected = HashSet::from([1, 3, 9]);
+        assert_eq!(selection_flags(&selected, 4), vec![0.0, 1.0, 0.0, 1.0]);
+        assert!(selection_flags(&selected, 0).is_empty());
+    }
+
+    #[test]
+    fn test_back_to_front() {
+        let positions = [
+            0.0, 0.0, 1.0, //
+            0.0, 0.0, -1.0, //
+            1.0, 0.0, 0.0, //
+            0.0, 0.0, 0.5,
+        ];
+        let mut order = vec![];
+        back_to_front(&positions, Vec3::new(0.0, 0.0, 3.0), &mut order);
+        assert_eq!(order, vec![1, 2, 3, 0]);
+        // reused order is refilled, not appended to
+        back_to_front(&positions, Vec3::new(0.0, 0.0, -3.0), &mut order);
+        assert_eq!(order, vec![0, 3, 2, 1]);
+        back_to_front(&[], Vec3::ZERO, &mut order);
+        assert!(order.is_empty());
+    }
+
+    #[test]
+    fn test_lat_lon_positions() {
+        let positions = lat_lon_positions(&[0.0, 90.0], &[0.0, 0.0], 2.0).unwrap();
+        assert_eq!(positions.len(), 6);
+        let expected = [0.0, 0.0, 2.0, 0.0, 2.0, 0.0];
+        for (a, b) in positions.iter().zip(expected.iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        assert!(lat_lon_positions(&[0.0, 1.0], &[0.0], 1.0).is_err());
+        assert!(lat_lon_positions(&[], &[], 1.0).unwrap().is_empty());
+    }
 }
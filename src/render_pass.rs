@@ -0,0 +1,160 @@
+use crate::gl_wrap::Drop;
+use crate::pick::{bounding_sphere, frustum_planes, sphere_in_frustum};
+use glam::Mat4;
+use glow::HasContext;
+
+// camera matrices at time of draw, passed to custom render passes
+#[derive(Clone, Copy, Debug)]
+pub struct CameraView {
+    pub proj: Mat4,
+    pub view: Mat4,
+    pub model: Mat4,
+}
+
+impl CameraView {
+    // combined matrix for transforming model space positions to clip space
+    pub fn mvp(&self) -> Mat4 {
+        self.proj * self.view * self.model
+    }
+
+    // check if body drawn as sphere of radius under world transform may be on screen,
+    // skip drawing when false, e.g. for planets / moons outside the view in custom passes
+    pub fn sphere_visible(&self, transform: Mat4, radius: f32) -> bool {
+        let (center, radius) = bounding_sphere(transform, radius);
+        sphere_in_frustum(&frustum_planes(self.proj * self.view), center, radius)
+    }
+}
+
+// where a custom render pass is drawn in the frame relative to built in layers,
+// passes in the same stage are drawn in order added
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PassStage {
+    // before globe and points, e.g. backgrounds that built in layers draw over
+    BeforeLayers,
+    // between first and second built in layer, in whichever order DrawOrder gives them
+    BetweenLayers,
+    // after globe and points
+    #[default]
+    AfterLayers,
+}
+
+// depth comparison for fragments of a pass against existing depth
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthFunc {
+    // gl default, coplanar geometry drawn later loses
+    #[default]
+    Less,
+    // coplanar geometry drawn later wins, if depths match exactly
+    LessEqual,
+    // ignore depth, always draw over existing geometry
+    Always,
+}
+
+impl DepthFunc {
+    fn gl_func(self) -> u32 {
+        match self {
+            DepthFunc::Less => glow::LESS,
+            DepthFunc::LessEqual => glow::LEQUAL,
+            DepthFunc::Always => glow::ALWAYS,
+        }
+    }
+}
+
+// gl polygon offset, depth moved by factor * depth slope + units * smallest depth step.
+// only applies to filled triangles, lines and points drawn over a surface need the
+// surface pushed back instead, e.g. with VisBuilder::with_globe_polygon_offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units: f32,
+}
+
+impl PolygonOffset {
+    // pull triangles toward camera, drawing over coplanar geometry
+    pub const TOWARD: Self = Self {
+        factor: -1.0,
+        units: -1.0,
+    };
+    // push triangles away from camera, letting coplanar geometry draw over them
+    pub const AWAY: Self = Self {
+        factor: 1.0,
+        units: 1.0,
+    };
+
+    // enable offset for following triangle draws
+    pub fn enable(&self, gl: &glow::Context) {
+        unsafe {
+            gl.enable(glow::POLYGON_OFFSET_FILL);
+            gl.polygon_offset(self.factor, self.units);
+        }
+    }
+
+    pub fn disable(gl: &glow::Context) {
+        unsafe {
+            gl.disable(glow::POLYGON_OFFSET_FILL);
+        }
+    }
+}
+
+// depth settings for a render pass, so overlays on the globe surface draw over it
+// cleanly instead of z-fighting. applied before the pass draws, gl defaults restored after
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PassDepth {
+    pub func: DepthFunc,
+    pub polygon_offset: Option<PolygonOffset>,
+    // false keeps pass from hiding geometry drawn after it
+    pub write: bool,
+}
+
+impl Default for PassDepth {
+    // matches gl state built in layers draw with
+    fn default() -> Self {
+        Self {
+            func: DepthFunc::Less,
+            polygon_offset: None,
+            write: true,
+        }
+    }
+}
+
+impl PassDepth {
+    // triangles coplanar with globe surface drawn over it, still hidden by far side of globe
+    pub fn overlay() -> Self {
+        Self {
+            func: DepthFunc::LessEqual,
+            polygon_offset: Some(PolygonOffset::TOWARD),
+            write: true,
+        }
+    }
+
+    pub fn apply(&self, gl: &glow::Context) {
+        unsafe {
+            gl.depth_func(self.func.gl_func());
+            gl.depth_mask(self.write);
+        }
+        match &self.polygon_offset {
+            Some(offset) => offset.enable(gl),
+            None => PolygonOffset::disable(gl),
+        }
+    }
+
+    // back to defaults for built in layers
+    pub fn reset(gl: &glow::Context) {
+        PassDepth::default().apply(gl);
+    }
+}
+
+// user defined render pass, drawn each frame at its stage
+// gl state set by a pass isn't restored, built in passes bind what they need,
+// except depth settings from depth which are reset after the pass
+pub trait RenderPass: Drop {
+    // called once after vis gl setup, before first frame
+    fn setup(&mut self, gl: &glow::Context);
+
+    fn draw(&mut self, gl: &glow::Context, camera: &CameraView);
+
+    // depth test / offset pass is drawn with, override for overlays on globe surface
+    fn depth(&self) -> PassDepth {
+        PassDepth::default()
+    }
+}
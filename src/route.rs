@@ -0,0 +1,191 @@
+use crate::geo::{lat_lon_to_xyz, xyz_to_lat_lon};
+use crate::markers::Marker;
+use glam::{Quat, Vec3};
+
+// what a moving marker does once it reaches the end of its route
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteRepeat {
+    // stop at destination
+    Once,
+    // jump back to start and travel again
+    Loop,
+    // travel back to start, then forward again
+    PingPong,
+}
+
+// marker travelling from one lat / lon to another along the great circle between them,
+// position is computed from time so it can be sampled for any frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovingMarker {
+    // unit sphere endpoints
+    pub from: Vec3,
+    pub to: Vec3,
+    // time in ms travel starts, same clock as VisState update ms
+    pub start_ms: f32,
+    pub duration_ms: f32,
+    pub repeat: RouteRepeat,
+    pub color: [f32; 4],
+    pub size: f32,
+}
+
+impl MovingMarker {
+    pub fn new(
+        from: (f32, f32),
+        to: (f32, f32),
+        start_ms: f32,
+        duration_ms: f32,
+        repeat: RouteRepeat,
+    ) -> Self {
+        Self {
+            from: Vec3::from(lat_lon_to_xyz(from.0, from.1, 1.0)),
+            to: Vec3::from(lat_lon_to_xyz(to.0, to.1, 1.0)),
+            start_ms,
+            duration_ms,
+            repeat,
+            color: [1.0, 1.0, 1.0, 1.0],
+            size: 0.01,
+        }
+    }
+
+    pub fn with_style(mut self, color: [f32; 4], size: f32) -> Self {
+        self.color = color;
+        self.size = size;
+        self
+    }
+
+    // fraction of route travelled at ms, 0 before start
+    pub fn progress(&self, ms: f32) -> f32 {
+        if self.duration_ms <= 0.0 {
+            return 1.0;
+        }
+        let t = ((ms - self.start_ms) / self.duration_ms).max(0.0);
+        match self.repeat {
+            RouteRepeat::Once => t.min(1.0),
+            RouteRepeat::Loop => t.fract(),
+            RouteRepeat::PingPong => {
+                let t = t % 2.0;
+                if t > 1.0 {
+                    2.0 - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+
+    // position on unit sphere at ms, scale for other radii
+    pub fn position(&self, ms: f32) -> [f32; 3] {
+        // antipodal endpoints have no unique great circle, rotation arc picks one
+        let arc = Quat::from_rotation_arc(self.from, self.to);
+        let rotation = Quat::IDENTITY.slerp(arc, self.progress(ms));
+        (rotation * self.from).to_array()
+    }
+
+    pub fn lat_lon(&self, ms: f32) -> (f32, f32) {
+        xyz_to_lat_lon(self.position(ms))
+    }
+
+    pub fn marker(&self, ms: f32) -> Marker {
+        Marker {
+            pos: self.position(ms),
+            color: self.color,
+            size: self.size,
+        }
+    }
+}
+
+// set of moving markers kept in user state, sampled each frame into markers
+// from VisState::update_markers or point positions from update_points_into
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MovingMarkers {
+    pub markers: Vec<MovingMarker>,
+}
+
+impl MovingMarkers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // add marker and get its index for position queries
+    pub fn add(&mut self, marker: MovingMarker) -> usize {
+        self.markers.push(marker);
+        self.markers.len() - 1
+    }
+
+    // unit sphere position of marker at index, None if index isn't a marker
+    pub fn position(&self, index: usize, ms: f32) -> Option<[f32; 3]> {
+        self.markers.get(index).map(|marker| marker.position(ms))
+    }
+
+    // instanced markers at ms for VisState::update_markers
+    pub fn markers_at(&self, ms: f32) -> Vec<Marker> {
+        self.markers
+            .iter()
+            .map(|marker| marker.marker(ms))
+            .collect()
+    }
+
+    // append xyz positions at ms, for VisState::update_points_into
+    pub fn extend_points(&self, ms: f32, points: &mut Vec<f32>) {
+        points.extend(self.markers.iter().flat_map(|marker| marker.position(ms)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::great_circle_degrees;
+
+    #[test]
+    fn test_moving_marker_path() {
+        let marker = MovingMarker::new((0.0, 0.0), (0.0, 90.0), 1000.0, 2000.0, RouteRepeat::Once);
+        // waits at start, then moves at constant angular speed along equator
+        assert_eq!(marker.progress(0.0), 0.0);
+        let (lat, lon) = marker.lat_lon(2000.0);
+        assert!(lat.abs() < 1e-4 && (lon - 45.0).abs() < 1e-3);
+        let (lat, lon) = marker.lat_lon(5000.0);
+        assert!(lat.abs() < 1e-4 && (lon - 90.0).abs() < 1e-3);
+
+        // stays on great circle between non equatorial endpoints
+        let (from, to) = ((51.5, -0.1), (40.7, -74.0));
+        let marker = MovingMarker::new(from, to, 0.0, 100.0, RouteRepeat::Once);
+        let total = great_circle_degrees(from.0, from.1, to.0, to.1);
+        for ms in [10.0, 50.0, 90.0] {
+            let (lat, lon) = marker.lat_lon(ms);
+            let a = great_circle_degrees(from.0, from.1, lat, lon);
+            let b = great_circle_degrees(lat, lon, to.0, to.1);
+            assert!((a + b - total).abs() < 1e-2);
+            assert!((a - total * ms / 100.0).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_moving_marker_repeat() {
+        let progress = |repeat, ms| {
+            MovingMarker::new((0.0, 0.0), (0.0, 90.0), 0.0, 100.0, repeat).progress(ms)
+        };
+        assert_eq!(progress(RouteRepeat::Once, 250.0), 1.0);
+        assert!((progress(RouteRepeat::Loop, 250.0) - 0.5).abs() < 1e-5);
+        assert!((progress(RouteRepeat::PingPong, 125.0) - 0.75).abs() < 1e-5);
+        assert!((progress(RouteRepeat::PingPong, 225.0) - 0.25).abs() < 1e-5);
+
+        let mut moving = MovingMarkers::new();
+        let id = moving.add(MovingMarker::new(
+            (0.0, 0.0),
+            (90.0, 0.0),
+            0.0,
+            100.0,
+            RouteRepeat::Loop,
+        ));
+        assert!(moving.position(id + 1, 0.0).is_none());
+        let pos = moving.position(id, 100.0).unwrap();
+        assert!((pos[2] - 1.0).abs() < 1e-5);
+        let mut points = vec![];
+        moving.extend_points(50.0, &mut points);
+        assert_eq!(points.len(), 3);
+        assert_eq!(
+            moving.markers_at(50.0)[0].pos,
+            moving.position(id, 50.0).unwrap()
+        );
+    }
+}
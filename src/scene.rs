@@ -0,0 +1,253 @@
+use crate::{
+    camera::CameraState,
+    colors::CategoryColors,
+    globe::{DEFAULT_SUBDIVISIONS, MAX_SUBDIVISIONS},
+    points::DEFAULT_POINT_SIZE,
+    vis_build::VisOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// declarative vis config, deserialize from json / toml / any serde format
+// and pass to VisBuilder::from_scene. missing fields use builder defaults
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub width: f64,
+    pub height: f64,
+    // linear rgb
+    pub background: [f32; 3],
+    pub globe: SceneGlobe,
+    // None uses default view
+    pub view: Option<SceneView>,
+    // exact camera from VisGl::export_scene, takes precedence over view
+    pub camera: Option<CameraState>,
+    // point data saved inline by VisGl::export_scene
+    pub points: Option<ScenePoints>,
+    pub layers: Vec<PointLayer>,
+    pub color_scales: Vec<ColorScale>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneGlobe {
+    // linear rgb
+    pub color: [f32; 3],
+    pub subdivisions: usize,
+    // 1 for opaque
+    pub opacity: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneView {
+    pub lat: f32,
+    pub lon: f32,
+    pub distance: f32,
+}
+
+// inline xyz positions, rgba colors (empty for default color) and logical point size
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScenePoints {
+    pub positions: Vec<f32>,
+    #[serde(default)]
+    pub colors: Vec<f32>,
+    pub size: f32,
+}
+
+// point data file loaded by user state, scene only records and validates the reference
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PointLayer {
+    pub name: String,
+    // relative paths resolve from directory passed to Scene::validate
+    pub path: PathBuf,
+    // logical pixels, None uses default point size
+    #[serde(default)]
+    pub point_size: Option<f32>,
+    // name of entry in Scene::color_scales
+    #[serde(default)]
+    pub color_scale: Option<String>,
+}
+
+// named categorical palette of linear rgba colors
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColorScale {
+    pub name: String,
+    pub colors: Vec<[f32; 4]>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        let options = VisOptions::default();
+        Self {
+            width: options.width,
+            height: options.height,
+            background: options.background,
+            globe: SceneGlobe::default(),
+            view: None,
+            camera: None,
+            points: None,
+            layers: vec![],
+            color_scales: vec![],
+        }
+    }
+}
+
+impl Default for SceneGlobe {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            subdivisions: DEFAULT_SUBDIVISIONS,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Scene {
+    // check values are in range, color scale references resolve,
+    // and layer data files exist relative to base directory
+    pub fn validate(&self, base: &Path) -> Result<(), SceneError> {
+        if self.globe.subdivisions > MAX_SUBDIVISIONS {
+            return Err(SceneError::Subdivisions(self.globe.subdivisions));
+        }
+        for scale in &self.color_scales {
+            if scale.colors.is_empty() {
+                return Err(SceneError::EmptyColorScale(scale.name.clone()));
+            }
+        }
+        for layer in &self.layers {
+            if let Some(scale) = &layer.color_scale {
+                if self.color_scale(scale).is_none() {
+                    return Err(SceneError::ColorScale {
+                        layer: layer.name.clone(),
+                        scale: scale.clone(),
+                    });
+                }
+            }
+            let path = base.join(&layer.path);
+            if !path.is_file() {
+                return Err(SceneError::MissingFile {
+                    layer: layer.name.clone(),
+                    path,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn color_scale(&self, name: &str) -> Option<&ColorScale> {
+        self.color_scales.iter().find(|scale| scale.name == name)
+    }
+
+    // palette for layer's color scale, default palette when layer has none
+    pub fn layer_colors(&self, layer: &PointLayer) -> CategoryColors {
+        match layer
+            .color_scale
+            .as_deref()
+            .and_then(|s| self.color_scale(s))
+        {
+            Some(scale) => CategoryColors::from_palette(scale.colors.clone()),
+            None => CategoryColors::new(),
+        }
+    }
+
+    // builder takes a single point size, first layer with a size sets it,
+    // then size of inline points
+    pub fn point_size(&self) -> f32 {
+        self.layers
+            .iter()
+            .find_map(|layer| layer.point_size)
+            .or(self.points.as_ref().map(|points| points.size))
+            .unwrap_or(DEFAULT_POINT_SIZE)
+    }
+}
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SceneError {
+    #[error("Scene globe subdivisions {0} above maximum of {MAX_SUBDIVISIONS}")]
+    Subdivisions(usize),
+    #[error("Scene color scale '{0}' has no colors")]
+    EmptyColorScale(String),
+    #[error("Scene layer '{layer}' references undefined color scale '{scale}'")]
+    ColorScale { layer: String, scale: String },
+    #[error("Scene layer '{layer}' data file not found at {}", path.display())]
+    MissingFile { layer: String, path: PathBuf },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_scene() -> Scene {
+        Scene {
+            width: 800.0,
+            height: 600.0,
+            background: [0.1, 0.1, 0.2],
+            globe: SceneGlobe {
+                color: [0.8, 0.9, 1.0],
+                subdivisions: 5,
+                opacity: 0.7,
+            },
+            view: Some(SceneView {
+                lat: 40.0,
+                lon: -74.0,
+                distance: 3.0,
+            }),
+            camera: Some(CameraState {
+                orientation: [0.1, 0.2, 0.3, 0.927],
+                distance: 2.5,
+                orthographic: true,
+            }),
+            points: Some(ScenePoints {
+                positions: vec![0.0, 1.0, 0.0, 0.6, 0.0, 0.8],
+                colors: vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.5],
+                size: 6.0,
+            }),
+            layers: vec![PointLayer {
+                name: "cities".to_string(),
+                path: PathBuf::from("Cargo.toml"),
+                point_size: Some(8.0),
+                color_scale: Some("warm".to_string()),
+            }],
+            color_scales: vec![ColorScale {
+                name: "warm".to_string(),
+                colors: vec![[1.0, 0.5, 0.0, 1.0], [1.0, 0.0, 0.0, 1.0]],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_scene_round_trip() {
+        let scene = sample_scene();
+        let json = serde_json::to_string(&scene).unwrap();
+        let parsed: Scene = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, scene);
+
+        // missing fields fall back to defaults
+        let parsed: Scene = serde_json::from_str(r#"{"width": 300.0}"#).unwrap();
+        assert_eq!(parsed.width, 300.0);
+        assert_eq!(parsed.globe, SceneGlobe::default());
+        assert_eq!(parsed.point_size(), DEFAULT_POINT_SIZE);
+    }
+
+    #[test]
+    fn test_scene_validate() {
+        let base = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut scene = sample_scene();
+        scene.validate(base).unwrap();
+        assert_eq!(scene.point_size(), 8.0);
+        assert_eq!(scene.layer_colors(&scene.layers[0]).palette_len(), 2);
+
+        scene.layers[0].path = PathBuf::from("missing.bin");
+        let err = scene.validate(base).unwrap_err();
+        assert!(matches!(&err, SceneError::MissingFile { layer, .. } if layer == "cities"));
+        assert!(err.to_string().contains("missing.bin"));
+
+        scene.layers[0].color_scale = Some("cool".to_string());
+        assert!(matches!(
+            scene.validate(base),
+            Err(SceneError::ColorScale { .. })
+        ));
+    }
+}
@@ -1,3 +1,73 @@
+use crate::{
+    gl_wrap::UniformValue, lines::LineSegment, markers::Marker, points::Point, vis_ctx::Backend,
+};
+use std::collections::HashSet;
+
 pub trait VisState {
-    fn update_points(&mut self, ms: f32) -> Vec<f32>;
+    // called once before first frame with backend in use
+    fn on_start(&mut self, _backend: Backend) {}
+
+    // flat xyz positions, 3 floats per point
+    fn update_points(&mut self, _ms: f32) -> Vec<f32> {
+        vec![]
+    }
+
+    // write points into persistent buffer reused across frames, cleared before call
+    // override to avoid allocating a new vec each frame, defaults to update_points
+    fn update_points_into(&mut self, ms: f32, points: &mut Vec<f32>) {
+        points.extend_from_slice(&self.update_points(ms));
+    }
+
+    // counter changed whenever point data changes. update_points / update_points_typed
+    // are skipped while it's unchanged, so point data isn't copied or re-uploaded each
+    // frame. None reads points every frame and compares against the previous frame
+    fn points_revision(&self) -> Option<u64> {
+        None
+    }
+
+    // points with per point color and size, called before update_points each frame.
+    // non empty result is drawn and update_points is skipped, flat data is faster
+    // for large point sets since colors and sizes are re-uploaded with every update
+    fn update_points_typed(&mut self, _ms: f32) -> Vec<Point> {
+        vec![]
+    }
+
+    // colored, sized markers drawn with instancing, None keeps previous markers
+    // markers are drawn in addition to points from update_points
+    fn update_markers(&mut self, _ms: f32) -> Option<Vec<Marker>> {
+        None
+    }
+
+    // anti aliased line segments such as arcs or a graticule, None keeps previous lines
+    fn update_lines(&mut self, _ms: f32) -> Option<Vec<LineSegment>> {
+        None
+    }
+
+    // time for each point, None keeps previous times
+    // points are drawn with time 0 until a time is set for every point
+    fn update_point_times(&mut self, _ms: f32) -> Option<Vec<f32>> {
+        None
+    }
+
+    // inclusive time window of visible points, None keeps previous window
+    // ms is wall time since start, advance window from it for temporal playback
+    fn update_time_window(&mut self, _ms: f32) -> Option<(f32, f32)> {
+        None
+    }
+
+    // uniforms set by name each frame on built in programs declaring them, for animating
+    // custom shader effects like a pulsing glow. empty sets nothing
+    fn update_uniforms(&mut self, _ms: f32) -> Vec<(String, UniformValue)> {
+        vec![]
+    }
+
+    // lat / lon under cursor when hover is enabled, None when cursor is off globe
+    fn on_hover(&mut self, _lat_lon: Option<(f32, f32)>) {}
+
+    // indices of points in cluster under cursor when hover and clustering are enabled,
+    // None when cursor isn't over a cluster or points are drawn individually
+    fn on_cluster_hover(&mut self, _members: Option<&[usize]>) {}
+
+    // indices of selected points after a click changes selection, when click select is on
+    fn on_selection(&mut self, _selected: &HashSet<usize>) {}
 }
@@ -0,0 +1,84 @@
+// stage of a touch point, mapped from platform touch phase in vis_ctx
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    // interrupted by platform, removed without counting as a release
+    Cancelled,
+}
+
+// active touch points in physical pixels, keyed by platform touch id
+// one finger drags like the left mouse button, two fingers pinch zoom
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TouchState {
+    touches: Vec<(u64, (f64, f64))>,
+}
+
+impl TouchState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn count(&self) -> usize {
+        self.touches.len()
+    }
+
+    pub fn position(&self, id: u64) -> Option<(f64, f64)> {
+        self.touches.iter().find(|(i, _)| *i == id).map(|(_, p)| *p)
+    }
+
+    // first active touch, drags continue from it when a pinch ends
+    pub fn primary(&self) -> Option<(f64, f64)> {
+        self.touches.first().map(|(_, p)| *p)
+    }
+
+    // add, move or remove touch point
+    pub fn update(&mut self, id: u64, phase: TouchPhase, x: f64, y: f64) {
+        let index = self.touches.iter().position(|(i, _)| *i == id);
+        match (phase, index) {
+            (TouchPhase::Ended | TouchPhase::Cancelled, Some(index)) => {
+                self.touches.remove(index);
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, None) => (),
+            (_, Some(index)) => self.touches[index].1 = (x, y),
+            (_, None) => self.touches.push((id, (x, y))),
+        }
+    }
+
+    // distance and midpoint between first two touches, None unless pinching
+    pub fn pinch(&self) -> Option<(f64, (f64, f64))> {
+        let [(_, a), (_, b), ..] = self.touches.as_slice() else {
+            return None;
+        };
+        let distance = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        Some((distance, ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touch_state() {
+        let mut touches = TouchState::new();
+        touches.update(3, TouchPhase::Started, 10.0, 10.0);
+        assert!(touches.pinch().is_none());
+        touches.update(7, TouchPhase::Started, 40.0, 50.0);
+        let (distance, center) = touches.pinch().unwrap();
+        assert!((distance - 50.0).abs() < 1e-9);
+        assert_eq!(center, (25.0, 30.0));
+
+        touches.update(7, TouchPhase::Moved, 70.0, 90.0);
+        assert_eq!(touches.position(7), Some((70.0, 90.0)));
+        // ending unknown touch is ignored
+        touches.update(9, TouchPhase::Ended, 0.0, 0.0);
+        assert_eq!(touches.count(), 2);
+        touches.update(3, TouchPhase::Ended, 10.0, 10.0);
+        assert_eq!(touches.primary(), Some((70.0, 90.0)));
+        assert!(touches.pinch().is_none());
+        touches.update(7, TouchPhase::Cancelled, 70.0, 90.0);
+        assert_eq!(touches.count(), 0);
+    }
+}
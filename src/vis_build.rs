@@ -1,32 +1,283 @@
+#[cfg(feature = "serde")]
+use crate::scene::{Scene, SceneError};
 use crate::{
+    camera::CameraState,
+    gl_wrap::{BufferUsage, ShaderSources},
+    globe::{DirectionalLight, GlobeRenderMode, GlobeTone, DEFAULT_SUBDIVISIONS},
+    keyboard::KeyBindings,
+    lines::DEFAULT_LINE_WIDTH,
+    mouse::{DragBindings, DEFAULT_PICK_RADIUS},
+    points::DEFAULT_POINT_SIZE,
+    render_pass::{PassStage, PolygonOffset, RenderPass},
     vis_ctx::{VisContext, VisContextError},
-    vis_gl::{VisGl, VisGlError},
+    vis_gl::{DrawOrder, MapMode, VisGl, VisGlError},
     VisState,
 };
+use glam::Vec3;
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::sync::{atomic::AtomicBool, Arc};
 
 // builder for initialization and running vis
 pub struct VisBuilder<T: VisState + 'static> {
-    width: Option<f64>,
-    height: Option<f64>,
+    options: VisOptions,
     state: Option<T>,
+    passes: Vec<(PassStage, Box<dyn RenderPass>)>,
+    on_error: Option<ErrorCallback>,
+    on_ready: Option<ReadyCallback>,
+    on_frame: Option<FrameCallback>,
+}
+
+// called with non fatal errors that don't prevent vis from running
+pub type ErrorCallback = Box<dyn FnMut(&dyn std::error::Error)>;
+
+// called once gl setup completes, before the first frame is drawn
+pub type ReadyCallback = Box<dyn FnOnce()>;
+
+// called after each drawn frame with ms since start, same clock as VisState updates,
+// and primary camera at that frame
+pub type FrameCallback = Box<dyn FnMut(f32, &CameraState)>;
+
+// rgba8 pixels row major from top left, checked to match dimensions
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowIcon {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowIcon {
+    pub fn new(rgba: &[u8], width: u32, height: u32) -> Result<Self, VisBuilderError> {
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if expected != Some(rgba.len()) {
+            return Err(VisBuilderError::IconSize(rgba.len(), width, height));
+        }
+        Ok(Self {
+            rgba: rgba.to_vec(),
+            width,
+            height,
+        })
+    }
+}
+
+// resolved builder values passed to context and gl initialization
+#[derive(Clone, Debug)]
+pub struct VisOptions {
+    pub width: f64,
+    pub height: f64,
+    // native window title, canvas title attribute on web
+    pub title: String,
+    pub srgb: bool,
+    pub draw_order: DrawOrder,
+    pub hover: bool,
+    pub globe_shaders: Option<ShaderSources>,
+    pub point_shaders: Option<ShaderSources>,
+    pub shader_fallback: bool,
+    // create point cloud resources and request point data from state each frame
+    pub points: bool,
+    // logical pixels, scaled by device pixel ratio
+    pub point_size: f32,
+    // logical pixels point edges fade over, 0 for hard edges
+    pub point_softness: f32,
+    // lerp point positions between updates
+    pub point_interpolation: bool,
+    // sort and blend translucent points back to front
+    pub point_alpha_sort: bool,
+    // usage hint for point position buffer
+    pub point_usage: BufferUsage,
+    // points buffers are allocated for at setup, 0 allocates on first update
+    pub point_capacity: usize,
+    // xyz positions and rgba colors uploaded once points are created, empty colors use
+    // default color. replaced by point data from VisState::update_points
+    pub initial_points: Option<(Vec<f32>, Vec<f32>)>,
+    // native window icon, ignored on wasm
+    pub icon: Option<WindowIcon>,
+    // event loop exits once set, shared with state to close window programmatically
+    pub exit_signal: Option<Arc<AtomicBool>>,
+    // show grab / grabbing cursor over globe
+    pub grab_cursor: bool,
+    // side by side views, Some(linked) when enabled
+    pub split_view: Option<bool>,
+    // spin axis tilt from vertical in degrees
+    pub axial_tilt: f32,
+    // frames skipped to approximate rate, None draws every frame
+    pub target_fps: Option<f32>,
+    // draw every frame, false only draws after input, animation or point data changes
+    pub continuous_render: bool,
+    pub globe_render_mode: GlobeRenderMode,
+    // dot size in logical pixels for points render mode
+    pub globe_point_size: f32,
+    // linear rgb multiplied with globe vertex colors
+    pub globe_color: [f32; 3],
+    // second globe color by latitude or specular mask, Solid for globe color only
+    pub globe_tone: GlobeTone,
+    // icosphere subdivision level of globe mesh
+    pub globe_subdivisions: usize,
+    // globe alpha, 1 for opaque
+    pub globe_opacity: f32,
+    // fade globe rim in shader for a smooth outline without msaa
+    pub edge_smoothing: bool,
+    // directional lights added to globe shading, at most MAX_LIGHTS
+    pub lights: Vec<DirectionalLight>,
+    // depth offset of filled globe, so surface overlays draw over it
+    pub globe_polygon_offset: Option<PolygonOffset>,
+    // write translucent globe depth before blending it, so only its near side shows
+    pub depth_prepass: bool,
+    // width of line segments in logical pixels
+    pub line_width: f32,
+    // linear rgb clear color behind globe
+    pub background: [f32; 3],
+    // clear to alpha 0 so page / desktop shows through around globe
+    pub transparent_background: bool,
+    // lat / lon facing camera and camera distance at start, None uses default view
+    pub initial_view: Option<(f32, f32, f32)>,
+    // exact saved camera at start, applied after initial_view
+    pub initial_camera: Option<CameraState>,
+    // start looking down on north (true) or south pole with orthographic projection
+    pub polar_view: Option<bool>,
+    // 3D globe or flat equirectangular map
+    pub map_mode: MapMode,
+    // group points into sized cluster markers when zoomed out
+    pub clustering: bool,
+    // center and zoom camera on first non empty point data
+    pub fit_to_points: bool,
+    // keyboard camera controls, None ignores key input
+    pub key_bindings: Option<KeyBindings>,
+    // modifier keys held during left drag select rotate / pan / roll / constrained rotate
+    pub drag_bindings: DragBindings,
+    // left click toggles selection of point under cursor
+    pub click_select: bool,
+    // logical pixels outside a point's radius that clicks still pick it from
+    pub pick_radius: f32,
+    // host page webgl2 context to draw into instead of creating a canvas
+    #[cfg(target_arch = "wasm32")]
+    pub existing_context: Option<web_sys::WebGl2RenderingContext>,
+}
+
+impl Default for VisOptions {
+    fn default() -> Self {
+        Self {
+            width: 500.0,
+            height: 500.0,
+            title: String::from("window"),
+            srgb: false,
+            draw_order: DrawOrder::GlobeFirst,
+            hover: false,
+            globe_shaders: None,
+            point_shaders: None,
+            shader_fallback: false,
+            points: true,
+            point_size: DEFAULT_POINT_SIZE,
+            point_softness: 0.0,
+            point_interpolation: false,
+            point_alpha_sort: false,
+            point_usage: BufferUsage::Dynamic,
+            point_capacity: 0,
+            initial_points: None,
+            icon: None,
+            exit_signal: None,
+            grab_cursor: true,
+            split_view: None,
+            axial_tilt: 0.0,
+            target_fps: None,
+            continuous_render: true,
+            globe_render_mode: GlobeRenderMode::Filled,
+            globe_point_size: DEFAULT_POINT_SIZE,
+            globe_color: [1.0, 1.0, 1.0],
+            globe_tone: GlobeTone::Solid,
+            globe_subdivisions: DEFAULT_SUBDIVISIONS,
+            globe_opacity: 1.0,
+            edge_smoothing: false,
+            globe_polygon_offset: None,
+            depth_prepass: false,
+            line_width: DEFAULT_LINE_WIDTH,
+            lights: vec![],
+            background: [0.0, 0.0, 0.0],
+            transparent_background: false,
+            initial_view: None,
+            initial_camera: None,
+            polar_view: None,
+            map_mode: MapMode::Globe,
+            clustering: false,
+            fit_to_points: false,
+            key_bindings: Some(KeyBindings::default()),
+            drag_bindings: DragBindings::default(),
+            click_select: false,
+            pick_radius: DEFAULT_PICK_RADIUS,
+            #[cfg(target_arch = "wasm32")]
+            existing_context: None,
+        }
+    }
 }
 
 impl<T: VisState + 'static> VisBuilder<T> {
     pub fn new() -> Self {
-        let width = None;
-        let height = None;
+        let options = VisOptions::default();
         let state = None;
+        let passes = vec![];
+        let on_error = None;
+        let on_ready = None;
+        let on_frame = None;
         Self {
-            width,
-            height,
+            options,
             state,
+            passes,
+            on_error,
+            on_ready,
+            on_frame,
+        }
+    }
+
+    // builder configured from declarative scene, after validating scene against data files
+    // layer data paths resolve from base, usually the directory of the scene file.
+    // layer data itself is loaded by user state, add it with with_state.
+    // embedded scene points seed the point cloud
+    #[cfg(feature = "serde")]
+    pub fn from_scene(scene: &Scene, base: &Path) -> Result<Self, SceneError> {
+        scene.validate(base)?;
+        let mut builder = Self::new()
+            .with_dimensions(scene.width, scene.height)
+            .with_background(scene.background)
+            .with_globe_color(scene.globe.color)
+            .with_globe_subdivisions(scene.globe.subdivisions)
+            .with_globe_opacity(scene.globe.opacity)
+            .with_point_size(scene.point_size());
+        if let Some(view) = &scene.view {
+            builder = builder.with_initial_view(view.lat, view.lon, view.distance);
+        }
+        if let Some(camera) = scene.camera {
+            builder = builder.with_camera_state(camera);
+        }
+        if let Some(points) = &scene.points {
+            builder = builder.with_initial_points(&points.positions, &points.colors);
         }
+        Ok(builder)
+    }
+
+    // set window icon from rgba8 pixels, no op on wasm where the page sets its favicon
+    // errors if pixel data isn't width * height * 4 bytes
+    pub fn with_icon(
+        mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VisBuilderError> {
+        self.options.icon = Some(WindowIcon::new(rgba, width, height)?);
+        Ok(self)
     }
 
     // set window size
     pub fn with_dimensions(mut self, width: f64, height: f64) -> Self {
-        self.width = Some(width);
-        self.height = Some(height);
+        self.options.width = width;
+        self.options.height = height;
+        self
+    }
+
+    // set window title
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.options.title = title.to_string();
         self
     }
 
@@ -36,19 +287,392 @@ impl<T: VisState + 'static> VisBuilder<T> {
         self
     }
 
+    // gamma correct output, native uses srgb framebuffer and wasm uses shader gamma
+    // off by default, colors are written to framebuffer as is
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.options.srgb = srgb;
+        self
+    }
+
+    // set whether globe or points are drawn first each frame
+    pub fn with_draw_order(mut self, draw_order: DrawOrder) -> Self {
+        self.options.draw_order = draw_order;
+        self
+    }
+
+    // report lat / lon under cursor to VisState::on_hover
+    pub fn with_hover(mut self, hover: bool) -> Self {
+        self.options.hover = hover;
+        self
+    }
+
+    // disable default point cloud for a globe without data overlay,
+    // skips points gl resource creation and VisState::update_points each frame
+    pub fn with_points(mut self, points: bool) -> Self {
+        self.options.points = points;
+        self
+    }
+
+    // set point size in logical pixels, looks the same across device pixel ratios
+    pub fn with_point_size(mut self, size: f32) -> Self {
+        self.options.point_size = size;
+        self
+    }
+
+    // start with interleaved xyz positions and rgba colors, until VisState::update_points
+    // returns data. empty colors use default point color
+    pub fn with_initial_points(mut self, positions: &[f32], colors: &[f32]) -> Self {
+        self.options.initial_points = Some((positions.to_vec(), colors.to_vec()));
+        self
+    }
+
+    // feather point edges over softness logical pixels for antialiased circles, useful on
+    // web where msaa doesn't reliably cover point primitives. soft points are blended,
+    // 0 keeps hard edges. change at runtime with Points::set_softness
+    pub fn with_point_softness(mut self, softness: f32) -> Self {
+        self.options.point_softness = softness;
+        self
+    }
+
+    // smooth point motion when VisState::update_points changes less often than frames are
+    // drawn, e.g. streaming at 10hz. motion trails data by one update
+    pub fn with_point_interpolation(mut self, interpolation: bool) -> Self {
+        self.options.point_interpolation = interpolation;
+        self
+    }
+
+    // blend translucent point colors back to front from camera, sorting points each frame.
+    // costly for large point sets, change at runtime with Points::set_alpha_sort
+    pub fn with_point_alpha_sort(mut self, alpha_sort: bool) -> Self {
+        self.options.point_alpha_sort = alpha_sort;
+        self
+    }
+
+    // buffer usage hint for point positions, Stream for data replaced every frame,
+    // Static for data uploaded once, default Dynamic for data that changes now and then
+    pub fn with_point_usage(mut self, usage: BufferUsage) -> Self {
+        self.options.point_usage = usage;
+        self
+    }
+
+    // allocate points buffers for n points at setup, updates up to n points
+    // then overwrite existing buffers instead of reallocating
+    pub fn with_point_capacity(mut self, n: usize) -> Self {
+        self.options.point_capacity = n;
+        self
+    }
+
+    // add custom render pass, drawn each frame after globe and points in order added
+    pub fn add_pass(self, pass: impl RenderPass + 'static) -> Self {
+        self.add_pass_at(PassStage::AfterLayers, pass)
+    }
+
+    // add custom render pass drawn before, between or after globe and points,
+    // combine with with_draw_order to choose which layer it's drawn over
+    pub fn add_pass_at(mut self, stage: PassStage, pass: impl RenderPass + 'static) -> Self {
+        self.passes.push((stage, Box::new(pass)));
+        self
+    }
+
+    // replace built in globe shaders
+    pub fn with_globe_shaders(mut self, vertex: &str, fragment: &str) -> Self {
+        self.options.globe_shaders = Some(ShaderSources::new(vertex, fragment));
+        self
+    }
+
+    // replace built in point shaders
+    pub fn with_point_shaders(mut self, vertex: &str, fragment: &str) -> Self {
+        self.options.point_shaders = Some(ShaderSources::new(vertex, fragment));
+        self
+    }
+
+    // use built in shaders if custom shaders fail instead of aborting start,
+    // failures are reported to error callback or logged if no callback is set
+    pub fn with_shader_fallback(mut self, fallback: bool) -> Self {
+        self.options.shader_fallback = fallback;
+        self
+    }
+
+    // exit event loop once signal is set, checked each event loop iteration
+    // clone signal into state to close window when e.g. an animation finishes
+    pub fn with_exit_signal(mut self, signal: Arc<AtomicBool>) -> Self {
+        self.options.exit_signal = Some(signal);
+        self
+    }
+
+    // show grab cursor, grabbing while dragging, on by default
+    // disable for embeds that manage their own cursor
+    pub fn with_grab_cursor(mut self, grab_cursor: bool) -> Self {
+        self.options.grab_cursor = grab_cursor;
+        self
+    }
+
+    // draw into existing webgl2 context instead of creating a canvas,
+    // see VisContext::from_existing for event loop limitations
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_existing_context(mut self, ctx: web_sys::WebGl2RenderingContext) -> Self {
+        self.options.existing_context = Some(ctx);
+        self
+    }
+
+    // draw scene twice side by side with a camera per half for comparing views
+    // linked cameras rotate / zoom together from input on either half
+    pub fn with_split_view(mut self, linked: bool) -> Self {
+        self.options.split_view = Some(linked);
+        self
+    }
+
+    // tilt globe spin axis from vertical in degrees, e.g. 23.4 for earth
+    pub fn with_axial_tilt(mut self, degrees: f32) -> Self {
+        self.options.axial_tilt = degrees;
+        self
+    }
+
+    // draw at approximately target fps by skipping frames, saves power for slow changing data
+    // browser animation frame / vsync rate is still the upper limit
+    pub fn with_target_fps(mut self, target_fps: Option<f32>) -> Self {
+        self.options.target_fps = target_fps;
+        self
+    }
+
+    // cap frame rate for battery friendly embeds, 0 removes the cap. same throttle as
+    // with_target_fps, native sleeps until next frame is due instead of polling.
+    // camera animations, morphs and interpolated points advance by frame time,
+    // so they keep their speed at lower rates but move in larger steps
+    pub fn with_max_fps(self, max_fps: u32) -> Self {
+        self.with_target_fps((max_fps > 0).then_some(max_fps as f32))
+    }
+
+    // false only redraws when camera moves, point data changes or an animation runs,
+    // otherwise the loop waits for input. data is compared against the last frame, so
+    // state that changes on its own clock between input events needs continuous render
+    pub fn with_continuous_render(mut self, continuous: bool) -> Self {
+        self.options.continuous_render = continuous;
+        self
+    }
+
+    // draw globe as filled triangles, wireframe or dots
+    pub fn with_globe_render_mode(mut self, render_mode: GlobeRenderMode) -> Self {
+        self.options.globe_render_mode = render_mode;
+        self
+    }
+
+    // set dot size for points globe render mode in logical pixels
+    pub fn with_globe_point_size(mut self, size: f32) -> Self {
+        self.options.globe_point_size = size;
+        self
+    }
+
+    // set initial globe base color, multiplied with vertex colors and lighting
+    // change at runtime with Globe::set_color
+    pub fn with_globe_color(mut self, color: [f32; 3]) -> Self {
+        self.options.globe_color = color;
+        self
+    }
+
+    // two tone globe, e.g. GlobeTone::Polar for ice caps or GlobeTone::Masked for oceans
+    // with a specular mask. change at runtime with Globe::set_tone
+    pub fn with_globe_tone(mut self, tone: GlobeTone) -> Self {
+        self.options.globe_tone = tone;
+        self
+    }
+
+    // globe mesh detail, change at runtime with Globe::set_subdivisions
+    pub fn with_globe_subdivisions(mut self, subdivisions: usize) -> Self {
+        self.options.globe_subdivisions = subdivisions;
+        self
+    }
+
+    // draw globe translucent below 1, clamped to 0..1. a translucent globe is always drawn
+    // after points and markers so they show through, overriding draw order.
+    // change at runtime with Globe::set_opacity
+    pub fn with_globe_opacity(mut self, opacity: f32) -> Self {
+        self.options.globe_opacity = opacity;
+        self
+    }
+
+    // smooth jagged globe silhouette where msaa is unavailable, blending rim pixels
+    // over the background. change at runtime with Globe::set_edge_smoothing
+    pub fn with_edge_smoothing(mut self, edge_smoothing: bool) -> Self {
+        self.options.edge_smoothing = edge_smoothing;
+        self
+    }
+
+    // add directional light to globe shading, direction points from globe toward light in
+    // world space and color is linear rgb scaled by intensity. lights add up on top of
+    // ambient and are clamped together, so lower ambient (Globe::set_ambient) for visible
+    // shading. at most MAX_LIGHTS (4) lights, starting fails with more
+    pub fn add_light(mut self, direction: [f32; 3], color: [f32; 3], intensity: f32) -> Self {
+        self.options.lights.push(DirectionalLight {
+            direction: Vec3::from(direction),
+            color: Vec3::from(color),
+            intensity,
+        });
+        self
+    }
+
+    // push filled globe depth back by offset so lines, points and triangles on the globe
+    // surface draw over it instead of z-fighting, without nudging their radius.
+    // PolygonOffset::AWAY is usually enough. triangle overlays can instead pull themselves
+    // forward from their pass with RenderPass::depth
+    pub fn with_globe_polygon_offset(mut self, offset: Option<PolygonOffset>) -> Self {
+        self.options.globe_polygon_offset = offset;
+        self
+    }
+
+    // draw opaque depth before translucent geometry. points always draw before a
+    // translucent globe, which writes its depth first so only its near side blends over
+    // them instead of near and far side stacking. an opaque globe draws first instead, and
+    // blended points test depth without writing it. overrides with_draw_order, far side of
+    // the globe no longer shows through, and translucent points still blend in draw order
+    // unless alpha sorted, see Globe::set_depth_prepass
+    pub fn with_depth_prepass(mut self, depth_prepass: bool) -> Self {
+        self.options.depth_prepass = depth_prepass;
+        self
+    }
+
+    // width in logical pixels of line segments from VisState::update_lines, drawn as
+    // anti aliased quads so width is consistent across platforms
+    pub fn with_line_width(mut self, width: f32) -> Self {
+        self.options.line_width = width.max(0.0);
+        self
+    }
+
+    // clear background to transparent so the globe composites over page content.
+    // on wasm the webgl2 context gets a premultiplied alpha channel, on native the window
+    // is created transparent, which depends on platform / compositor support (e.g. x11
+    // needs a compositing window manager). globe writes alpha 1 unless translucent, points
+    // and markers write their color alpha, so keep their colors opaque to avoid see through
+    // points. contexts from a host page keep the alpha settings the host created them with
+    pub fn with_transparent_background(mut self, transparent: bool) -> Self {
+        self.options.transparent_background = transparent;
+        self
+    }
+
+    // set linear rgb color drawn behind globe
+    pub fn with_background(mut self, color: [f32; 3]) -> Self {
+        self.options.background = color;
+        self
+    }
+
+    // start with lat / lon facing camera from distance, clamped to zoom limits
+    pub fn with_initial_view(mut self, lat: f32, lon: f32, distance: f32) -> Self {
+        self.options.initial_view = Some((lat, lon, distance));
+        self
+    }
+
+    // start from camera saved with VisGl::camera_state, overrides with_initial_view
+    pub fn with_camera_state(mut self, camera: CameraState) -> Self {
+        self.options.initial_camera = Some(camera);
+        self
+    }
+
+    // start with orthographic view straight down on north or south pole, framed to the
+    // hemisphere. takes precedence over with_initial_view
+    pub fn with_polar_view(mut self, north: bool) -> Self {
+        self.options.polar_view = Some(north);
+        self
+    }
+
+    // start as 3D globe or flat map, flat map pans on drag instead of rotating
+    pub fn with_map_mode(mut self, map_mode: MapMode) -> Self {
+        self.options.map_mode = map_mode;
+        self
+    }
+
+    // draw points as clusters sized by member count when zoomed out,
+    // switching to individual points as camera moves in
+    pub fn with_clustering(mut self, clustering: bool) -> Self {
+        self.options.clustering = clustering;
+        self
+    }
+
+    // frame first non empty point data from VisState::update_points, centering camera on
+    // its centroid and zooming in to its extent. an explicit with_initial_view, camera
+    // state or polar view skips the fit, and camera input, set_center or fly_to before
+    // data arrives take precedence and cancel it
+    pub fn with_fit_to_points(mut self, fit_to_points: bool) -> Self {
+        self.options.fit_to_points = fit_to_points;
+        self
+    }
+
+    // remap keyboard camera controls, None disables them
+    // defaults to + / - and numpad equivalents zooming in animated steps
+    pub fn with_key_bindings(mut self, key_bindings: Option<KeyBindings>) -> Self {
+        self.options.key_bindings = key_bindings;
+        self
+    }
+
+    // remap modifier + left drag camera motion, unbound modifier combinations rotate
+    // defaults to shift panning, ctrl rolling about view axis and alt constraining
+    // rotation to one axis
+    pub fn with_drag_bindings(mut self, drag_bindings: DragBindings) -> Self {
+        self.options.drag_bindings = drag_bindings;
+        self
+    }
+
+    // toggle selection of point under cursor on left click, off by default
+    // selection changes are sent to VisState::on_selection
+    pub fn with_click_select(mut self, click_select: bool) -> Self {
+        self.options.click_select = click_select;
+        self
+    }
+
+    // extra logical pixels around each point's drawn radius that still pick it on click
+    pub fn with_pick_radius(mut self, radius: f32) -> Self {
+        self.options.pick_radius = radius.max(0.0);
+        self
+    }
+
+    // set callback for non fatal errors
+    pub fn on_error(mut self, callback: impl FnMut(&dyn std::error::Error) + 'static) -> Self {
+        self.on_error = Some(Box::new(callback));
+        self
+    }
+
+    // set callback for when renderer is ready, e.g. to hide a loading indicator
+    // runs on the event loop thread (browser main thread on wasm) with gl context current,
+    // after VisGl::setup_gl succeeds and before the first frame
+    pub fn on_ready(mut self, callback: impl FnOnce() + 'static) -> Self {
+        self.on_ready = Some(Box::new(callback));
+        self
+    }
+
+    // set callback run after every drawn frame, for syncing overlays or recording
+    // frames skipped by target fps or on demand rendering don't call it
+    pub fn on_frame(mut self, callback: impl FnMut(f32, &CameraState) + 'static) -> Self {
+        self.on_frame = Some(Box::new(callback));
+        self
+    }
+
     // run visualization from prev set fields
     pub fn start(&mut self) -> Result<(), VisBuilderError> {
-        let width = self.width.unwrap_or(500.0);
-        let height = self.height.unwrap_or(500.0);
         let state = self.state.take();
 
-        let window = VisContext::new(width, height)?;
-        let gl = VisGl::new(&window, width, height)?;
-        VisContext::run(window, gl, state)?;
+        let mut window = VisContext::new(&self.options)?;
+        let mut gl = VisGl::new(&window, &self.options)?;
+        for (stage, pass) in self.passes.drain(..) {
+            gl.add_pass_at(stage, pass);
+        }
+        for err in gl.shader_errors.drain(..) {
+            match self.on_error.as_mut() {
+                Some(on_error) => on_error(&err),
+                None => log::error!("custom shader failed, using built in: {}", err),
+            }
+        }
+        window.on_error = self.on_error.take();
+        window.on_frame = self.on_frame.take();
+        VisContext::run(window, gl, state, self.on_ready.take())?;
         Ok(())
     }
 }
 
+impl<T: VisState + 'static> Default for VisBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -57,4 +681,55 @@ pub enum VisBuilderError {
     VisGl(#[from] VisGlError),
     #[error("{0}")]
     VisContext(#[from] VisContextError),
+    #[error("Icon data length {0} doesn't match {1}x{2} rgba pixels")]
+    IconSize(usize, u32, u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_icon_size() {
+        let icon = WindowIcon::new(&[255; 2 * 3 * 4], 2, 3).unwrap();
+        assert_eq!((icon.width, icon.height, icon.rgba.len()), (2, 3, 24));
+        assert!(matches!(
+            WindowIcon::new(&[255; 23], 2, 3),
+            Err(VisBuilderError::IconSize(23, 2, 3))
+        ));
+        // overflowing dimensions can't match any buffer
+        assert!(WindowIcon::new(&[], u32::MAX, u32::MAX).is_err());
+        assert!(WindowIcon::new(&[], 0, 0).is_ok());
+    }
+
+    struct EmptyState;
+    impl VisState for EmptyState {}
+
+    #[test]
+    fn test_max_fps() {
+        let builder = VisBuilder::<EmptyState>::new().with_max_fps(30);
+        assert_eq!(builder.options.target_fps, Some(30.0));
+        // 0 removes cap
+        let builder = builder.with_max_fps(0);
+        assert_eq!(builder.options.target_fps, None);
+        assert!(VisBuilder::<EmptyState>::new().options.continuous_render);
+    }
+
+    #[test]
+    fn test_title() {
+        assert_eq!(VisBuilder::<EmptyState>::new().options.title, "window");
+        let builder = VisBuilder::<EmptyState>::new().with_title("earthquakes");
+        assert_eq!(builder.options.title, "earthquakes");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_scene_points() {
+        let json = r#"{"points": {"positions": [0.0, 0.0, 1.0], "size": 6.0}}"#;
+        let scene: Scene = serde_json::from_str(json).unwrap();
+        let builder = VisBuilder::<EmptyState>::from_scene(&scene, Path::new(".")).unwrap();
+        let (positions, colors) = builder.options.initial_points.unwrap();
+        assert_eq!((positions, colors), (vec![0.0, 0.0, 1.0], vec![]));
+        assert_eq!(builder.options.point_size, 6.0);
+    }
 }
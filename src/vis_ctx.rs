@@ -1,20 +1,35 @@
 use crate::{
-    gl_wrap::Drop,
-    mouse::{MouseButtons, SCROLL_LINE_HEIGHT},
+    gl_wrap::{Drop, UniformValue},
+    keyboard::Key,
+    mouse::{Modifiers, MouseButtons, SCROLL_LINE_HEIGHT},
+    touch::TouchPhase,
+    vis_build::{ErrorCallback, FrameCallback, ReadyCallback, VisOptions},
     vis_gl::{VisGl, VisGlError},
     VisState,
 };
 use glow::HasContext;
 use instant::Instant;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 // use glutin when compiling to native
 #[cfg(not(target_arch = "wasm32"))]
 mod native {
     pub use glutin::{
         dpi::LogicalSize,
-        event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent},
-        event_loop::{ControlFlow, EventLoop},
-        window::{Window, WindowBuilder},
+        event::{
+            ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta, StartCause, Touch,
+            TouchPhase as PlatformTouchPhase, VirtualKeyCode, WindowEvent,
+        },
+        event_loop::EventLoop,
+        window::{BadIcon, CursorIcon, Icon, Window, WindowBuilder},
         ContextBuilder, ContextWrapper, PossiblyCurrent,
     };
     pub type VisWindow = ContextWrapper<PossiblyCurrent, Window>;
@@ -25,14 +40,18 @@ use native::*;
 // use winit when compiling to wasm
 #[cfg(target_arch = "wasm32")]
 mod web {
-    pub use wasm_bindgen::JsCast;
-    pub use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
+    pub use std::{cell::Cell, rc::Rc};
+    pub use wasm_bindgen::{closure::Closure, JsCast};
+    pub use web_sys::{HtmlCanvasElement, WebGl2RenderingContext, WebGlContextAttributes};
     pub use winit::{
         event::MouseButton as MouseButtonWinit,
-        event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent},
-        event_loop::{ControlFlow, EventLoop},
+        event::{
+            ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta, Touch,
+            TouchPhase as PlatformTouchPhase, VirtualKeyCode, WindowEvent,
+        },
+        event_loop::EventLoop,
         platform::web::WindowExtWebSys,
-        window::{Window, WindowBuilder},
+        window::{CursorIcon, Window, WindowBuilder},
     };
     pub type VisWindow = Window;
 }
@@ -45,20 +64,72 @@ pub struct VisContext {
     pub event_loop: EventLoop<()>,
     pub shader_version: String,
     pub window: VisWindow,
+    // canvas drawn to, differs from window canvas when wrapping an existing context
+    #[cfg(target_arch = "wasm32")]
+    pub canvas: HtmlCanvasElement,
     pub dpi: f64,
+    pub exit_signal: Option<Arc<AtomicBool>>,
+    pub grab_cursor: bool,
+    backend: Backend,
+    pub target_fps: Option<f32>,
+    // draw every frame, false only draws when something changed
+    pub continuous_render: bool,
+    // called with errors that occur while running, e.g. webgl context loss
+    pub on_error: Option<ErrorCallback>,
+    // called after each drawn frame with elapsed ms and camera
+    pub on_frame: Option<FrameCallback>,
+    // webgl context lost / restored events from canvas
+    #[cfg(target_arch = "wasm32")]
+    pub context_loss: ContextLoss,
+    // window resizes, canvas backing store is only resized after one
+    #[cfg(target_arch = "wasm32")]
+    pub canvas_resize: CanvasResize,
+    // options vis gl is recreated from after context restore
+    #[cfg(target_arch = "wasm32")]
+    pub options: VisOptions,
+}
+
+// graphics backend in use, for branching on features that differ between native and web
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    // desktop gl 4.1 core through glutin
+    Native,
+    // webgl2 in browser
+    WebGl2,
+}
+
+impl Backend {
+    // wireframe polygon mode, unavailable in webgl2
+    pub fn polygon_mode(self) -> bool {
+        self == Backend::Native
+    }
+
+    // srgb framebuffer output, webgl2 applies gamma in shaders instead
+    pub fn srgb_framebuffer(self) -> bool {
+        self == Backend::Native
+    }
 }
 
 impl VisContext {
     // native constructor, initialize glutin window and get context
+    // srgb requests an srgb capable framebuffer for gamma correct output
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn new(width: f64, height: f64) -> Result<Self, VisContextError> {
+    pub fn new(options: &VisOptions) -> Result<Self, VisContextError> {
+        let VisOptions { width, height, .. } = *options;
         let shader_version = String::from("#version 410");
         let event_loop = EventLoop::new();
+        let icon = match &options.icon {
+            Some(icon) => Some(Icon::from_rgba(icon.rgba.clone(), icon.width, icon.height)?),
+            None => None,
+        };
         let window_builder = WindowBuilder::new()
             .with_inner_size(LogicalSize::new(width, height))
-            .with_title("window");
+            .with_title(&options.title)
+            .with_window_icon(icon)
+            .with_transparent(options.transparent_background);
         let ctx_builder = ContextBuilder::new()
             .with_multisampling(4)
+            .with_srgb(options.srgb)
             .build_windowed(window_builder, &event_loop)?;
         let (gl, window);
         unsafe {
@@ -66,6 +137,7 @@ impl VisContext {
                 .make_current()
                 .map_err(|_| VisContextError::CtxCurrent)?;
             gl = glow::Context::from_loader_function(|x| window.get_proc_address(x) as *const _);
+            crate::gl_wrap::load_primitive_restart(|x| window.get_proc_address(x) as *const _);
         }
         let dpi = window.window().scale_factor();
         Ok(Self {
@@ -74,26 +146,55 @@ impl VisContext {
             event_loop,
             shader_version,
             dpi,
+            exit_signal: options.exit_signal.clone(),
+            grab_cursor: options.grab_cursor,
+            backend: Backend::Native,
+            target_fps: options.target_fps,
+            continuous_render: options.continuous_render,
+            on_error: None,
+            on_frame: None,
         })
     }
 
     // wasm constructor, init winit window, create canvas with webgl2 ctx and append to dom
+    // webgl2 default framebuffer can't be srgb, gamma is applied in shaders instead
     #[cfg(target_arch = "wasm32")]
-    pub fn new(width: f64, height: f64) -> Result<Self, VisContextError> {
+    pub fn new(options: &VisOptions) -> Result<Self, VisContextError> {
+        if let Some(ctx) = &options.existing_context {
+            let mut context = Self::from_existing(ctx.clone(), options.width, options.height)?;
+            context.exit_signal = options.exit_signal.clone();
+            context.grab_cursor = options.grab_cursor;
+            context.target_fps = options.target_fps;
+            context.continuous_render = options.continuous_render;
+            context.window.set_title(&options.title);
+            context.options = options.clone();
+            return Ok(context);
+        }
+        let VisOptions { width, height, .. } = *options;
         let shader_version = String::from("#version 300 es");
         let event_loop = EventLoop::new();
         let window = WindowBuilder::new()
-            .with_title("window")
+            .with_title(&options.title)
             .build(&event_loop)?;
         let dpi = window.scale_factor();
         let canvas = window.canvas();
         canvas
             .style()
-            .set_css_text(&format!("width: {:.0}px; height: {:.0}px;", width, height));
+            // touch-action stops the browser scrolling / zooming page on touch drags and pinches
+            .set_css_text(&format!(
+                "width: {:.0}px; height: {:.0}px; touch-action: none;",
+                width, height
+            ));
         canvas.set_width((width * dpi) as u32);
         canvas.set_height((height * dpi) as u32);
+        // alpha channel is only requested for compositing over page, premultiplied to match
+        // blending of translucent and smoothed globe edges into cleared alpha
+        let mut attributes = WebGlContextAttributes::new();
+        attributes
+            .alpha(options.transparent_background)
+            .premultiplied_alpha(true);
         let ctx = canvas
-            .get_context("webgl2")
+            .get_context_with_context_options("webgl2", &attributes)
             .ok()
             .and_then(|o| o)
             .and_then(|e| e.dyn_into::<WebGl2RenderingContext>().ok())
@@ -104,89 +205,400 @@ impl VisContext {
             .and_then(|d| d.body())
             .and_then(|b| b.append_child(&canvas).ok())
             .ok_or(VisContextError::DomBody)?;
+        let context_loss = ContextLoss::new(&canvas);
+        let canvas_resize = CanvasResize::new();
         Ok(Self {
             gl,
             window,
+            canvas,
             event_loop,
             shader_version,
             dpi,
+            exit_signal: options.exit_signal.clone(),
+            grab_cursor: options.grab_cursor,
+            backend: Backend::WebGl2,
+            target_fps: options.target_fps,
+            continuous_render: options.continuous_render,
+            on_error: None,
+            on_frame: None,
+            context_loss,
+            canvas_resize,
+            options: options.clone(),
         })
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
+    // wrap webgl2 context owned by a host page instead of creating a canvas
+    // a winit window is still created to drive redraws each animation frame, but its canvas
+    // isn't added to the dom, so mouse input on the host canvas isn't received and the
+    // camera only moves through programmatic calls. host drawing into the same context
+    // must restore any gl state it changes, vis state is only set once in setup_gl
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_existing(
+        ctx: WebGl2RenderingContext,
+        width: f64,
+        height: f64,
+    ) -> Result<Self, VisContextError> {
+        let shader_version = String::from("#version 300 es");
+        let event_loop = EventLoop::new();
+        let window = WindowBuilder::new()
+            .with_title("window")
+            .build(&event_loop)?;
+        let dpi = window.scale_factor();
+        let canvas = ctx
+            .canvas()
+            .and_then(|c| c.dyn_into::<HtmlCanvasElement>().ok())
+            .ok_or(VisContextError::WebGl2Context)?;
+        canvas.set_width((width * dpi) as u32);
+        canvas.set_height((height * dpi) as u32);
+        let gl = glow::Context::from_webgl2_context(ctx);
+        let context_loss = ContextLoss::new(&canvas);
+        let canvas_resize = CanvasResize::new();
+        Ok(Self {
+            gl,
+            window,
+            canvas,
+            event_loop,
+            shader_version,
+            dpi,
+            exit_signal: None,
+            grab_cursor: false,
+            backend: Backend::WebGl2,
+            target_fps: None,
+            continuous_render: true,
+            on_error: None,
+            on_frame: None,
+            context_loss,
+            canvas_resize,
+            options: VisOptions {
+                width,
+                height,
+                ..VisOptions::default()
+            },
+        })
+    }
+
+    // backend context was created for, available at runtime unlike cfg(target_arch)
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
     fn redraw(window: &VisWindow) {
+        VisContext::present(window);
+        VisContext::request_redraw(window);
+    }
+
+    // show drawn frame without scheduling another
+    #[cfg(not(target_arch = "wasm32"))]
+    fn present(window: &VisWindow) {
         window.swap_buffers().unwrap();
+    }
+
+    // browser presents canvas after each animation frame
+    #[cfg(target_arch = "wasm32")]
+    fn present(_window: &VisWindow) {}
+
+    // schedule next frame without presenting, used when a frame is skipped
+    #[cfg(not(target_arch = "wasm32"))]
+    fn request_redraw(window: &VisWindow) {
         window.window().request_redraw();
     }
 
     #[cfg(target_arch = "wasm32")]
-    fn redraw(window: &VisWindow) {
+    fn request_redraw(window: &VisWindow) {
         window.request_redraw();
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_cursor(window: &VisWindow, icon: CursorIcon) {
+        window.window().set_cursor_icon(icon);
+    }
+
+    // sets canvas css cursor on web
+    #[cfg(target_arch = "wasm32")]
+    fn set_cursor(window: &VisWindow, icon: CursorIcon) {
+        window.set_cursor_icon(icon);
+    }
+
+    // canvas can be read before page layout, giving 0 client size and a blank context
+    // re-read client size after layout changes and resize backing store / projection once
+    // nonzero, returns false while canvas has no size so drawing can be skipped
+    #[cfg(target_arch = "wasm32")]
+    fn sync_canvas_size(
+        canvas: &HtmlCanvasElement,
+        gl: &glow::Context,
+        dpi: f64,
+        vis: &mut VisGl,
+    ) -> Result<bool, VisGlError> {
+        let (width, height) = (canvas.client_width(), canvas.client_height());
+        if width <= 0 || height <= 0 {
+            return Ok(false);
+        }
+        let phys_width = (width as f64 * dpi) as u32;
+        let phys_height = (height as f64 * dpi) as u32;
+        if canvas.width() != phys_width || canvas.height() != phys_height {
+            canvas.set_width(phys_width);
+            canvas.set_height(phys_height);
+            unsafe {
+                gl.viewport(0, 0, phys_width as i32, phys_height as i32);
+            }
+            vis.resize(gl, width as f64, height as f64)?;
+        }
+        Ok(true)
+    }
+
     // window passed as argument since running event loop causes move
     // calls vis event handlers on event
     pub fn run<T: VisState + 'static>(
         mut context: VisContext,
         mut vis: VisGl,
         mut state: Option<T>,
+        on_ready: Option<ReadyCallback>,
     ) -> Result<(), VisContextError> {
         vis.setup_gl(&context.gl)?;
+        if let Some(on_ready) = on_ready {
+            on_ready();
+        }
+        if let Some(state) = state.as_mut() {
+            state.on_start(context.backend());
+        }
+        if context.grab_cursor {
+            VisContext::set_cursor(&context.window, CursorIcon::Grab);
+        }
 
         let time = Instant::now();
+        let mut throttle = FrameThrottle::new(context.target_fps);
+        let mut last_frame = 0.0;
+        // persistent point buffer, reused each frame to avoid allocation
+        let mut point_data: Vec<f32> = vec![];
+        // when not rendering continuously, frames are only drawn while dirty
+        let mut dirty = true;
+        let mut point_changes = PointChanges::new();
+        let mut uniform_changes = UniformChanges::new();
+        // time throttled native loop sleeps until, None waits for next event
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut wake_at: Option<std::time::Instant> = None;
+        // loss is reported once, then events are ignored until context is restored
+        #[cfg(target_arch = "wasm32")]
+        let mut loss_reported = false;
+        // native window size is known on creation, canvas waits for page layout
+        #[cfg(target_arch = "wasm32")]
+        let mut canvas_ready = false;
         context.event_loop.run(move |event, _, control_flow| {
             #[cfg(not(target_arch = "wasm32"))]
-            control_flow.set_wait();
+            match wake_at {
+                Some(time) => control_flow.set_wait_until(time),
+                None => control_flow.set_wait(),
+            }
             #[cfg(target_arch = "wasm32")]
             control_flow.set_poll();
+            // programmatic exit, cleanup still runs on LoopDestroyed
+            if let Some(signal) = &context.exit_signal {
+                if signal.load(Ordering::Relaxed) {
+                    control_flow.set_exit();
+                }
+            }
+            // gl calls fail while context is lost, uniform lookups would panic in handlers.
+            // closing doesn't touch gl, so it still exits
+            #[cfg(target_arch = "wasm32")]
+            if context.context_loss.is_lost() {
+                if let Event::WindowEvent {
+                    event: WindowEvent::CloseRequested,
+                    ..
+                } = &event
+                {
+                    control_flow.set_exit();
+                }
+                if !loss_reported {
+                    loss_reported = true;
+                    report_error(&mut context.on_error, &VisContextError::ContextLost);
+                }
+                return;
+            }
+            // all gl resources were lost, recreate vis and resume drawing
+            #[cfg(target_arch = "wasm32")]
+            if context.context_loss.take_restored() {
+                loss_reported = false;
+                if let Err(err) = vis.restore(&context, &context.options) {
+                    report_error(&mut context.on_error, &VisContextError::VisGl(err));
+                }
+                // point data only lived in lost buffers, read it from state again
+                point_changes = PointChanges::new();
+                // uniforms were set on lost programs
+                uniform_changes = UniformChanges::new();
+                dirty = true;
+                VisContext::request_redraw(&context.window);
+            }
 
             match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::CursorMoved { position, .. } => {
-                        vis.mouse_move(&context.gl, position.x, position.y).unwrap();
+                Event::WindowEvent { event, .. } => {
+                    // platform side of events, vis handles the rest
+                    match &event {
+                        WindowEvent::MouseInput {
+                            button: MouseButton::Left,
+                            state: button_state,
+                            ..
+                        } if context.grab_cursor => {
+                            let icon = match button_state {
+                                ElementState::Pressed => CursorIcon::Grabbing,
+                                ElementState::Released => CursorIcon::Grab,
+                            };
+                            VisContext::set_cursor(&context.window, icon);
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        WindowEvent::Resized(size) => context.window.resize(*size),
+                        #[cfg(target_arch = "wasm32")]
+                        WindowEvent::Resized(_) => context.canvas_resize.mark(),
+                        WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                            context.dpi = *scale_factor;
+                            // backing store size depends on dpi
+                            #[cfg(target_arch = "wasm32")]
+                            context.canvas_resize.mark();
+                        }
+                        WindowEvent::CloseRequested => control_flow.set_exit(),
+                        _ => (),
                     }
-                    WindowEvent::MouseWheel { delta, .. } => {
-                        let ds = match delta {
-                            MouseScrollDelta::PixelDelta(position) => position.y / context.dpi,
-                            MouseScrollDelta::LineDelta(_, y) => (y as f64) * SCROLL_LINE_HEIGHT,
-                        };
-                        vis.mouse_wheel(&context.gl, ds).unwrap();
+                    dirty |= vis.handle_event(&context.gl, &event).unwrap();
+                    match (&event, state.as_mut()) {
+                        (WindowEvent::CursorMoved { position, .. }, Some(state))
+                            if vis.hover_ready() =>
+                        {
+                            let (x, y) = (position.x / context.dpi, position.y / context.dpi);
+                            notify_hover(&vis, state, x, y);
+                        }
+                        (WindowEvent::MouseInput { .. } | WindowEvent::Touch(_), state) => {
+                            notify_selection(&mut vis, state);
+                        }
+                        _ => (),
                     }
-                    WindowEvent::MouseInput { button, state, .. } => {
-                        let button = match button {
-                            MouseButton::Left => MouseButtons::Left,
-                            MouseButton::Right => MouseButtons::Right,
-                            _ => MouseButtons::Other,
-                        };
-                        let state = match state {
-                            ElementState::Pressed => true,
-                            ElementState::Released => false,
-                        };
-                        vis.mouse_input(&context.gl, button, state);
+                }
+                Event::MainEventsCleared => {
+                    // hover dropped by throttle is reported once its interval passes
+                    if let (Some((x, y)), Some(state)) = (vis.pending_hover(), state.as_mut()) {
+                        notify_hover(&vis, state, x, y);
                     }
-                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                        context.dpi = scale_factor
+                    // wake idle loop when pending hover is due
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(wait) = vis.hover_wait() {
+                        let hover_at = std::time::Instant::now() + wait;
+                        control_flow.set_wait_until(wake_at.map_or(hover_at, |t| t.min(hover_at)));
                     }
-                    WindowEvent::CloseRequested => {
-                        control_flow.set_exit();
+                    // window resized, or canvas still waiting for layout to give it a size
+                    #[cfg(target_arch = "wasm32")]
+                    if context.canvas_resize.is_pending() {
+                        VisContext::request_redraw(&context.window);
                     }
-                    _ => (),
-                },
+                    // input changed view while idle, wake loop for a frame
+                    if dirty && !context.continuous_render {
+                        VisContext::request_redraw(&context.window);
+                    }
+                }
+                // throttled frame is due
+                #[cfg(not(target_arch = "wasm32"))]
+                Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+                    wake_at = None;
+                    VisContext::request_redraw(&context.window);
+                }
                 Event::LoopDestroyed => {
                     vis.drop(&context.gl);
                 }
                 Event::RedrawRequested(_) => {
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        if context.canvas_resize.take_resized() {
+                            let (canvas, gl, dpi) = (&context.canvas, &context.gl, context.dpi);
+                            canvas_ready =
+                                VisContext::sync_canvas_size(canvas, gl, dpi, &mut vis).unwrap();
+                            dirty = true;
+                        }
+                        // canvas may get its size from page layout without a resize,
+                        // keep checking each frame only until it has one
+                        if !canvas_ready {
+                            context.canvas_resize.mark();
+                            return;
+                        }
+                    }
                     let elapsed = time.elapsed().as_millis() as f32;
-                    let point_data = state.as_mut().map(|u| u.update_points(elapsed));
-
-                    unsafe {
-                        context
-                            .gl
-                            .clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+                    if !throttle.ready(elapsed) {
+                        // native sleeps until frame is due, browser frames are already paced
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            let wait = Duration::from_secs_f32(throttle.remaining_ms() / 1000.0);
+                            let time = std::time::Instant::now() + wait;
+                            wake_at = Some(time);
+                            control_flow.set_wait_until(time);
+                        }
+                        #[cfg(target_arch = "wasm32")]
+                        VisContext::request_redraw(&context.window);
+                        return;
+                    }
+                    // continuous motion advances by frame delta, not per event
+                    let delta_ms = elapsed - last_frame;
+                    last_frame = elapsed;
+                    // checked before stepping so the final animation step is still drawn
+                    let animating = vis.is_animating();
+                    // skip point update entirely when points are disabled,
+                    // only changed data is passed on to be uploaded
+                    let points = match (vis.points.as_mut(), state.as_mut()) {
+                        (Some(p), Some(u)) if point_changes.pending(u.points_revision()) => {
+                            point_data.clear();
+                            let typed = u.update_points_typed(elapsed);
+                            if typed.is_empty() {
+                                u.update_points_into(elapsed, &mut point_data);
+                            } else {
+                                p.set_typed(&context.gl, &typed, context.dpi, &mut point_data);
+                            }
+                            point_changes
+                                .changed(u.points_revision(), &point_data)
+                                .then_some(point_data.as_slice())
+                        }
+                        _ => None,
+                    };
+                    let markers = state.as_mut().and_then(|u| u.update_markers(elapsed));
+                    if let Some(lines) = state.as_mut().and_then(|u| u.update_lines(elapsed)) {
+                        vis.set_lines(&context.gl, &lines);
+                        dirty = true;
+                    }
+                    if let (Some(p), Some(u)) = (vis.points.as_mut(), state.as_mut()) {
+                        if let Some(times) = u.update_point_times(elapsed) {
+                            p.set_times(&context.gl, &times);
+                            dirty = true;
+                        }
+                        if let Some((start, end)) = u.update_time_window(elapsed) {
+                            p.set_time_window(&context.gl, start, end).unwrap();
+                            dirty = true;
+                        }
+                    }
+                    let uniforms = state.as_mut().map(|u| u.update_uniforms(elapsed));
+                    for (name, value) in uniforms.into_iter().flatten() {
+                        // unchanged values are still set on programs, no redraw needed
+                        if !uniform_changes.changed(&name, value) {
+                            continue;
+                        }
+                        if let Err(err) = vis.set_uniform(&context.gl, &name, value) {
+                            log::debug!("{}, skipping state uniform", err);
+                        }
+                        dirty = true;
+                    }
+                    if !context.continuous_render {
+                        dirty |= markers.is_some() || points.is_some() || animating;
+                        // nothing changed since last drawn frame, wait for input
+                        if !dirty {
+                            return;
+                        }
+                    }
+                    vis.frame(&context.gl, delta_ms, points, markers.as_deref())
+                        .unwrap();
+                    if let Some(on_frame) = context.on_frame.as_mut() {
+                        on_frame(elapsed, &vis.camera_state());
+                    }
+                    if context.continuous_render {
+                        VisContext::redraw(&context.window);
+                    } else {
+                        // state is polled once more, loop goes idle if it has no changes
+                        VisContext::present(&context.window);
+                        dirty = false;
+                        VisContext::request_redraw(&context.window);
                     }
-                    vis.globe.draw(&context.gl);
-                    vis.points.draw(&context.gl, point_data);
-                    VisContext::redraw(&context.window);
                 }
                 _ => (),
             }
@@ -194,6 +606,380 @@ impl VisContext {
     }
 }
 
+// platform window events for VisGl, kept with the platform event types
+impl VisGl {
+    // apply window event to camera, selection, viewport and dpi, for driving vis from an
+    // app's own event loop. returns true if the event changed what's drawn, hover alone
+    // doesn't. closing, cursor icons and VisState callbacks are left to the caller
+    pub fn handle_event(
+        &mut self,
+        gl: &glow::Context,
+        event: &WindowEvent<'_>,
+    ) -> Result<bool, VisGlError> {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let dragging = self.mouse.dragging;
+                self.mouse_move(gl, position.x, position.y)?;
+                Ok(dragging)
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let ds = match delta {
+                    MouseScrollDelta::PixelDelta(position) => position.y / self.dpi,
+                    MouseScrollDelta::LineDelta(_, y) => (*y as f64) * SCROLL_LINE_HEIGHT,
+                };
+                self.mouse_wheel(gl, ds)?;
+                Ok(true)
+            }
+            WindowEvent::MouseInput { button, state, .. } => {
+                let button = match button {
+                    MouseButton::Left => MouseButtons::Left,
+                    MouseButton::Right => MouseButtons::Right,
+                    _ => MouseButtons::Other,
+                };
+                self.mouse_input(gl, button, *state == ElementState::Pressed);
+                Ok(true)
+            }
+            WindowEvent::Touch(Touch {
+                id,
+                phase,
+                location,
+                ..
+            }) => {
+                let phase = match phase {
+                    PlatformTouchPhase::Started => TouchPhase::Started,
+                    PlatformTouchPhase::Moved => TouchPhase::Moved,
+                    PlatformTouchPhase::Ended => TouchPhase::Ended,
+                    PlatformTouchPhase::Cancelled => TouchPhase::Cancelled,
+                };
+                self.touch(gl, *id, phase, location.x, location.y)?;
+                Ok(true)
+            }
+            WindowEvent::ModifiersChanged(state) => {
+                self.set_modifiers(modifiers_from_state(*state));
+                Ok(false)
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                // held keys send repeated presses, each repeat is another step
+                let action = input
+                    .virtual_keycode
+                    .and_then(key_from_code)
+                    .zip(self.key_bindings.as_ref())
+                    .and_then(|(key, bindings)| bindings.action(key));
+                match (input.state, action) {
+                    (ElementState::Pressed, Some(action)) => {
+                        self.key_action(action);
+                        Ok(true)
+                    }
+                    _ => Ok(false),
+                }
+            }
+            WindowEvent::Resized(size) => {
+                unsafe {
+                    gl.viewport(0, 0, size.width as i32, size.height as i32);
+                }
+                let width = size.width as f64 / self.dpi;
+                let height = size.height as f64 / self.dpi;
+                self.resize(gl, width, height)?;
+                Ok(true)
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.set_dpi(gl, *scale_factor)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+// pass lat / lon and cluster under logical cursor position to state
+fn notify_hover<T: VisState>(vis: &VisGl, state: &mut T, x: f64, y: f64) {
+    state.on_hover(vis.surface_lat_lon(x, y));
+    if vis.clustering.is_some() {
+        state.on_cluster_hover(vis.cluster_members(x, y));
+    }
+}
+
+// pass selection to state after a click or tap changed it
+fn notify_selection<T: VisState>(vis: &mut VisGl, state: Option<&mut T>) {
+    if !vis.take_selection_changed() {
+        return;
+    }
+    if let (Some(state), Some(points)) = (state, &vis.points) {
+        state.on_selection(&points.selected);
+    }
+}
+
+// send error to callback, or log if no callback is set
+#[cfg(target_arch = "wasm32")]
+fn report_error(on_error: &mut Option<ErrorCallback>, err: &VisContextError) {
+    match on_error.as_mut() {
+        Some(on_error) => on_error(err),
+        None => log::error!("{}", err),
+    }
+}
+
+// tracks webglcontextlost / webglcontextrestored events on a canvas
+// default on loss is to never restore, listener prevents that so the browser may restore it
+#[cfg(target_arch = "wasm32")]
+pub struct ContextLoss {
+    lost: Rc<Cell<bool>>,
+    restored: Rc<Cell<bool>>,
+    // kept alive for as long as listeners are registered
+    _listeners: [Closure<dyn FnMut(web_sys::Event)>; 2],
+}
+
+#[cfg(target_arch = "wasm32")]
+impl ContextLoss {
+    pub fn new(canvas: &HtmlCanvasElement) -> Self {
+        let (lost, restored) = (Rc::new(Cell::new(false)), Rc::new(Cell::new(false)));
+        let on_lost = {
+            let lost = lost.clone();
+            Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+                event.prevent_default();
+                lost.set(true);
+            })
+        };
+        let on_restored = {
+            let (lost, restored) = (lost.clone(), restored.clone());
+            Closure::<dyn FnMut(web_sys::Event)>::new(move |_: web_sys::Event| {
+                lost.set(false);
+                restored.set(true);
+            })
+        };
+        for (name, listener) in [
+            ("webglcontextlost", &on_lost),
+            ("webglcontextrestored", &on_restored),
+        ] {
+            if canvas
+                .add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())
+                .is_err()
+            {
+                log::warn!(
+                    "couldn't listen for {}, context loss won't be detected",
+                    name
+                );
+            }
+        }
+        Self {
+            lost,
+            restored,
+            _listeners: [on_lost, on_restored],
+        }
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost.get()
+    }
+
+    // true once after context is restored
+    pub fn take_restored(&self) -> bool {
+        self.restored.replace(false)
+    }
+}
+
+// tracks window resizes, which change canvas layout size, so client size is only
+// re-read after a resize instead of every frame
+#[cfg(target_arch = "wasm32")]
+pub struct CanvasResize {
+    resized: Rc<Cell<bool>>,
+    // kept alive for as long as listener is registered
+    listener: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CanvasResize {
+    pub fn new() -> Self {
+        // pending from the start so size is read on first frame
+        let resized = Rc::new(Cell::new(true));
+        let listener = {
+            let resized = resized.clone();
+            Closure::<dyn FnMut(web_sys::Event)>::new(move |_: web_sys::Event| resized.set(true))
+        };
+        let registered = web_sys::window().is_some_and(|window| {
+            window
+                .add_event_listener_with_callback("resize", listener.as_ref().unchecked_ref())
+                .is_ok()
+        });
+        if !registered {
+            log::warn!("couldn't listen for resize, canvas size is only read on dpi change");
+        }
+        Self { resized, listener }
+    }
+
+    // request size be re-read on next frame
+    pub fn mark(&self) {
+        self.resized.set(true);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.resized.get()
+    }
+
+    // true once after window was resized
+    pub fn take_resized(&self) -> bool {
+        self.resized.replace(false)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Default for CanvasResize {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// remove listener before closure is freed
+#[cfg(target_arch = "wasm32")]
+impl std::ops::Drop for CanvasResize {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            let listener = self.listener.as_ref().unchecked_ref();
+            let _ = window.remove_event_listener_with_callback("resize", listener);
+        }
+    }
+}
+
+// frames up to this early are drawn, absorbs ms rounding of frame times
+const THROTTLE_SLACK_MS: f32 = 1.0;
+
+// map platform key code to vis key, None for keys vis doesn't use
+fn key_from_code(code: VirtualKeyCode) -> Option<Key> {
+    let key = match code {
+        VirtualKeyCode::Plus => Key::Plus,
+        VirtualKeyCode::Equals => Key::Equals,
+        VirtualKeyCode::Minus => Key::Minus,
+        VirtualKeyCode::NumpadAdd => Key::NumpadAdd,
+        VirtualKeyCode::NumpadSubtract => Key::NumpadSubtract,
+        VirtualKeyCode::PageUp => Key::PageUp,
+        VirtualKeyCode::PageDown => Key::PageDown,
+        VirtualKeyCode::Up => Key::Up,
+        VirtualKeyCode::Down => Key::Down,
+        VirtualKeyCode::Left => Key::Left,
+        VirtualKeyCode::Right => Key::Right,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// map platform modifier state to vis modifiers, logo key isn't used
+fn modifiers_from_state(state: ModifiersState) -> Modifiers {
+    Modifiers {
+        shift: state.shift(),
+        ctrl: state.ctrl(),
+        alt: state.alt(),
+    }
+}
+
+// detects new point data from state, by revision when state reports one and by
+// comparing against the last changed data otherwise
+pub struct PointChanges {
+    revision: Option<u64>,
+    // only kept for states without a revision
+    last: Vec<f32>,
+}
+
+impl PointChanges {
+    pub fn new() -> Self {
+        Self {
+            revision: None,
+            last: vec![],
+        }
+    }
+
+    // check if points need to be read from state for its current revision
+    pub fn pending(&self, revision: Option<u64>) -> bool {
+        revision.is_none() || revision != self.revision
+    }
+
+    // record data read at revision, returns true if it changed since last read
+    pub fn changed(&mut self, revision: Option<u64>, data: &[f32]) -> bool {
+        let changed = revision.is_some() || data != self.last.as_slice();
+        if revision.is_none() && changed {
+            self.last.clear();
+            self.last.extend_from_slice(data);
+        }
+        self.revision = revision;
+        changed
+    }
+}
+
+impl Default for PointChanges {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// detects state uniforms whose value differs from the last one set under that name
+pub struct UniformChanges {
+    last: HashMap<String, UniformValue>,
+}
+
+impl UniformChanges {
+    pub fn new() -> Self {
+        Self {
+            last: HashMap::new(),
+        }
+    }
+
+    // record value set for name, returns true if it changed since last set
+    pub fn changed(&mut self, name: &str, value: UniformValue) -> bool {
+        if self.last.get(name) == Some(&value) {
+            return false;
+        }
+        self.last.insert(name.to_string(), value);
+        true
+    }
+}
+
+impl Default for UniformChanges {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// skips frames to approximate a target frame rate below the display rate
+// the browser / vsync still gates frames, so targets above that rate have no effect
+pub struct FrameThrottle {
+    interval_ms: Option<f32>,
+    accumulated_ms: f32,
+    last_ms: f32,
+}
+
+impl FrameThrottle {
+    pub fn new(target_fps: Option<f32>) -> Self {
+        let interval_ms = target_fps.filter(|fps| *fps > 0.0).map(|fps| 1000.0 / fps);
+        Self {
+            interval_ms,
+            accumulated_ms: 0.0,
+            last_ms: 0.0,
+        }
+    }
+
+    // ms until next frame is due as of last ready check, 0 when unthrottled
+    pub fn remaining_ms(&self) -> f32 {
+        match self.interval_ms {
+            Some(interval) => (interval - self.accumulated_ms - THROTTLE_SLACK_MS).max(0.0),
+            None => 0.0,
+        }
+    }
+
+    // check if frame at time should be drawn, accumulating time across skipped frames
+    pub fn ready(&mut self, now_ms: f32) -> bool {
+        let Some(interval) = self.interval_ms else {
+            return true;
+        };
+        self.accumulated_ms += now_ms - self.last_ms;
+        self.last_ms = now_ms;
+        if self.accumulated_ms + THROTTLE_SLACK_MS < interval {
+            return false;
+        }
+        // keep remainder for steady rate, dropped after a long stall to avoid a burst
+        let remainder = self.accumulated_ms - interval;
+        self.accumulated_ms = if remainder < interval { remainder } else { 0.0 };
+        true
+    }
+}
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -206,6 +992,9 @@ pub enum VisContextError {
     #[cfg(not(target_arch = "wasm32"))]
     #[error("Context could not be made current")]
     CtxCurrent,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("{0}")]
+    Icon(#[from] BadIcon),
     #[cfg(target_arch = "wasm32")]
     #[error("Canvas element couldn't be added to web sys body")]
     DomBody,
@@ -215,4 +1004,76 @@ pub enum VisContextError {
     #[cfg(target_arch = "wasm32")]
     #[error("{0}")]
     Os(#[from] winit::error::OsError),
+    // only raised on web, native contexts aren't lost while the window is open
+    #[error("WebGL context lost, waiting for browser to restore it")]
+    ContextLost,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_changes() {
+        // revision skips reading while unchanged, without keeping a copy
+        let mut changes = PointChanges::new();
+        assert!(changes.pending(Some(0)));
+        assert!(changes.changed(Some(0), &[1.0, 2.0, 3.0]));
+        assert!(!changes.pending(Some(0)));
+        assert!(changes.pending(Some(1)));
+        assert!(changes.last.is_empty());
+
+        // without revision, data is read every frame and compared
+        let mut changes = PointChanges::new();
+        assert!(changes.pending(None));
+        assert!(changes.changed(None, &[1.0, 2.0, 3.0]));
+        assert!(!changes.changed(None, &[1.0, 2.0, 3.0]));
+        assert!(changes.changed(None, &[]));
+    }
+
+    #[test]
+    fn test_uniform_changes() {
+        let mut changes = UniformChanges::new();
+        assert!(changes.changed("glow", UniformValue::Float(1.0)));
+        assert!(!changes.changed("glow", UniformValue::Float(1.0)));
+        assert!(changes.changed("glow", UniformValue::Float(0.5)));
+        // names are tracked separately
+        assert!(changes.changed("pulse", UniformValue::Float(0.5)));
+        assert!(!changes.changed("glow", UniformValue::Float(0.5)));
+    }
+
+    #[test]
+    fn test_frame_throttle() {
+        // 60hz frames at 20 fps target, every third frame drawn
+        let mut throttle = FrameThrottle::new(Some(20.0));
+        let frame_ms = 1000.0 / 60.0;
+        let drawn = (1..=60)
+            .filter(|i| throttle.ready(*i as f32 * frame_ms))
+            .count();
+        assert_eq!(drawn, 20);
+
+        // long stall draws once instead of catching up
+        let mut throttle = FrameThrottle::new(Some(10.0));
+        assert!(throttle.ready(5000.0));
+        assert!(!throttle.ready(5050.0));
+        assert!(throttle.ready(5100.0));
+
+        let mut unthrottled = FrameThrottle::new(None);
+        assert!(unthrottled.ready(0.0) && unthrottled.ready(0.0));
+        assert_eq!(unthrottled.remaining_ms(), 0.0);
+
+        // skipped frame waits out rest of interval
+        let mut throttle = FrameThrottle::new(Some(10.0));
+        assert!(throttle.ready(100.0));
+        assert!(!throttle.ready(130.0));
+        assert_eq!(throttle.remaining_ms(), 69.0);
+    }
+
+    #[test]
+    fn test_backend_capabilities() {
+        assert!(Backend::Native.polygon_mode());
+        assert!(Backend::Native.srgb_framebuffer());
+        assert!(!Backend::WebGl2.polygon_mode());
+        assert!(!Backend::WebGl2.srgb_framebuffer());
+    }
 }
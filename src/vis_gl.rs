@@ -1,41 +1,477 @@
+#[cfg(feature = "serde")]
+use crate::scene::{Scene, SceneGlobe, ScenePoints};
 use crate::{
-    gl_wrap::{Drop, Program, UniformMatrix},
+    camera::{
+        axial_tilt, fit_distance, orientation_for_center, points_extent, CameraAnimation,
+        CameraState, MorphAnimation,
+    },
+    cluster::ClusterLayer,
+    geo::{flat_position, xyz_to_lat_lon},
+    gl_wrap::{
+        enable_primitive_restart, Bind, Drop, Program, ProgramCache, UniformError, UniformFloat,
+        UniformMatrix, UniformValue,
+    },
     globe::Globe,
-    mouse::{rotate_from_mouse, zoom_from_scroll, MouseButtons, MouseState},
-    points::Points,
+    keyboard::{key_zoom_distance, KeyAction, KeyBindings, KEY_ZOOM_MS},
+    lines::{LinePath, LineSegment, Lines, Paths},
+    markers::{Marker, Markers},
+    mouse::{
+        constrain_drag, is_click, pinch_scroll_delta, roll_from_mouse, rotate_from_mouse,
+        zoom_from_scroll, DragAction, DragBindings, Modifiers, MouseButtons, MouseState,
+        HOVER_INTERVAL_MS, MAX_DISTANCE, MIN_DISTANCE,
+    },
+    pick::{
+        is_occluded, ndc_to_ray, nearest_within, project, ray_plane_intersect,
+        ray_sphere_intersect, screen_to_ndc,
+    },
+    points::{logical_to_physical, Points},
+    render_pass::{CameraView, PassDepth, PassStage, RenderPass},
+    touch::TouchPhase,
+    vis_build::VisOptions,
     vis_ctx::VisContext,
 };
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Quat, Vec2, Vec3};
 use glow::HasContext;
+use instant::Instant;
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::time::Duration;
 
 // contains all vis gl resources and camera mouse handlers
 pub struct VisGl {
     pub globe: Globe,
-    pub points: Points,
+    // None when points disabled in builder
+    pub points: Option<Points>,
+    pub markers: Markers,
+    // anti aliased line segments, drawn after markers
+    pub lines: Lines,
+    // one pixel line strips, drawn after line segments
+    pub paths: Paths,
+    // programs shared between layers drawn with the same shaders
+    pub program_cache: ProgramCache,
     pub mvp: MvpMatrices,
     pub mouse: MouseState,
+    pub srgb: bool,
+    pub gamma: UniformFloat,
+    // camera morph from 0 globe to 1 flat map, skipped for custom shaders without it
+    pub flat_map: UniformFloat,
+    pub draw_order: DrawOrder,
+    pub hover: bool,
+    pub width: f64,
+    pub height: f64,
+    // device pixel ratio, logical pixel sizes are scaled by this when uploaded
+    pub dpi: f64,
+    // custom passes with the stage they're drawn at
+    pub passes: Vec<(PassStage, Box<dyn RenderPass>)>,
+    // custom shader failures replaced by built in shaders during init
+    pub shader_errors: Vec<VisGlError>,
+    pub animation: Option<CameraAnimation>,
+    // running globe / flat map transition, stepped with camera animation
+    pub morph: Option<MorphAnimation>,
+    // second viewport on right half of window, None for single view
+    pub split: Option<SplitView>,
+    // None when clustering or points disabled
+    pub clustering: Option<ClusterLayer>,
+    // fit camera to first non empty point data, cancelled by user or programmatic camera moves
+    pub fit_pending: bool,
+    // linear rgb clear color
+    pub background: [f32; 3],
+    // clear alpha is 0 instead of 1, for compositing over page content
+    pub transparent_background: bool,
+    // keyboard camera controls, None ignores key input
+    pub key_bindings: Option<KeyBindings>,
+    // camera motion for left drag with each modifier combination
+    pub drag_bindings: DragBindings,
+    // left click toggles selection of point under cursor
+    pub click_select: bool,
+    // logical pixels outside a point's drawn radius that still pick it
+    pub pick_radius: f32,
+    // selection changed by click since last take_selection_changed
+    selection_changed: bool,
 }
 
+// right half camera for side by side comparison view
+// mvp above is used for left half, and is the camera moved by programmatic controls
+pub struct SplitView {
+    pub mvp: MvpMatrices,
+    // right camera copies left camera each frame, input on either half moves both
+    pub linked: bool,
+    // drag started on right half
+    pub right_active: bool,
+}
+
+// order globe and points are drawn in each frame
+// with depth test enabled opaque geometry looks the same either way,
+// order only matters once blending or depth write changes are involved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawOrder {
+    // globe fills depth buffer first, points behind globe fail depth test
+    // translucent points blend over the globe surface
+    GlobeFirst,
+    // points fill depth buffer first, globe fragments behind points are discarded
+    // translucent globe blends over points on its far side
+    PointsFirst,
+}
+
+// gamma applied in fragment shaders when srgb output requested
+// native uses an srgb framebuffer instead, so shaders pass color through
+#[cfg(not(target_arch = "wasm32"))]
+const SRGB_SHADER_GAMMA: f32 = 1.0;
+#[cfg(target_arch = "wasm32")]
+const SRGB_SHADER_GAMMA: f32 = 2.2;
+
 impl VisGl {
-    pub fn new(context: &VisContext, width: f64, height: f64) -> Result<Self, VisGlError> {
+    pub fn new(context: &VisContext, options: &VisOptions) -> Result<Self, VisGlError> {
+        Self::new_with_gl(&context.gl, &context.shader_version, context.dpi, options)
+    }
+
+    // create vis against a gl context owned by the caller, for embedding the globe in an
+    // app's own window and event loop instead of VisBuilder::start. shader version is
+    // "#version 300 es" on webgl2 or "#version 410" on desktop gl, dpi is the window scale
+    // factor. call setup_gl once before the first frame, then pass window events to
+    // handle_event and call frame each frame
+    pub fn new_with_gl(
+        gl: &glow::Context,
+        version: &str,
+        dpi: f64,
+        options: &VisOptions,
+    ) -> Result<Self, VisGlError> {
         let mouse = MouseState::new();
-        let globe = Globe::new(&context.gl, &context.shader_version)?;
-        let points = Points::new(&context.gl, &context.shader_version)?;
-        let mvp = MvpMatrices::new_default((width / height) as f32)?;
+        let mut shader_errors = vec![];
+        let mut globe = with_fallback(
+            options
+                .globe_shaders
+                .as_ref()
+                .map(|s| Globe::new_with_shaders(gl, version, &s.vertex, &s.fragment)),
+            || Globe::new(gl, version),
+            options.shader_fallback,
+            &mut shader_errors,
+        )?;
+        globe.set_render_mode(options.globe_render_mode);
+        globe.point_size = options.globe_point_size;
+        globe.point_size_uniform.data = logical_to_physical(options.globe_point_size, dpi);
+        globe.color.data = Vec3::from(options.globe_color);
+        globe.set_tone(gl, options.globe_tone)?;
+        globe.opacity.data = options.globe_opacity.clamp(0.0, 1.0);
+        globe.edge_smoothing.data = if options.edge_smoothing { 1.0 } else { 0.0 };
+        globe.polygon_offset = options.globe_polygon_offset;
+        globe.set_depth_prepass(options.depth_prepass);
+        for light in &options.lights {
+            globe.add_light(gl, *light)?;
+        }
+        globe.set_subdivisions(gl, options.globe_subdivisions)?;
+        globe.set_flat(gl, options.map_mode == MapMode::Flat);
+        let points = if options.points {
+            let mut points = with_fallback(
+                options
+                    .point_shaders
+                    .as_ref()
+                    .map(|s| Points::new_with_shaders(gl, version, &s.vertex, &s.fragment)),
+                || Points::new(gl, version),
+                options.shader_fallback,
+                &mut shader_errors,
+            )?;
+            points.softness = options.point_softness.max(0.0);
+            // scales logical softness, outline and selection widths for dpi along with size
+            points.set_point_size(gl, options.point_size, dpi)?;
+            points.set_interpolation(options.point_interpolation);
+            points.set_alpha_sort(gl, options.point_alpha_sort)?;
+            points.set_usage(options.point_usage);
+            points.reserve(gl, options.point_capacity)?;
+            if let Some((positions, colors)) = &options.initial_points {
+                points.set_positions(gl, positions);
+                points.set_colors(gl, colors);
+            }
+            Some(points)
+        } else {
+            None
+        };
+        let mut program_cache = ProgramCache::new();
+        let markers = Markers::new_with_cache(gl, &mut program_cache, version)?;
+        let mut lines = Lines::new(gl, version)?;
+        lines.set_width(gl, options.line_width, dpi)?;
+        let paths = Paths::new(gl, version)?;
+        let clustering = if options.clustering && points.is_some() {
+            Some(ClusterLayer::new_with_cache(
+                gl,
+                &mut program_cache,
+                version,
+            )?)
+        } else {
+            None
+        };
+        let split = match options.split_view {
+            Some(linked) => {
+                let mut mvp = MvpMatrices::new_default(options.width * 0.5, options.height)?;
+                mvp.set_tilt(axial_tilt(options.axial_tilt));
+                mvp.set_map_mode(options.map_mode);
+                Some(SplitView {
+                    mvp,
+                    linked,
+                    right_active: false,
+                })
+            }
+            None => None,
+        };
+        let view_width = if split.is_some() {
+            options.width * 0.5
+        } else {
+            options.width
+        };
+        let mut mvp = MvpMatrices::new_default(view_width, options.height)?;
+        mvp.set_tilt(axial_tilt(options.axial_tilt));
+        mvp.set_map_mode(options.map_mode);
+        if let Some((lat, lon, distance)) = options.initial_view {
+            mvp.set_orientation(mvp.center_orientation(lat, lon));
+            mvp.set_pan(flat_center(lat, lon));
+            mvp.set_distance(distance.clamp(MIN_DISTANCE, MAX_DISTANCE));
+        }
+        if let Some(camera) = &options.initial_camera {
+            mvp.set_camera_state(camera);
+        }
+        if let Some(north) = options.polar_view {
+            mvp.set_polar_view(north);
+        }
+        let srgb = options.srgb;
+        let gamma = UniformFloat::new("gamma", if srgb { SRGB_SHADER_GAMMA } else { 1.0 });
+        let flat_map = UniformFloat::new("flatMap", mvp.morph);
+        let draw_order = options.draw_order;
+        let hover = options.hover;
+        let VisOptions { width, height, .. } = *options;
+        let passes = vec![];
         Ok(Self {
             globe,
             points,
+            markers,
+            lines,
+            paths,
+            program_cache,
             mvp,
             mouse,
+            srgb,
+            gamma,
+            flat_map,
+            draw_order,
+            hover,
+            width,
+            height,
+            dpi,
+            passes,
+            shader_errors,
+            animation: None,
+            morph: None,
+            split,
+            clustering,
+            // explicit view wins over fitting to data
+            fit_pending: options.fit_to_points
+                && options.initial_view.is_none()
+                && options.initial_camera.is_none()
+                && options.polar_view.is_none(),
+            background: options.background,
+            transparent_background: options.transparent_background,
+            key_bindings: options.key_bindings.clone(),
+            drag_bindings: options.drag_bindings.clone(),
+            click_select: options.click_select,
+            pick_radius: options.pick_radius,
+            selection_changed: false,
         })
     }
 
+    // add custom render pass, drawn after built in layers in order added
+    pub fn add_pass(&mut self, pass: Box<dyn RenderPass>) {
+        self.add_pass_at(PassStage::AfterLayers, pass);
+    }
+
+    // add custom render pass drawn before, between or after globe and points
+    pub fn add_pass_at(&mut self, stage: PassStage, pass: Box<dyn RenderPass>) {
+        self.passes.push((stage, pass));
+    }
+
+    // draw globe and points in configured order, markers are drawn with points
+    // in split view scene is drawn once per half, uploading data on first draw only
+    pub fn draw(
+        &mut self,
+        gl: &glow::Context,
+        point_data: Option<&[f32]>,
+        markers: Option<&[Marker]>,
+    ) -> Result<(), VisGlError> {
+        let Some(split) = self.split.as_mut() else {
+            self.draw_scene(gl, point_data, markers);
+            return Ok(());
+        };
+        if split.linked {
+            split.mvp.set_orientation(self.mvp.orientation);
+            split.mvp.projection = self.mvp.projection;
+            split.mvp.set_pan(self.mvp.pan);
+            split.mvp.set_shift(self.mvp.shift);
+            split.mvp.map_mode = self.mvp.map_mode;
+            split.mvp.set_morph(self.mvp.morph);
+            split.mvp.set_distance(self.mvp.distance);
+        }
+        let [left, right] = split_viewports(self.width, self.height, self.dpi);
+        self.draw_viewport(gl, left, false, point_data, markers)?;
+        self.draw_viewport(gl, right, true, None, None)?;
+        // restore primary camera uniforms and full window viewport
+        self.mvp.apply(gl, &VisGl::programs(self))?;
+        let (x, _, width, height) = right;
+        unsafe {
+            gl.viewport(0, 0, x + width, height);
+        }
+        Ok(())
+    }
+
+    // draw scene into viewport rect with left or right split view camera
+    fn draw_viewport(
+        &mut self,
+        gl: &glow::Context,
+        viewport: (i32, i32, i32, i32),
+        right: bool,
+        point_data: Option<&[f32]>,
+        markers: Option<&[Marker]>,
+    ) -> Result<(), VisGlError> {
+        let (x, y, width, height) = viewport;
+        unsafe {
+            gl.viewport(x, y, width, height);
+        }
+        let camera = match (&self.split, right) {
+            (Some(split), true) => &split.mvp,
+            _ => &self.mvp,
+        };
+        camera.apply(gl, &VisGl::programs(self))?;
+        let view = camera.camera_view();
+        let eye = camera.camera_position();
+        let size = [width as f32, height as f32];
+        self.draw_layers(gl, point_data, markers, eye, &view, size);
+        Ok(())
+    }
+
+    fn draw_scene(
+        &mut self,
+        gl: &glow::Context,
+        point_data: Option<&[f32]>,
+        markers: Option<&[Marker]>,
+    ) {
+        let (eye, view) = (self.mvp.camera_position(), self.mvp.camera_view());
+        // viewport covers window, set on resize
+        let size = [
+            (self.width * self.dpi) as f32,
+            (self.height * self.dpi) as f32,
+        ];
+        self.draw_layers(gl, point_data, markers, eye, &view, size);
+    }
+
+    // draw globe and points in draw order with custom passes at their stages,
+    // viewport is physical size of current gl viewport
+    fn draw_layers(
+        &mut self,
+        gl: &glow::Context,
+        point_data: Option<&[f32]>,
+        markers: Option<&[Marker]>,
+        eye: Vec3,
+        view: &CameraView,
+        viewport: [f32; 2],
+    ) {
+        if let Some(points) = self.points.as_mut() {
+            points.set_sort_camera(eye);
+            // prepassed translucent globe is drawn after points and would cover blended
+            // points in front of it, so they only skip depth writes over an opaque globe
+            points.depth_prepass = self.globe.depth_prepass && !self.globe.is_translucent();
+        }
+        // translucent globe blends over everything behind it, which must be drawn first.
+        // with a depth prepass an opaque globe writes depth before blended points test it
+        let order = if self.globe.is_translucent() {
+            DrawOrder::PointsFirst
+        } else if self.globe.depth_prepass {
+            DrawOrder::GlobeFirst
+        } else {
+            self.draw_order
+        };
+        self.draw_passes(gl, PassStage::BeforeLayers, view);
+        match order {
+            DrawOrder::GlobeFirst => {
+                self.globe.draw(gl);
+                self.draw_passes(gl, PassStage::BetweenLayers, view);
+                self.draw_point_layers(gl, point_data, markers, viewport);
+            }
+            DrawOrder::PointsFirst => {
+                self.draw_point_layers(gl, point_data, markers, viewport);
+                self.draw_passes(gl, PassStage::BetweenLayers, view);
+                self.globe.draw(gl);
+            }
+        }
+        self.draw_passes(gl, PassStage::AfterLayers, view);
+    }
+
+    fn draw_point_layers(
+        &mut self,
+        gl: &glow::Context,
+        point_data: Option<&[f32]>,
+        markers: Option<&[Marker]>,
+        viewport: [f32; 2],
+    ) {
+        let distance = self.mvp.distance;
+        let (points, clustering) = (self.points.as_mut(), self.clustering.as_mut());
+        draw_points(gl, points, clustering, point_data, distance);
+        self.markers.draw(gl, markers);
+        self.lines.draw(gl, viewport);
+        self.paths.draw(gl);
+    }
+
+    fn draw_passes(&mut self, gl: &glow::Context, stage: PassStage, view: &CameraView) {
+        for (_, pass) in self.passes.iter_mut().filter(|(s, _)| *s == stage) {
+            let depth = pass.depth();
+            depth.apply(gl);
+            pass.draw(gl, view);
+            if depth != PassDepth::default() {
+                PassDepth::reset(gl);
+            }
+        }
+    }
+
     pub fn mouse_move(&mut self, gl: &glow::Context, x: f64, y: f64) -> Result<(), VisGlError> {
         if self.mouse.dragging {
             let dx = x - self.mouse.x;
             let dy = y - self.mouse.y;
-            // rotate model matrix from mouse move deltas
-            self.mvp.model.data = rotate_from_mouse(self.mvp.model.data, dx, dy);
+            // rotate model orientation from mouse move deltas
+            let right = self.split.as_ref().is_some_and(|s| s.right_active);
+            // roll pivots around center of view being dragged, physical like mouse positions
+            let (width, height) = (self.width * self.dpi, self.height * self.dpi);
+            let center = match (&self.split, right) {
+                (Some(_), true) => (width * 0.75, height * 0.5),
+                (Some(_), false) => (width * 0.25, height * 0.5),
+                (None, _) => (width * 0.5, height * 0.5),
+            };
+            let from = (self.mouse.x, self.mouse.y);
+            let action = self.drag_bindings.action(self.mouse.modifiers);
+            // mouse deltas are physical pixels
+            let height = self.height * self.dpi;
+            let mvp = self.input_camera(right);
+            // flat map drags in screen space instead of rotating, keeping point under cursor
+            if mvp.map_mode == MapMode::Flat {
+                mvp.set_pan(mvp.pan + pan_from_drag(mvp.distance, height, dx, dy));
+                self.mvp.model.apply(gl, &VisGl::programs(self))?;
+                self.mouse.x = x;
+                self.mouse.y = y;
+                return Ok(());
+            }
+            // globe pan shifts camera with the same screen space scale as flat map drags
+            if action == DragAction::Pan {
+                mvp.set_shift(mvp.shift + pan_from_drag(mvp.distance, height, dx, dy));
+                self.mvp.view.apply(gl, &VisGl::programs(self))?;
+                self.mouse.x = x;
+                self.mouse.y = y;
+                return Ok(());
+            }
+            let orientation = match action {
+                DragAction::Rotate => rotate_from_mouse(mvp.orientation, dx, dy),
+                DragAction::ConstrainAxis => {
+                    let (dx, dy) = constrain_drag(dx, dy);
+                    rotate_from_mouse(mvp.orientation, dx, dy)
+                }
+                DragAction::Roll => roll_from_mouse(mvp.orientation, center, from, (x, y)),
+                DragAction::Pan => mvp.orientation,
+            };
+            mvp.set_orientation(orientation);
+            // split view cameras are reapplied per viewport in draw
             self.mvp.model.apply(gl, &VisGl::programs(self))?;
         }
         // save last mouse position
@@ -44,41 +480,810 @@ impl VisGl {
         Ok(())
     }
 
+    // held keyboard modifiers, selects drag action from drag bindings
+    pub fn set_modifiers(&mut self, modifiers: Modifiers) {
+        self.mouse.modifiers = modifiers;
+    }
+
     pub fn mouse_wheel(&mut self, gl: &glow::Context, delta: f64) -> Result<(), VisGlError> {
-        self.mvp.view.data = zoom_from_scroll(self.mvp.view.data, delta);
-        self.mvp.view.apply(gl, &VisGl::programs(self))?;
+        let right = self.mouse_on_right();
+        let mvp = self.input_camera(right);
+        let distance = zoom_from_scroll(mvp.distance, delta);
+        mvp.set_distance(distance);
+        self.mvp.apply(gl, &VisGl::programs(self))?;
         Ok(())
     }
 
-    pub fn mouse_input(&mut self, _: &glow::Context, button: MouseButtons, pressed: bool) {
+    pub fn mouse_input(&mut self, gl: &glow::Context, button: MouseButtons, pressed: bool) {
         // save mouse drag state on left mouse input
         if let MouseButtons::Left = button {
             self.mouse.dragging = pressed;
+            // user drag takes over from any running camera animation
+            if pressed {
+                self.animation = None;
+                self.fit_pending = false;
+                let right = self.mouse_on_right();
+                if let Some(split) = self.split.as_mut() {
+                    split.right_active = right;
+                }
+                self.mouse.press = Some((self.mouse.x, self.mouse.y));
+            } else if let Some(press) = self.mouse.press.take() {
+                let (x, y) = (self.mouse.x, self.mouse.y);
+                if self.click_select && is_click(press, x, y, self.dpi) {
+                    self.click_toggle(gl, x / self.dpi, y / self.dpi);
+                }
+            }
         }
     }
 
+    // touch point in physical pixels, one finger drags and taps like the left mouse button,
+    // two finger pinch zooms. lifting a finger from a pinch resumes dragging with the other
+    pub fn touch(
+        &mut self,
+        gl: &glow::Context,
+        id: u64,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+    ) -> Result<(), VisGlError> {
+        let before = self.mouse.touches.pinch();
+        self.mouse.touches.update(id, phase, x, y);
+        let after = self.mouse.touches.pinch();
+        match (before, after) {
+            (Some((from, _)), Some((to, (cx, cy)))) => {
+                // zoom split view half under pinch center
+                (self.mouse.x, self.mouse.y) = (cx, cy);
+                self.mouse_wheel(gl, pinch_scroll_delta(from, to))?;
+            }
+            // second finger down, stop dragging so pinch doesn't also rotate
+            (None, Some(_)) => {
+                self.mouse.dragging = false;
+                self.mouse.press = None;
+            }
+            // back to one finger, drag from it without treating its release as a tap
+            (Some(_), None) => {
+                if let Some((x, y)) = self.mouse.touches.primary() {
+                    (self.mouse.x, self.mouse.y) = (x, y);
+                    self.mouse.dragging = true;
+                }
+            }
+            (None, None) => match phase {
+                TouchPhase::Started => {
+                    self.mouse_move(gl, x, y)?;
+                    self.mouse_input(gl, MouseButtons::Left, true);
+                }
+                TouchPhase::Moved => self.mouse_move(gl, x, y)?,
+                TouchPhase::Ended => {
+                    if self.mouse.touches.count() == 0 {
+                        self.mouse_move(gl, x, y)?;
+                        self.mouse_input(gl, MouseButtons::Left, false);
+                    }
+                }
+                // drop drag without a release, so cancelled touches never tap
+                TouchPhase::Cancelled => {
+                    if self.mouse.touches.count() == 0 {
+                        self.mouse.dragging = false;
+                        self.mouse.press = None;
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // toggle selection of point under logical pixel position, if any
+    fn click_toggle(&mut self, gl: &glow::Context, x: f64, y: f64) {
+        let Some(index) = self.pick_point(gl, x, y) else {
+            return;
+        };
+        if let Some(points) = self.points.as_mut() {
+            points.toggle(index);
+            self.selection_changed = true;
+        }
+    }
+
+    // index of visible point nearest to logical pixel position within its drawn radius
+    // plus pick radius, None if no point is close enough. reads positions back from gpu,
+    // so call on clicks rather than every frame. uses primary camera like project,
+    // and points hidden by time window or interleaved raw data aren't considered
+    pub fn pick_point(&self, gl: &glow::Context, x: f64, y: f64) -> Option<usize> {
+        let points = self.points.as_ref()?;
+        if points.raw_layout.is_some() {
+            return None;
+        }
+        let positions = points.read_back(gl);
+        let sizes = points.drawn_sizes(gl, positions.len() / 3, self.dpi);
+        let candidates = positions.chunks_exact(3).enumerate().filter_map(|(i, p)| {
+            let (px, py, visible) = self.project(Vec3::from_slice(p))?;
+            let radius = sizes[i] * 0.5 + self.pick_radius;
+            visible.then_some((i, px, py, radius))
+        });
+        nearest_within(candidates, x as f32, y as f32)
+    }
+
+    // check and reset if a click changed point selection, for notifying user state
+    pub fn take_selection_changed(&mut self) -> bool {
+        std::mem::take(&mut self.selection_changed)
+    }
+
+    // mouse over right half of window, mouse position is physical and width logical
+    fn mouse_on_right(&self) -> bool {
+        self.mouse.x / self.dpi > self.width * 0.5
+    }
+
+    // camera moved by input on left or right half,
+    // always primary camera unless split view is unlinked and input is on right
+    fn input_camera(&mut self, right: bool) -> &mut MvpMatrices {
+        match self.split.as_mut() {
+            Some(split) if right && !split.linked => &mut split.mvp,
+            _ => &mut self.mvp,
+        }
+    }
+
+    // tilt globe spin axis by degrees from vertical, e.g. 23.4 for earth
+    pub fn set_axial_tilt(&mut self, gl: &glow::Context, degrees: f32) -> Result<(), VisGlError> {
+        self.mvp.set_tilt(axial_tilt(degrees));
+        if let Some(split) = self.split.as_mut() {
+            split.mvp.set_tilt(axial_tilt(degrees));
+        }
+        self.mvp.model.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    // rotate globe so lat / lon faces the camera
+    pub fn set_center(&mut self, gl: &glow::Context, lat: f32, lon: f32) -> Result<(), VisGlError> {
+        self.fit_pending = false;
+        self.mvp
+            .set_orientation(self.mvp.center_orientation(lat, lon));
+        self.mvp.set_pan(flat_center(lat, lon));
+        self.mvp.model.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    // switch between 3D globe and flat equirectangular map, for both split view cameras
+    // globe mesh is swapped for a flat grid, resetting globe vertex colors
+    pub fn set_map_mode(
+        &mut self,
+        gl: &glow::Context,
+        map_mode: MapMode,
+    ) -> Result<(), VisGlError> {
+        self.animation = None;
+        self.morph = None;
+        self.mvp.set_map_mode(map_mode);
+        if let Some(split) = self.split.as_mut() {
+            split.mvp.set_map_mode(map_mode);
+        }
+        self.globe.set_flat(gl, map_mode == MapMode::Flat);
+        self.apply_morph(gl)
+    }
+
+    // animate between globe and flat map, stepped in step_animation
+    // view stays centered on same lat / lon, input and picking switch to new mode at start
+    pub fn morph_to(&mut self, gl: &glow::Context, map_mode: MapMode, duration_ms: f32) {
+        self.animation = None;
+        let target = match map_mode {
+            MapMode::Globe => 0.0,
+            MapMode::Flat => 1.0,
+        };
+        self.morph = Some(MorphAnimation::new(self.mvp.morph, target, duration_ms));
+        // grid mesh morphs without seam triangles, icosphere restored once globe is reached
+        self.globe.set_flat(gl, true);
+        let cameras = std::iter::once(&mut self.mvp).chain(self.split.as_mut().map(|s| &mut s.mvp));
+        for mvp in cameras {
+            let (lat, lon) = mvp.view_center();
+            mvp.set_orientation(mvp.center_orientation(lat, lon));
+            mvp.set_pan(flat_center(lat, lon));
+            mvp.map_mode = map_mode;
+        }
+    }
+
+    // advance running morph by time since last frame
+    fn step_morph(&mut self, gl: &glow::Context, delta_ms: f32) -> Result<(), VisGlError> {
+        let Some(morph) = self.morph.as_mut() else {
+            return Ok(());
+        };
+        let (value, done) = morph.step(delta_ms);
+        self.mvp.set_morph(value);
+        if let Some(split) = self.split.as_mut() {
+            split.mvp.set_morph(value);
+        }
+        if done {
+            self.morph = None;
+            self.globe.set_flat(gl, self.mvp.map_mode == MapMode::Flat);
+        }
+        self.apply_morph(gl)
+    }
+
+    // upload camera morph and model matrix, shared by both split view cameras
+    fn apply_morph(&mut self, gl: &glow::Context) -> Result<(), VisGlError> {
+        self.flat_map.data = self.mvp.morph;
+        for program in VisGl::programs(self) {
+            program.bind(gl);
+            // custom shaders without flatMap keep drawing the globe
+            self.flat_map.apply_optional(gl, program);
+            self.mvp.model.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // switch primary camera between perspective and orthographic projection
+    pub fn set_projection(
+        &mut self,
+        gl: &glow::Context,
+        projection: Projection,
+    ) -> Result<(), VisGlError> {
+        self.mvp.set_projection(projection);
+        self.mvp.proj.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    // look straight down on north or south pole with orthographic projection,
+    // framed to show the whole hemisphere. cancels running animation and pending fit
+    pub fn set_polar_view(&mut self, gl: &glow::Context, north: bool) -> Result<(), VisGlError> {
+        self.animation = None;
+        self.fit_pending = false;
+        self.mvp.set_polar_view(north);
+        self.mvp.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    pub fn camera_state(&self) -> CameraState {
+        self.mvp.camera_state()
+    }
+
+    // jump to saved camera, cancels running animation and pending fit
+    pub fn set_camera_state(
+        &mut self,
+        gl: &glow::Context,
+        state: &CameraState,
+    ) -> Result<(), VisGlError> {
+        self.animation = None;
+        self.fit_pending = false;
+        self.mvp.set_camera_state(state);
+        self.mvp.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    // save camera, globe, background and uploaded xyz points / colors as scene json.
+    // interleaved data from Points::upload_raw isn't exported
+    #[cfg(feature = "serde")]
+    pub fn export_scene(&self, gl: &glow::Context) -> Result<String, VisGlError> {
+        let points = self
+            .points
+            .as_ref()
+            .filter(|points| points.raw_layout.is_none())
+            .map(|points| ScenePoints {
+                positions: points.read_back(gl),
+                colors: points.color_buffer.read_back(gl),
+                size: points.size,
+            });
+        let scene = Scene {
+            width: self.width,
+            height: self.height,
+            background: self.background,
+            globe: SceneGlobe {
+                color: self.globe.color.data.to_array(),
+                subdivisions: self.globe.subdivisions,
+                opacity: self.globe.opacity.data,
+            },
+            view: None,
+            camera: Some(self.camera_state()),
+            points,
+            layers: vec![],
+            color_scales: vec![],
+        };
+        Ok(serde_json::to_string(&scene)?)
+    }
+
+    // restore scene json from export_scene or a hand written scene. window size isn't
+    // changed, and point data from VisState::update_points replaces imported points
+    #[cfg(feature = "serde")]
+    pub fn import_scene(&mut self, gl: &glow::Context, json: &str) -> Result<(), VisGlError> {
+        let scene: Scene = serde_json::from_str(json)?;
+        self.globe.set_subdivisions(gl, scene.globe.subdivisions)?;
+        self.globe.set_color(gl, scene.globe.color)?;
+        self.globe.set_opacity(gl, scene.globe.opacity)?;
+        self.background = scene.background;
+        self.apply_clear_color(gl);
+        if let Some(camera) = &scene.camera {
+            self.set_camera_state(gl, camera)?;
+        } else if let Some(view) = &scene.view {
+            let orientation = self.mvp.center_orientation(view.lat, view.lon);
+            let state = CameraState {
+                orientation: orientation.to_array(),
+                distance: view.distance,
+                orthographic: false,
+            };
+            self.set_camera_state(gl, &state)?;
+        }
+        if let (Some(points), Some(data)) = (self.points.as_mut(), &scene.points) {
+            points.set_positions(gl, &data.positions);
+            points.set_colors(gl, &data.colors);
+            points.set_point_size(gl, data.size, self.dpi)?;
+        }
+        Ok(())
+    }
+
+    // animate rotation to center lat / lon while moving camera to distance, clearing pan
+    // stepped each frame in step_animation, cancelled by user drag
+    pub fn fly_to(&mut self, lat: f32, lon: f32, distance: f32, duration_ms: f32) {
+        self.fit_pending = false;
+        self.mvp.set_shift(Vec2::ZERO);
+        self.animation = Some(CameraAnimation::new(
+            (self.mvp.orientation, self.mvp.distance),
+            (self.mvp.center_orientation(lat, lon), distance),
+            duration_ms,
+        ));
+    }
+
+    // respond to bound key press, zoom steps animate from current camera
+    // repeated steps build on target of running step so held keys zoom continuously
+    pub fn key_action(&mut self, action: KeyAction) {
+        match action {
+            KeyAction::ZoomIn | KeyAction::ZoomOut => {
+                let (orientation, distance, duration) = match &self.animation {
+                    // keep running fly to target and remaining time
+                    Some(a) => (
+                        a.to_orientation,
+                        a.to_distance,
+                        (a.duration_ms - a.elapsed_ms).max(KEY_ZOOM_MS),
+                    ),
+                    None => (self.mvp.orientation, self.mvp.distance, KEY_ZOOM_MS),
+                };
+                self.fit_pending = false;
+                self.animation = Some(CameraAnimation::new(
+                    (self.mvp.orientation, self.mvp.distance),
+                    (orientation, key_zoom_distance(distance, action)),
+                    duration,
+                ));
+            }
+        }
+    }
+
+    // center camera on centroid of interleaved xyz points and zoom in to frame their extent
+    // data spread around the globe keeps current view, never zooms out past default distance
+    pub fn fit_to_points(&mut self, gl: &glow::Context, points: &[f32]) -> Result<(), VisGlError> {
+        self.fit_pending = false;
+        let Some((lat, lon, extent)) = points_extent(points) else {
+            return Ok(());
+        };
+        let view_width = if self.split.is_some() {
+            self.width * 0.5
+        } else {
+            self.width
+        };
+        let aspect = aspect_ratio(view_width, self.height)?;
+        let vertical = FOV_Y * 0.5;
+        let horizontal = (vertical.tan() * aspect).atan();
+        let distance = fit_distance(extent, vertical.min(horizontal), DEFAULT_DISTANCE);
+        self.mvp
+            .set_orientation(self.mvp.center_orientation(lat, lon));
+        self.mvp.set_shift(Vec2::ZERO);
+        self.mvp.set_distance(distance);
+        self.mvp.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    // advance camera animation, morph and point interpolation by time since last frame,
+    // called once per frame
+    pub fn step_animation(&mut self, gl: &glow::Context, delta_ms: f32) -> Result<(), VisGlError> {
+        self.step_morph(gl, delta_ms)?;
+        if let Some(points) = self.points.as_mut() {
+            points.step_interpolation(delta_ms);
+        }
+        let Some(animation) = self.animation.as_mut() else {
+            return Ok(());
+        };
+        let (orientation, distance, done) = animation.step(delta_ms);
+        if done {
+            self.animation = None;
+        }
+        self.mvp.set_orientation(orientation);
+        self.mvp.set_distance(distance);
+        self.mvp.apply(gl, &VisGl::programs(self))?;
+        Ok(())
+    }
+
+    // camera animation, morph or point interpolation needs more frames to finish
+    pub fn is_animating(&self) -> bool {
+        let interpolating = self
+            .points
+            .as_ref()
+            .is_some_and(|points| points.is_interpolating());
+        self.animation.is_some() || self.morph.is_some() || interpolating
+    }
+
+    // check if hover lat / lon should be recomputed
+    // throttled to avoid a matrix inverse on every pixel of mouse movement,
+    // moves inside the interval leave a hover pending for pending_hover
+    pub fn hover_ready(&mut self) -> bool {
+        if !self.hover {
+            return false;
+        }
+        if self.mouse.last_hover.elapsed().as_millis() < HOVER_INTERVAL_MS {
+            self.mouse.hover_pending = true;
+            return false;
+        }
+        self.mouse.last_hover = Instant::now();
+        self.mouse.hover_pending = false;
+        true
+    }
+
+    // logical cursor position of a hover dropped by the throttle, once its interval has
+    // passed. keeps the final cursor position reported after movement stops
+    pub fn pending_hover(&mut self) -> Option<(f64, f64)> {
+        if !self.mouse.hover_pending || !self.hover_ready() {
+            return None;
+        }
+        Some((self.mouse.x / self.dpi, self.mouse.y / self.dpi))
+    }
+
+    // time until a pending hover can be reported, None if nothing is pending
+    pub fn hover_wait(&self) -> Option<Duration> {
+        let interval = Duration::from_millis(HOVER_INTERVAL_MS as u64);
+        self.mouse
+            .hover_pending
+            .then(|| interval.saturating_sub(self.mouse.last_hover.elapsed()))
+    }
+
+    // get lat / lon on globe surface under logical pixel position, None if off globe
+    // in split view position is relative to the half it falls in
+    pub fn surface_lat_lon(&self, x: f64, y: f64) -> Option<(f32, f32)> {
+        let (mvp, x, width) = match &self.split {
+            Some(split) => {
+                let half = self.width * 0.5;
+                match (x > half, split.linked) {
+                    (true, false) => (&split.mvp, x - half, half),
+                    (true, true) => (&self.mvp, x - half, half),
+                    (false, _) => (&self.mvp, x, half),
+                }
+            }
+            None => (&self.mvp, x, self.width),
+        };
+        let (nx, ny) = screen_to_ndc(x, y, width, self.height);
+        let (origin, dir) = ndc_to_ray(mvp.view_projection(), nx, ny);
+        if mvp.map_mode == MapMode::Flat {
+            // model space of flat map is lon / lat in radians
+            let hit = ray_plane_intersect(origin, dir)?;
+            if hit.x.abs() > PI || hit.y.abs() > FRAC_PI_2 {
+                return None;
+            }
+            return Some((hit.y.to_degrees(), hit.x.to_degrees()));
+        }
+        let hit = ray_sphere_intersect(origin, dir, 1.0)?;
+        Some(xyz_to_lat_lon(hit.to_array()))
+    }
+
+    // indices of points in cluster under logical pixel position,
+    // None if clustering is off, camera is zoomed in past clustering, or no cluster is hit
+    pub fn cluster_members(&self, x: f64, y: f64) -> Option<&[usize]> {
+        let clustering = self.clustering.as_ref()?;
+        let (lat, lon) = self.surface_lat_lon(x, y)?;
+        let cluster = clustering.cluster_at(lat, lon)?;
+        Some(&cluster.members)
+    }
+
+    // combined proj * view * model of primary camera, for syncing external rendering
+    // or picking with the globe. left half camera in split view
+    pub fn mvp_matrix(&self) -> Mat4 {
+        self.mvp.view_projection()
+    }
+
+    // get logical pixel position of model space point, and if it is visible
+    // on the camera facing side of the globe, None if behind camera
+    // uses primary camera, relative to left half in split view
+    // globe positions are moved onto map in flat mode, where nothing is occluded
+    pub fn project(&self, world: Vec3) -> Option<(f32, f32, bool)> {
+        if self.mvp.map_mode == MapMode::Flat {
+            let flat = Vec3::from(flat_position(world.to_array()));
+            let (x, y) = project(self.mvp_matrix(), flat, self.width, self.height)?;
+            return Some((x, y, true));
+        }
+        let (x, y) = project(self.mvp_matrix(), world, self.width, self.height)?;
+        let eye = (self.mvp.view.data * self.mvp.model.data)
+            .inverse()
+            .transform_point3(Vec3::ZERO);
+        // parallel orthographic rays see the whole camera facing hemisphere at any distance
+        let visible = match self.mvp.projection {
+            Projection::Perspective => !is_occluded(eye, world, 1.0),
+            Projection::Orthographic => world.dot(eye) > 0.0,
+        };
+        Some((x, y, visible))
+    }
+
+    // update projection for new logical window size
+    pub fn resize(
+        &mut self,
+        gl: &glow::Context,
+        width: f64,
+        height: f64,
+    ) -> Result<(), VisGlError> {
+        if let Some(split) = self.split.as_mut() {
+            split.mvp.set_aspect(width * 0.5, height)?;
+            self.mvp.set_aspect(width * 0.5, height)?;
+        } else {
+            self.mvp.set_aspect(width, height)?;
+        }
+        self.mvp.proj.apply(gl, &VisGl::programs(self))?;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    // set point size in logical pixels
+    pub fn set_point_size(&mut self, gl: &glow::Context, size: f32) -> Result<(), VisGlError> {
+        if let Some(points) = self.points.as_mut() {
+            points.set_point_size(gl, size, self.dpi)?;
+        }
+        Ok(())
+    }
+
+    // outline points in color with width in logical pixels, None removes outline
+    pub fn set_point_outline(
+        &mut self,
+        gl: &glow::Context,
+        color: Option<[f32; 4]>,
+        width: f32,
+    ) -> Result<(), VisGlError> {
+        if let Some(points) = self.points.as_mut() {
+            points.set_outline(gl, color, width, self.dpi)?;
+        }
+        Ok(())
+    }
+
+    // reapply logical pixel sizes for new device pixel ratio
+    pub fn set_dpi(&mut self, gl: &glow::Context, dpi: f64) -> Result<(), VisGlError> {
+        self.dpi = dpi;
+        self.globe.set_point_size(gl, self.globe.point_size, dpi)?;
+        if let Some(points) = self.points.as_mut() {
+            points.set_point_size(gl, points.size, dpi)?;
+        }
+        self.lines.set_width(gl, self.lines.width, dpi)?;
+        Ok(())
+    }
+
+    // recreate all gl resources after context loss, keeping camera, view settings and
+    // runtime styling. markers, lines and point times are uploaded again from copies kept
+    // on upload. point positions, colors and sizes must be passed again, the run loop
+    // reads them from VisState on the next frame. render passes are dropped and set up
+    // again, so they must recreate their own resources in setup. vertex colors and
+    // textures are reset to options
+    pub fn restore(
+        &mut self,
+        context: &VisContext,
+        options: &VisOptions,
+    ) -> Result<(), VisGlError> {
+        self.restore_with_gl(&context.gl, &context.shader_version, options)
+    }
+
+    // restore for vis created with new_with_gl, see restore
+    pub fn restore_with_gl(
+        &mut self,
+        gl: &glow::Context,
+        version: &str,
+        options: &VisOptions,
+    ) -> Result<(), VisGlError> {
+        let mut restored = VisGl::new_with_gl(gl, version, self.dpi, options)?;
+        restored.globe.restore_from(gl, &self.globe, self.dpi)?;
+        if let (Some(points), Some(lost)) = (restored.points.as_mut(), &self.points) {
+            points.restore_from(gl, lost, self.dpi)?;
+        }
+        restored.markers.restore_from(gl, &self.markers)?;
+        restored.lines.restore_from(gl, &self.lines, self.dpi)?;
+        restored.paths.restore_from(gl, &self.paths)?;
+        // swap in new gl resources, leaving lost ones in restored to be dropped
+        std::mem::swap(&mut restored.globe, &mut self.globe);
+        std::mem::swap(&mut restored.points, &mut self.points);
+        std::mem::swap(&mut restored.markers, &mut self.markers);
+        std::mem::swap(&mut restored.lines, &mut self.lines);
+        std::mem::swap(&mut restored.paths, &mut self.paths);
+        std::mem::swap(&mut restored.clustering, &mut self.clustering);
+        std::mem::swap(&mut restored.program_cache, &mut self.program_cache);
+        self.shader_errors = std::mem::take(&mut restored.shader_errors);
+        // old handles belong to lost context, deleting them only frees glow's bookkeeping
+        restored.drop(gl);
+        for (_, pass) in &self.passes {
+            Drop::drop(pass.as_ref(), gl);
+        }
+        self.setup_gl(gl)
+    }
+
+    // replace line segments drawn with points and markers, e.g. from lines::graticule
+    // or lines::great_circle_arc
+    pub fn set_lines(&mut self, gl: &glow::Context, segments: &[LineSegment]) {
+        self.lines.set_segments(gl, segments);
+    }
+
+    // replace one pixel paths drawn after line segments, e.g. from lines::great_circle_path
+    pub fn set_paths(&mut self, gl: &glow::Context, paths: &[LinePath]) {
+        self.paths.set_paths(gl, paths);
+    }
+
+    // set line width in logical pixels
+    pub fn set_line_width(&mut self, gl: &glow::Context, width: f32) -> Result<(), VisGlError> {
+        self.lines.set_width(gl, width, self.dpi)?;
+        Ok(())
+    }
+
+    // set uniform by name on every vis program declaring it, for animating custom shader
+    // effects. errors if no program declares it, custom pass programs aren't included
+    pub fn set_uniform(
+        &self,
+        gl: &glow::Context,
+        name: &str,
+        value: UniformValue,
+    ) -> Result<(), VisGlError> {
+        let mut found = false;
+        for program in VisGl::programs(self) {
+            if let Some(location) = program.uniform_location(gl, name) {
+                program.bind(gl);
+                value.set(gl, &location);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(UniformError::Location(name.to_string()).into());
+        }
+        Ok(())
+    }
+
+    // advance camera animations by delta ms and draw one frame, clearing first. for driving
+    // vis from an external event loop. pass point data only when it changed, None keeps
+    // the previous upload, clusters and interpolation keyframe
+    pub fn frame(
+        &mut self,
+        gl: &glow::Context,
+        delta_ms: f32,
+        point_data: Option<&[f32]>,
+        markers: Option<&[Marker]>,
+    ) -> Result<(), VisGlError> {
+        self.step_animation(gl, delta_ms)?;
+        if let Some(p) = point_data.filter(|p| self.fit_pending && !p.is_empty()) {
+            self.fit_to_points(gl, p)?;
+        }
+        unsafe {
+            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+        self.draw(gl, point_data, markers)
+    }
+
     // set gl features and required values for start of draw loop
-    pub fn setup_gl(&self, gl: &glow::Context) -> Result<(), VisGlError> {
+    pub fn setup_gl(&mut self, gl: &glow::Context) -> Result<(), VisGlError> {
+        self.apply_clear_color(gl);
+        // paths break strips on restart index, drawn as separate lines without it
+        enable_primitive_restart(gl);
         unsafe {
-            gl.clear_color(0.0, 0.0, 0.0, 1.0);
             gl.enable(glow::DEPTH_TEST);
             // point size feature not needed for wasm
             #[cfg(not(target_arch = "wasm32"))]
             gl.enable(glow::PROGRAM_POINT_SIZE);
+            // convert linear output to srgb in framebuffer on native
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.srgb {
+                gl.enable(glow::FRAMEBUFFER_SRGB);
+            }
+        }
+        // bind each program once for all initial uniforms
+        for program in VisGl::programs(self) {
+            program.bind(gl);
+            self.mvp.apply_bound(gl, program)?;
+            self.gamma.apply_optional(gl, program);
+            self.flat_map.apply_optional(gl, program);
+        }
+        if let Some(points) = &self.points {
+            points.apply_uniforms(gl)?;
+        }
+        self.globe.apply_uniforms(gl)?;
+        for (_, pass) in &mut self.passes {
+            pass.setup(gl);
         }
-        self.mvp.apply(gl, &VisGl::programs(self)).unwrap();
         Ok(())
     }
 
+    // background color, transparent background clears alpha to 0 instead of 1
+    fn apply_clear_color(&self, gl: &glow::Context) {
+        let [r, g, b] = self.background;
+        let alpha = if self.transparent_background {
+            0.0
+        } else {
+            1.0
+        };
+        unsafe {
+            gl.clear_color(r, g, b, alpha);
+        }
+    }
+
     fn programs(vis: &VisGl) -> Vec<&Program> {
-        vec![&vis.points.program, &vis.globe.program]
+        let mut programs = vec![
+            vis.markers.program.as_ref(),
+            &vis.globe.program,
+            &vis.lines.program,
+            &vis.paths.program,
+        ];
+        if let Some(points) = &vis.points {
+            programs.push(&points.program);
+        }
+        // cluster markers share marker program, matrices only need setting once
+        if let Some(clustering) = &vis.clustering {
+            if clustering.markers.program.id != vis.markers.program.id {
+                programs.push(&clustering.markers.program);
+            }
+        }
+        programs
+    }
+}
+
+// draw points, or cluster markers in their place when zoomed out with clustering enabled
+// clustering follows primary camera distance in split view
+fn draw_points(
+    gl: &glow::Context,
+    points: Option<&mut Points>,
+    clustering: Option<&mut ClusterLayer>,
+    point_data: Option<&[f32]>,
+    distance: f32,
+) {
+    let Some(points) = points else {
+        return;
+    };
+    let Some(clustering) = clustering else {
+        points.draw_slice(gl, point_data);
+        return;
+    };
+    if clustering.update(gl, point_data, distance) {
+        clustering.markers.draw(gl, None);
+    } else {
+        points.draw_slice(gl, clustering.unclustered_data(point_data));
+    }
+}
+
+// flat map pan that centers lat / lon in view
+fn flat_center(lat: f32, lon: f32) -> Vec2 {
+    Vec2::new(lon.to_radians(), lat.to_radians())
+}
+
+// physical pixel viewport rects (x, y, width, height) for left and right halves of window
+pub fn split_viewports(width: f64, height: f64, dpi: f64) -> [(i32, i32, i32, i32); 2] {
+    let full = (width * dpi) as i32;
+    let half = full / 2;
+    let height = (height * dpi) as i32;
+    [(0, 0, half, height), (half, 0, full - half, height)]
+}
+
+// use result of custom shader init if present, falling back to built in on error if enabled
+fn with_fallback<T, E: Into<VisGlError>>(
+    custom: Option<Result<T, E>>,
+    builtin: impl FnOnce() -> Result<T, E>,
+    fallback: bool,
+    errors: &mut Vec<VisGlError>,
+) -> Result<T, VisGlError> {
+    match custom {
+        None => builtin().map_err(|e| e.into()),
+        Some(Ok(value)) => Ok(value),
+        Some(Err(err)) if fallback => {
+            errors.push(err.into());
+            builtin().map_err(|e| e.into())
+        }
+        Some(Err(err)) => Err(err.into()),
     }
 }
 
 impl Drop for VisGl {
     fn drop(&self, gl: &glow::Context) {
         self.globe.drop(gl);
-        self.points.drop(gl);
+        if let Some(points) = &self.points {
+            points.drop(gl);
+        }
+        self.markers.drop(gl);
+        self.lines.drop(gl);
+        self.paths.drop(gl);
+        if let Some(clustering) = &self.clustering {
+            clustering.drop(gl);
+        }
+        for (_, pass) in &self.passes {
+            Drop::drop(pass.as_ref(), gl);
+        }
+        // after layers, deletes programs they shared
+        self.program_cache.drop(gl);
     }
 }
 
@@ -88,27 +1293,311 @@ pub struct MvpMatrices {
     pub proj: UniformMatrix,
     pub view: UniformMatrix,
     pub model: UniformMatrix,
+    // globe rotation, model matrix is rebuilt from this to avoid drift
+    pub orientation: Quat,
+    // camera distance from globe center, view matrix is rebuilt from this on zoom
+    pub distance: f32,
+    // fixed spin axis tilt, applied to globe before orientation
+    pub tilt: Quat,
+    pub projection: Projection,
+    // viewport width / height, kept to rebuild projection on zoom or projection change
+    pub aspect: f32,
+    pub map_mode: MapMode,
+    // flat map lon / lat in radians at center of view, replaces orientation in flat mode
+    pub pan: Vec2,
+    // 0 for globe, 1 for flat map, in between while animating from one to the other
+    pub morph: f32,
+    // camera offset in globe radii from globe pan drags, view matrix is rebuilt from this
+    pub shift: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,
+    // parallel projection, zoom scales view extent to match perspective size at globe center
+    Orthographic,
+}
+
+// how globe surface is laid out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapMode {
+    // 3D sphere rotated by drag
+    Globe,
+    // equirectangular map in z = 0 plane, lon along x and lat along y in radians,
+    // panned by drag and never rotated
+    Flat,
+}
+
+pub const DEFAULT_DISTANCE: f32 = 2.0;
+
+// half height of view in globe radii for polar view, small border around hemisphere
+const POLAR_VIEW_MARGIN: f32 = 1.05;
+
+// camera looking at globe center from distance along +z
+pub fn view_from_distance(distance: f32) -> Mat4 {
+    Mat4::look_at_rh(Vec3::new(0.0, 0.0, distance), Vec3::ZERO, Vec3::Y)
+}
+
+// max camera shift from globe pan in globe radii, keeps globe partly in view
+const MAX_SHIFT: f32 = 1.0;
+
+// screen space pan from physical pixel drag deltas, scaled so the plane through globe
+// center follows the cursor. used for flat map pan and globe camera shift
+fn pan_from_drag(distance: f32, height: f64, dx: f64, dy: f64) -> Vec2 {
+    let scale = (2.0 * distance * (FOV_Y * 0.5).tan()) as f64 / height;
+    Vec2::new((-dx * scale) as f32, (dy * scale) as f32)
+}
+
+// perspective projection parameters
+// near plane must stay in front of globe surface at closest zoom, MIN_DISTANCE - 1.0
+// or front of globe is clipped and picking rays start inside the sphere
+const FOV_Y: f32 = 1.25;
+const NEAR: f32 = 0.01;
+const FAR: f32 = 10.0;
+
+// smallest window dimension used for aspect ratio
+// prevents infinite / NaN projection while window or canvas has 0 size
+const MIN_DIMENSION: f64 = 1.0;
+
+// get aspect ratio from window dimensions, clamping 0 size dimensions
+pub fn aspect_ratio(width: f64, height: f64) -> Result<f32, MvpError> {
+    if !width.is_finite() || !height.is_finite() || width < 0.0 || height < 0.0 {
+        return Err(MvpError::Dimensions(width, height));
+    }
+    if width < MIN_DIMENSION || height < MIN_DIMENSION {
+        log::warn!(
+            "window dimensions {}x{} below minimum, clamping aspect ratio",
+            width,
+            height
+        );
+    }
+    Ok((width.max(MIN_DIMENSION) / height.max(MIN_DIMENSION)) as f32)
+}
+
+// projection matrix for mode, orthographic extent at distance matches
+// perspective view of plane through globe center so switching modes keeps framing
+pub fn projection_matrix(projection: Projection, aspect: f32, distance: f32) -> Mat4 {
+    match projection {
+        Projection::Perspective => Mat4::perspective_rh_gl(FOV_Y, aspect, NEAR, FAR),
+        Projection::Orthographic => {
+            let half_height = distance * (FOV_Y * 0.5).tan();
+            let half_width = half_height * aspect;
+            Mat4::orthographic_rh_gl(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                NEAR,
+                FAR,
+            )
+        }
+    }
 }
 
 impl MvpMatrices {
     // initialize matrices with default values
-    pub fn new_default(aspect: f32) -> Result<Self, MvpError> {
+    pub fn new_default(width: f64, height: f64) -> Result<Self, MvpError> {
+        let aspect = aspect_ratio(width, height)?;
+        let projection = Projection::Perspective;
+        let distance = DEFAULT_DISTANCE;
         let proj = UniformMatrix::new(
             "projMatrix",
-            Mat4::perspective_rh_gl(1.25, aspect, 0.1, 10.0),
-        );
-        let view = UniformMatrix::new(
-            "viewMatrix",
-            Mat4::look_at_rh(Vec3::new(0.0, 0.0, 2.0), Vec3::ZERO, Vec3::Y),
+            projection_matrix(projection, aspect, distance),
         );
+        let view = UniformMatrix::new("viewMatrix", view_from_distance(distance));
         let model = UniformMatrix::new("modelMatrix", Mat4::IDENTITY);
-        Ok(Self { proj, view, model })
+        let orientation = Quat::IDENTITY;
+        let tilt = Quat::IDENTITY;
+        Ok(Self {
+            proj,
+            view,
+            model,
+            orientation,
+            distance,
+            tilt,
+            projection,
+            aspect,
+            map_mode: MapMode::Globe,
+            pan: Vec2::ZERO,
+            morph: 0.0,
+            shift: Vec2::ZERO,
+        })
     }
 
+    // bind each program once and set all matrices while bound
     pub fn apply(&self, gl: &glow::Context, programs: &[&Program]) -> Result<(), MvpError> {
-        self.proj.apply(gl, programs)?;
-        self.view.apply(gl, programs)?;
-        self.model.apply(gl, programs)?;
+        for &program in programs {
+            program.bind(gl);
+            self.apply_bound(gl, program)?;
+        }
+        Ok(())
+    }
+
+    // set globe rotation and rebuild model matrix
+    pub fn set_orientation(&mut self, orientation: Quat) {
+        self.orientation = orientation.normalize();
+        self.rebuild_model();
+    }
+
+    // rebuild model matrix from orientation and tilt for globe, pan for flat map,
+    // rotation unwinds as pan is applied while morphing between them
+    fn rebuild_model(&mut self) {
+        let rotation = (self.orientation * self.tilt).slerp(Quat::IDENTITY, self.morph);
+        let translation = -self.pan.extend(0.0) * self.morph;
+        self.model.data = Mat4::from_rotation_translation(rotation, translation);
+    }
+
+    // orientation is kept while flat so switching back restores globe view
+    pub fn set_map_mode(&mut self, map_mode: MapMode) {
+        self.map_mode = map_mode;
+        self.morph = match map_mode {
+            MapMode::Globe => 0.0,
+            MapMode::Flat => 1.0,
+        };
+        self.rebuild_model();
+    }
+
+    // set morph between globe and flat map without changing map mode
+    pub fn set_morph(&mut self, morph: f32) {
+        self.morph = morph.clamp(0.0, 1.0);
+        self.rebuild_model();
+    }
+
+    // lat / lon at center of view, from pan in flat mode or orientation for globe
+    pub fn view_center(&self) -> (f32, f32) {
+        if self.map_mode == MapMode::Flat {
+            return (self.pan.y.to_degrees(), self.pan.x.to_degrees());
+        }
+        let center = (self.orientation * self.tilt).inverse() * Vec3::Z;
+        xyz_to_lat_lon(center.to_array())
+    }
+
+    // set flat map center, clamped to map bounds
+    pub fn set_pan(&mut self, pan: Vec2) {
+        let bound = Vec2::new(PI, FRAC_PI_2);
+        self.pan = pan.clamp(-bound, bound);
+        self.rebuild_model();
+    }
+
+    // set globe pan camera shift, clamped to keep globe in view
+    pub fn set_shift(&mut self, shift: Vec2) {
+        self.shift = shift.clamp(Vec2::splat(-MAX_SHIFT), Vec2::splat(MAX_SHIFT));
+        self.rebuild_view();
+    }
+
+    // camera at distance, moved parallel to screen by shift
+    fn rebuild_view(&mut self) {
+        let shift = Mat4::from_translation(-self.shift.extend(0.0));
+        self.view.data = shift * view_from_distance(self.distance);
+    }
+
+    // set spin axis tilt and rebuild model matrix
+    // tilt is applied after geographic placement, so points / lat lon picking stay consistent
+    pub fn set_tilt(&mut self, tilt: Quat) {
+        self.tilt = tilt.normalize();
+        self.rebuild_model();
+    }
+
+    // orientation that brings lat / lon to face the camera, compensating for tilt
+    pub fn center_orientation(&self, lat: f32, lon: f32) -> Quat {
+        orientation_for_center(lat, lon) * self.tilt.inverse()
+    }
+
+    // set camera distance and rebuild view matrix, and projection since
+    // orthographic extent follows distance
+    pub fn set_distance(&mut self, distance: f32) {
+        self.distance = distance;
+        self.rebuild_view();
+        self.proj.data = projection_matrix(self.projection, self.aspect, distance);
+    }
+
+    // update projection matrix for new window dimensions
+    pub fn set_aspect(&mut self, width: f64, height: f64) -> Result<(), MvpError> {
+        self.aspect = aspect_ratio(width, height)?;
+        self.proj.data = projection_matrix(self.projection, self.aspect, self.distance);
+        Ok(())
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.proj.data = projection_matrix(projection, self.aspect, self.distance);
+    }
+
+    // orthographic view straight down on north or south pole, framed to the hemisphere
+    pub fn set_polar_view(&mut self, north: bool) {
+        let lat = if north { 90.0 } else { -90.0 };
+        self.set_orientation(self.center_orientation(lat, 0.0));
+        self.shift = Vec2::ZERO;
+        self.projection = Projection::Orthographic;
+        // fit hemisphere in shorter of width / height
+        let half_height = POLAR_VIEW_MARGIN / self.aspect.min(1.0);
+        let distance = half_height / (FOV_Y * 0.5).tan();
+        self.set_distance(distance.clamp(MIN_DISTANCE, MAX_DISTANCE));
+    }
+
+    // combined proj * view * model, transforms model space positions to clip space
+    pub fn view_projection(&self) -> Mat4 {
+        self.proj.data * self.view.data * self.model.data
+    }
+
+    pub fn proj_matrix(&self) -> Mat4 {
+        self.proj.data
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        self.view.data
+    }
+
+    // globe rotation including axial tilt
+    pub fn model_matrix(&self) -> Mat4 {
+        self.model.data
+    }
+
+    // camera position in globe model space, for sorting by distance from camera
+    pub fn camera_position(&self) -> Vec3 {
+        (self.view.data * self.model.data)
+            .inverse()
+            .transform_point3(Vec3::ZERO)
+    }
+
+    pub fn camera_state(&self) -> CameraState {
+        CameraState {
+            orientation: self.orientation.to_array(),
+            distance: self.distance,
+            orthographic: self.projection == Projection::Orthographic,
+        }
+    }
+
+    // restore saved camera, distance clamped to zoom limits and zero orientation ignored
+    // globe pan isn't saved, so restored views are centered
+    pub fn set_camera_state(&mut self, state: &CameraState) {
+        self.shift = Vec2::ZERO;
+        let orientation = Quat::from_array(state.orientation);
+        if orientation.length_squared() > 0.0 {
+            self.set_orientation(orientation);
+        }
+        self.projection = if state.orthographic {
+            Projection::Orthographic
+        } else {
+            Projection::Perspective
+        };
+        self.set_distance(state.distance.clamp(MIN_DISTANCE, MAX_DISTANCE));
+    }
+
+    // copy of current matrices for custom render passes
+    pub fn camera_view(&self) -> CameraView {
+        CameraView {
+            proj: self.proj.data,
+            view: self.view.data,
+            model: self.model.data,
+        }
+    }
+
+    // set all matrices on already bound program
+    pub fn apply_bound(&self, gl: &glow::Context, program: &Program) -> Result<(), MvpError> {
+        self.proj.apply_bound(gl, program)?;
+        self.view.apply_bound(gl, program)?;
+        self.model.apply_bound(gl, program)?;
         Ok(())
     }
 }
@@ -122,13 +1611,222 @@ pub enum VisGlError {
     #[error("{0}")]
     Points(#[from] crate::points::PointsError),
     #[error("{0}")]
+    Markers(#[from] crate::markers::MarkersError),
+    #[error("{0}")]
+    Lines(#[from] crate::lines::LinesError),
+    #[error("{0}")]
     Mvp(#[from] MvpError),
     #[error("{0}")]
-    UniformMatrix(#[from] crate::gl_wrap::UniformMatrixError),
+    Uniform(#[from] crate::gl_wrap::UniformError),
+    #[cfg(feature = "serde")]
+    #[error("{0}")]
+    SceneJson(#[from] serde_json::Error),
 }
 
 #[derive(Error, Debug)]
 pub enum MvpError {
     #[error("{0}")]
-    UniformMatrix(#[from] crate::gl_wrap::UniformMatrixError),
+    Uniform(#[from] crate::gl_wrap::UniformError),
+    #[error("Invalid window dimensions {0}x{1}")]
+    Dimensions(f64, f64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_height_projection() {
+        let mvp = MvpMatrices::new_default(500.0, 0.0).unwrap();
+        assert!(mvp.proj.data.is_finite());
+
+        let mut mvp = MvpMatrices::new_default(0.0, 0.0).unwrap();
+        assert!(mvp.proj.data.is_finite());
+        mvp.set_aspect(800.0, 0.0).unwrap();
+        assert!(mvp.proj.data.is_finite());
+    }
+
+    #[test]
+    fn test_shader_fallback() {
+        use crate::{
+            gl_wrap::{ProgramError, ShaderError},
+            globe::GlobeError,
+        };
+        let failed = || {
+            let err = ShaderError::Compilation("0:1: syntax error".to_string());
+            Some(Err::<u32, _>(GlobeError::Program(err.into())))
+        };
+        let builtin = || Ok::<u32, GlobeError>(1);
+
+        // compile error is kept as is for error callback
+        let mut errors = vec![];
+        assert_eq!(
+            with_fallback(failed(), builtin, true, &mut errors).unwrap(),
+            1
+        );
+        assert!(matches!(
+            &errors[..],
+            [VisGlError::Globe(GlobeError::Program(ProgramError::Shader(
+                ShaderError::Compilation(log)
+            )))] if log == "0:1: syntax error"
+        ));
+
+        let mut errors = vec![];
+        assert!(matches!(
+            with_fallback(failed(), builtin, false, &mut errors),
+            Err(VisGlError::Globe(GlobeError::Program(_)))
+        ));
+        assert!(errors.is_empty());
+
+        let custom = Some(Ok::<u32, GlobeError>(2));
+        assert_eq!(
+            with_fallback(custom, builtin, true, &mut errors).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_split_viewports() {
+        let [left, right] = split_viewports(500.0, 300.0, 2.0);
+        assert_eq!(left, (0, 0, 500, 600));
+        assert_eq!(right, (500, 0, 500, 600));
+
+        // odd width covered without gap
+        let [left, right] = split_viewports(501.0, 300.0, 1.0);
+        assert_eq!(left.2 + right.2, 501);
+        assert_eq!(right.0, left.2);
+    }
+
+    #[test]
+    fn test_axial_tilt() {
+        let mut mvp = MvpMatrices::new_default(500.0, 500.0).unwrap();
+        mvp.set_tilt(axial_tilt(23.4));
+        let pole = mvp.model.data.transform_vector3(Vec3::Y);
+        assert!((pole.angle_between(Vec3::Y) - 23.4_f32.to_radians()).abs() < 1e-5);
+
+        // centering still brings lat / lon to face camera
+        mvp.set_orientation(mvp.center_orientation(40.0, -75.0));
+        let pos = Vec3::from(crate::geo::lat_lon_to_xyz(40.0, -75.0, 1.0));
+        let facing = mvp.model.data.transform_vector3(pos);
+        assert!(facing.abs_diff_eq(Vec3::Z, 1e-5));
+    }
+
+    #[test]
+    fn test_view_projection() {
+        let mut mvp = MvpMatrices::new_default(500.0, 500.0).unwrap();
+        mvp.set_orientation(mvp.center_orientation(10.0, 20.0));
+        let expected = mvp.proj_matrix() * mvp.view_matrix() * mvp.model_matrix();
+        assert_eq!(mvp.view_projection(), expected);
+        assert_eq!(mvp.camera_view().mvp(), expected);
+
+        // centered surface point lands at center of clip space
+        let pos = Vec3::from(crate::geo::lat_lon_to_xyz(10.0, 20.0, 1.0));
+        let clip = mvp.view_projection().project_point3(pos);
+        assert!(clip.truncate().abs_diff_eq(glam::Vec2::ZERO, 1e-5));
+    }
+
+    #[test]
+    fn test_polar_view() {
+        let mut mvp = MvpMatrices::new_default(800.0, 500.0).unwrap();
+        mvp.set_tilt(axial_tilt(23.4));
+        for (north, pole) in [(true, Vec3::Y), (false, Vec3::NEG_Y)] {
+            mvp.set_polar_view(north);
+            // pole faces camera
+            let facing = mvp.model_matrix().transform_vector3(pole);
+            assert!(facing.abs_diff_eq(Vec3::Z, 1e-5));
+            // camera sits above pole in model space
+            let eye = mvp.camera_position();
+            assert!(eye.abs_diff_eq(pole * mvp.distance, 1e-4));
+            // orthographic, no perspective divide
+            assert_eq!(mvp.projection, Projection::Orthographic);
+            assert_eq!(mvp.proj_matrix().row(3), glam::Vec4::W);
+            // equator at limb just inside top / bottom of view
+            let limb = mvp
+                .view_projection()
+                .project_point3(Vec3::new(0.0, 0.0, 1.0));
+            let edge = limb.truncate().length();
+            assert!((edge - 1.0 / POLAR_VIEW_MARGIN).abs() < 1e-4, "{edge}");
+        }
+
+        // switching back keeps framing at globe center
+        mvp.set_projection(Projection::Perspective);
+        assert_eq!(
+            mvp.proj_matrix(),
+            Mat4::perspective_rh_gl(FOV_Y, mvp.aspect, NEAR, FAR)
+        );
+    }
+
+    #[test]
+    fn test_camera_state_round_trip() {
+        let mut mvp = MvpMatrices::new_default(800.0, 500.0).unwrap();
+        mvp.set_tilt(axial_tilt(23.4));
+        mvp.set_orientation(Quat::from_euler(glam::EulerRot::XYZ, 0.3, -1.2, 0.4));
+        mvp.set_projection(Projection::Orthographic);
+        mvp.set_distance(2.5);
+        let state = mvp.camera_state();
+
+        let mut restored = MvpMatrices::new_default(800.0, 500.0).unwrap();
+        restored.set_tilt(axial_tilt(23.4));
+        restored.set_camera_state(&state);
+        assert!(restored
+            .view_projection()
+            .abs_diff_eq(mvp.view_projection(), 1e-6));
+        assert!(restored
+            .model_matrix()
+            .abs_diff_eq(mvp.model_matrix(), 1e-6));
+
+        // out of range distance clamped, zero orientation keeps current
+        restored.set_camera_state(&CameraState {
+            orientation: [0.0; 4],
+            distance: 1000.0,
+            orthographic: false,
+        });
+        assert_eq!(restored.distance, MAX_DISTANCE);
+        assert!(restored
+            .model_matrix()
+            .abs_diff_eq(mvp.model_matrix(), 1e-6));
+    }
+
+    #[test]
+    fn test_flat_map_mode() {
+        let mut mvp = MvpMatrices::new_default(400.0, 400.0).unwrap();
+        mvp.set_orientation(mvp.center_orientation(30.0, 60.0));
+        mvp.set_map_mode(MapMode::Flat);
+        mvp.set_pan(Vec2::new(1.0, 4.0));
+        // pan clamped to map, camera looks straight down on pan position
+        assert_eq!(mvp.pan, Vec2::new(1.0, FRAC_PI_2));
+        let eye = mvp.camera_position();
+        assert!((eye - Vec3::new(1.0, FRAC_PI_2, mvp.distance)).length() < 1e-5);
+
+        // orientation kept for switching back
+        mvp.set_map_mode(MapMode::Globe);
+        let expected = Mat4::from_quat(mvp.center_orientation(30.0, 60.0));
+        assert!(mvp.model_matrix().abs_diff_eq(expected, 1e-5));
+    }
+
+    #[test]
+    fn test_morph_model() {
+        let mut mvp = MvpMatrices::new_default(400.0, 400.0).unwrap();
+        mvp.set_orientation(mvp.center_orientation(20.0, -40.0));
+        let (lat, lon) = mvp.view_center();
+        assert!((lat - 20.0).abs() < 1e-3 && (lon + 40.0).abs() < 1e-3);
+        let globe = mvp.model_matrix();
+        mvp.set_pan(flat_center(lat, lon));
+        assert!(mvp.model_matrix().abs_diff_eq(globe, 1e-6));
+
+        // fully morphed model matches flat mode, and centers same lat / lon
+        mvp.set_morph(1.0);
+        let morphed = mvp.model_matrix();
+        mvp.set_map_mode(MapMode::Flat);
+        assert!(mvp.model_matrix().abs_diff_eq(morphed, 1e-6));
+        let (flat_lat, flat_lon) = mvp.view_center();
+        assert!((flat_lat - lat).abs() < 1e-3 && (flat_lon - lon).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_invalid_dimensions() {
+        assert!(MvpMatrices::new_default(500.0, -1.0).is_err());
+        assert!(MvpMatrices::new_default(f64::NAN, 500.0).is_err());
+        assert!(MvpMatrices::new_default(500.0, f64::INFINITY).is_err());
+    }
 }
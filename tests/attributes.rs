@@ -0,0 +1,53 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use globe_vis::{
+    gl_wrap::{Drop, VertexArray, VertexArrayError},
+    points::{Points, PointsError},
+};
+
+// custom point shader without time or color attributes
+const VERT: &str = "in vec4 position;
+uniform mat4 projMatrix;
+uniform mat4 viewMatrix;
+uniform mat4 modelMatrix;
+uniform float pointSize;
+void main() {
+    gl_Position = projMatrix * viewMatrix * modelMatrix * position;
+    gl_PointSize = pointSize;
+}";
+
+const FRAG: &str = "precision highp float;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(1.0);
+}";
+
+#[test]
+fn test_missing_attribute() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+
+    // optional attributes left out of custom shader are skipped
+    let mut points = Points::new_with_shaders(gl, common::SHADER_VERSION, VERT, FRAG).unwrap();
+    points.draw(gl, Some(vec![0.0, 0.0, 1.0]));
+    points.set_colors(gl, &[1.0, 0.0, 0.0, 1.0]);
+    points.draw(gl, None);
+
+    // required attribute error names attribute
+    let err = VertexArray::attrib_location(gl, &points.program, "notDeclared").unwrap_err();
+    assert!(matches!(&err, VertexArrayError::Location(name) if name == "notDeclared"));
+    assert!(err.to_string().contains("notDeclared"));
+    let err = VertexArray::attrib_location(gl, &points.program, "gl_VertexID").unwrap_err();
+    assert!(matches!(err, VertexArrayError::Reserved(_)));
+    points.drop(gl);
+
+    // position is required
+    let no_position = VERT.replace("in vec4 position;", "vec4 position = vec4(0.0);");
+    let err = Points::new_with_shaders(gl, common::SHADER_VERSION, &no_position, FRAG);
+    assert!(matches!(
+        err,
+        Err(PointsError::VertexArray(VertexArrayError::Location(name))) if name == "position"
+    ));
+}
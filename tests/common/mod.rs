@@ -0,0 +1,138 @@
+use glam::Mat4;
+use globe_vis::{
+    gl_wrap::{self, ColorFormat, Drop, Framebuffer, UniformFloat, UniformMatrix},
+    points::Points,
+};
+use glow::HasContext;
+use khronos_egl as egl;
+
+#[allow(dead_code)]
+pub const SHADER_VERSION: &str = "#version 410";
+
+// mesa extension for creating egl display without window system
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+// headless gl context for integration tests
+// egl instance kept alive with context since gl functions are loaded from it
+pub struct HeadlessGl {
+    pub gl: glow::Context,
+    _egl: egl::DynamicInstance<egl::EGL1_5>,
+}
+
+impl HeadlessGl {
+    // create surfaceless gl 4.1 core context and make current on calling thread
+    // returns None if egl or a suitable driver is unavailable
+    pub fn new() -> Option<Self> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_5>::load_required() }.ok()?;
+        let display = egl
+            .get_platform_display(
+                PLATFORM_SURFACELESS_MESA,
+                egl::DEFAULT_DISPLAY,
+                &[egl::ATTRIB_NONE],
+            )
+            .ok()?;
+        egl.initialize(display).ok()?;
+        egl.bind_api(egl::OPENGL_API).ok()?;
+        let config = egl
+            .choose_first_config(display, &[egl::SURFACE_TYPE, egl::PBUFFER_BIT, egl::NONE])
+            .ok()??;
+        let ctx_attribs = [
+            egl::CONTEXT_MAJOR_VERSION,
+            4,
+            egl::CONTEXT_MINOR_VERSION,
+            1,
+            egl::CONTEXT_OPENGL_PROFILE_MASK,
+            egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            egl::NONE,
+        ];
+        let ctx = egl
+            .create_context(display, config, None, &ctx_attribs)
+            .ok()?;
+        egl.make_current(display, None, None, Some(ctx)).ok()?;
+        let loader = |name: &str| {
+            egl.get_proc_address(name)
+                .map_or(std::ptr::null(), |f| f as *const _)
+        };
+        let gl = unsafe { glow::Context::from_loader_function(loader) };
+        gl_wrap::load_primitive_restart(loader);
+        Some(Self { gl, _egl: egl })
+    }
+}
+
+// get headless context or skip test when not available on this machine
+#[macro_export]
+macro_rules! headless_or_skip {
+    () => {
+        match common::HeadlessGl::new() {
+            Some(headless) => headless,
+            None => {
+                eprintln!("skipping, headless gl context unavailable");
+                return;
+            }
+        }
+    };
+}
+
+// average rgb in 0 to 1 of each cell in grid x grid split of image, row major from bottom
+// compared instead of exact pixels so rasterization differences between drivers pass
+#[allow(dead_code)]
+pub fn region_averages(pixels: &[u8], width: i32, height: i32, grid: i32) -> Vec<[f32; 3]> {
+    let mut sums = vec![[0.0; 3]; (grid * grid) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let cell = ((y * grid / height) * grid + x * grid / width) as usize;
+            let i = ((y * width + x) * 4) as usize;
+            for c in 0..3 {
+                sums[cell][c] += pixels[i + c] as f32 / 255.0;
+            }
+        }
+    }
+    let count = ((width / grid) * (height / grid)) as f32;
+    sums.iter().map(|s| s.map(|c| c / count)).collect()
+}
+
+// panic with region index and values if any region channel differs by more than tolerance
+#[allow(dead_code)]
+pub fn assert_regions_near(actual: &[[f32; 3]], expected: &[[f32; 3]], tolerance: f32) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        let near = a.iter().zip(e).all(|(a, e)| (a - e).abs() <= tolerance);
+        assert!(near, "region {} is {:?}, expected {:?}", i, a, e);
+    }
+}
+
+// side of square rgba8 target render_points draws into
+#[allow(dead_code)]
+pub const POINT_TARGET: i32 = 16;
+
+// draw points with identity matrices and no gamma, so a point at the origin covers the
+// center of a POINT_TARGET square cleared to black, returning its pixels
+#[allow(dead_code)]
+pub fn render_points(gl: &glow::Context, points: &mut Points, data: Option<&[f32]>) -> Vec<u8> {
+    let framebuffer = Framebuffer::new(gl, POINT_TARGET, POINT_TARGET, ColorFormat::Rgba8).unwrap();
+    for name in ["projMatrix", "viewMatrix", "modelMatrix"] {
+        UniformMatrix::new(name, Mat4::IDENTITY)
+            .apply(gl, &[&points.program])
+            .unwrap();
+    }
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &[&points.program])
+        .unwrap();
+    unsafe {
+        gl.enable(glow::PROGRAM_POINT_SIZE);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+    }
+    points.draw_slice(gl, data);
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    let pixels = framebuffer.read_u8(gl).unwrap();
+    framebuffer.drop(gl);
+    pixels
+}
+
+// rgb at x, y of pixels from render_points, y from bottom
+#[allow(dead_code)]
+pub fn point_pixel(pixels: &[u8], x: i32, y: i32) -> [u8; 3] {
+    let i = ((y * POINT_TARGET + x) * 4) as usize;
+    [pixels[i], pixels[i + 1], pixels[i + 2]]
+}
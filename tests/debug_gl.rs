@@ -0,0 +1,42 @@
+#![cfg(all(target_os = "linux", feature = "debug_gl"))]
+
+mod common;
+
+use globe_vis::{
+    gl_wrap::{dump_state, Bind, Drop},
+    globe::Globe,
+};
+use glow::HasContext;
+
+#[test]
+fn test_dump_state() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    globe.program.bind(gl);
+    globe.vao.bind(gl);
+    globe.buffer.bind(gl);
+    unsafe {
+        gl.enable(glow::DEPTH_TEST);
+        gl.disable(glow::BLEND);
+        gl.viewport(1, 2, 30, 40);
+    }
+
+    let state = dump_state(gl);
+    assert_eq!(state.program, globe.program.id.0.get());
+    assert_eq!(state.vertex_array, globe.vao.id.0.get());
+    assert_eq!(state.array_buffer, globe.buffer.id.0.get());
+    assert_eq!(state.active_texture, 0);
+    assert!(state.depth_test && !state.blend);
+    assert_eq!(state.viewport, [1, 2, 30, 40]);
+    let printed = state.to_string();
+    assert!(printed.contains("depth test:           on"));
+    assert!(printed.contains("1, 2 30x40"));
+
+    unsafe { gl.use_program(None) };
+    assert_eq!(dump_state(gl).program, 0);
+    assert!(dump_state(gl)
+        .to_string()
+        .contains("program:              none"));
+    globe.drop(gl);
+}
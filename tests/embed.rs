@@ -0,0 +1,902 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use glam::Vec3;
+use globe_vis::{
+    coords::lat_lon_to_xyz,
+    gl_wrap::{
+        program_binds, ColorFormat, Drop, Framebuffer, ProgramError, ShaderError, ShaderSources,
+        UniformValue,
+    },
+    globe::GlobeError,
+    lines::{LinePath, LineSegment},
+    markers::Marker,
+    points::logical_to_physical,
+    vis_build::VisOptions,
+    vis_gl::{DrawOrder, MapMode, Projection, VisGl, VisGlError},
+    CameraView, PassStage, RenderPass,
+};
+use glow::HasContext;
+use glutin::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+        DeviceId, ElementState, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase,
+        WindowEvent,
+    },
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+const SIZE: i32 = 64;
+
+#[test]
+fn test_vis_gl_external_loop() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let options = VisOptions {
+        width: SIZE as f64,
+        height: SIZE as f64,
+        background: [0.0, 0.0, 0.0],
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+
+    // resize updates logical size from physical size and dpi
+    let resized = WindowEvent::Resized(PhysicalSize::new(SIZE as u32, SIZE as u32 / 2));
+    assert!(vis.handle_event(gl, &resized).unwrap());
+    assert_eq!((vis.width, vis.height), (SIZE as f64, SIZE as f64 / 2.0));
+    let resized = WindowEvent::Resized(PhysicalSize::new(SIZE as u32, SIZE as u32));
+    vis.handle_event(gl, &resized).unwrap();
+
+    // modifiers change drag behavior but not what's drawn
+    let modifiers = WindowEvent::ModifiersChanged(ModifiersState::SHIFT);
+    assert!(!vis.handle_event(gl, &modifiers).unwrap());
+    assert!(vis.mouse.modifiers.shift);
+
+    // globe drawn over background at center, corners left clear
+    vis.frame(gl, 16.0, None, None).unwrap();
+    let pixels = target.read_u8(gl).unwrap();
+    let pixel = |x: i32, y: i32| {
+        let i = 4 * (y * SIZE + x) as usize;
+        pixels[i..i + 3].iter().map(|c| *c as u32).sum::<u32>()
+    };
+    assert!(pixel(SIZE / 2, SIZE / 2) > 0);
+    assert_eq!(pixel(0, 0), 0);
+    vis.drop(gl);
+    target.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_set_uniform() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+
+    // set on programs declaring it, location is cached after first lookup
+    vis.set_uniform(gl, "opacity", UniformValue::Float(0.25))
+        .unwrap();
+    let program = &vis.globe.program;
+    let location = program.uniform_location(gl, "opacity").unwrap();
+    let mut value = [0.0];
+    unsafe {
+        gl.get_uniform_f32(program.id, &location, &mut value);
+    }
+    assert_eq!(value, [0.25]);
+
+    // undeclared name is an error, and stays undeclared in cache
+    assert!(vis
+        .set_uniform(gl, "glowIntensity", UniformValue::Float(1.0))
+        .is_err());
+    assert!(program.uniform_location(gl, "glowIntensity").is_none());
+    vis.drop(gl);
+}
+
+// synthetic input events, deprecated modifiers field is still required to construct them
+#[allow(deprecated)]
+fn cursor_moved(x: f64, y: f64) -> WindowEvent<'static> {
+    WindowEvent::CursorMoved {
+        device_id: unsafe { DeviceId::dummy() },
+        position: PhysicalPosition::new(x, y),
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+#[allow(deprecated)]
+fn left_button(state: ElementState) -> WindowEvent<'static> {
+    WindowEvent::MouseInput {
+        device_id: unsafe { DeviceId::dummy() },
+        state,
+        button: MouseButton::Left,
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+#[allow(deprecated)]
+fn scroll_lines(lines: f32) -> WindowEvent<'static> {
+    WindowEvent::MouseWheel {
+        device_id: unsafe { DeviceId::dummy() },
+        delta: MouseScrollDelta::LineDelta(0.0, lines),
+        phase: TouchPhase::Moved,
+        modifiers: ModifiersState::empty(),
+    }
+}
+
+// screen position of lat / lon on globe surface in ndc
+fn project(vis: &VisGl, lat: f32, lon: f32) -> Vec3 {
+    let clip = vis.mvp.view_projection() * Vec3::from(lat_lon_to_xyz(lat, lon, 1.0)).extend(1.0);
+    clip.truncate() / clip.w
+}
+
+#[test]
+fn test_vis_gl_drag_rotate() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let (cx, cy) = (vis.width * 0.5, vis.height * 0.5);
+    let start = project(&vis, 0.0, 0.0);
+
+    // moving without a press tracks cursor but doesn't change view
+    assert!(!vis.handle_event(gl, &cursor_moved(cx, cy)).unwrap());
+    assert_eq!(project(&vis, 0.0, 0.0), start);
+
+    // drag right and up carries surface under cursor with it
+    vis.handle_event(gl, &left_button(ElementState::Pressed))
+        .unwrap();
+    for i in 1..=10 {
+        let step = i as f64 * 4.0;
+        assert!(vis
+            .handle_event(gl, &cursor_moved(cx + step, cy - step))
+            .unwrap());
+    }
+    vis.handle_event(gl, &left_button(ElementState::Released))
+        .unwrap();
+    let end = project(&vis, 0.0, 0.0);
+    assert!(end.x > start.x + 0.01 && end.y > start.y + 0.01);
+    assert!(!vis.mouse.dragging);
+
+    // released button stops rotating
+    vis.handle_event(gl, &cursor_moved(cx, cy)).unwrap();
+    assert_eq!(project(&vis, 0.0, 0.0), end);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_shift_drag_pan() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let (cx, cy) = (vis.width * 0.5, vis.height * 0.5);
+    let orientation = vis.mvp.orientation;
+    let center = |vis: &VisGl| vis.mvp.view_projection().project_point3(Vec3::ZERO);
+
+    // shift drag moves globe center with cursor instead of rotating
+    let modifiers = WindowEvent::ModifiersChanged(ModifiersState::SHIFT);
+    vis.handle_event(gl, &modifiers).unwrap();
+    vis.handle_event(gl, &cursor_moved(cx, cy)).unwrap();
+    vis.handle_event(gl, &left_button(ElementState::Pressed))
+        .unwrap();
+    vis.handle_event(gl, &cursor_moved(cx + 20.0, cy - 10.0))
+        .unwrap();
+    vis.handle_event(gl, &left_button(ElementState::Released))
+        .unwrap();
+    assert_eq!(vis.mvp.orientation, orientation);
+    let moved = center(&vis);
+    assert!((moved.x as f64 - 40.0 / vis.width).abs() < 1e-4, "{moved}");
+    assert!((moved.y as f64 - 20.0 / vis.height).abs() < 1e-4, "{moved}");
+
+    // restoring a saved camera recenters
+    let state = vis.camera_state();
+    vis.set_camera_state(gl, &state).unwrap();
+    assert!(center(&vis).truncate().length() < 1e-6);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_roll_hidpi() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let dpi = 2.0;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, dpi, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let (cx, cy) = (vis.width * dpi * 0.5, vis.height * dpi * 0.5);
+    let start = vis.mvp.orientation;
+
+    // moving straight out from physical view center sweeps no angle, so doesn't roll
+    let ctrl = WindowEvent::ModifiersChanged(ModifiersState::CTRL);
+    vis.handle_event(gl, &ctrl).unwrap();
+    vis.handle_event(gl, &cursor_moved(cx + 20.0, cy)).unwrap();
+    vis.handle_event(gl, &left_button(ElementState::Pressed))
+        .unwrap();
+    vis.handle_event(gl, &cursor_moved(cx + 60.0, cy)).unwrap();
+    assert!(vis.mvp.orientation.abs_diff_eq(start, 1e-6));
+
+    // moving around center rolls
+    vis.handle_event(gl, &cursor_moved(cx, cy + 60.0)).unwrap();
+    assert!(!vis.mvp.orientation.abs_diff_eq(start, 1e-3));
+    vis.handle_event(gl, &left_button(ElementState::Released))
+        .unwrap();
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_project_orthographic() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    // near limb, hidden from perspective eye at distance 2 but facing camera
+    let angle = 80_f32.to_radians();
+    let limb = Vec3::new(angle.sin(), 0.0, angle.cos());
+    let behind = Vec3::new(angle.sin(), 0.0, -angle.cos());
+    assert!(!vis.project(limb).unwrap().2);
+
+    vis.set_projection(gl, Projection::Orthographic).unwrap();
+    assert!(vis.project(limb).unwrap().2);
+    assert!(!vis.project(behind).unwrap().2);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_flat_pan_hidpi() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let dpi = 2.0;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, dpi, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    vis.set_map_mode(gl, MapMode::Flat).unwrap();
+    let (lat, lon) = vis.mvp.view_center();
+    let center = Vec3::from(lat_lon_to_xyz(lat, lon, 1.0));
+    let (cx, cy) = (vis.width * dpi * 0.5, vis.height * dpi * 0.5);
+
+    // dragging 40 physical pixels carries map 20 logical pixels, keeping it under cursor
+    vis.handle_event(gl, &cursor_moved(cx, cy)).unwrap();
+    vis.handle_event(gl, &left_button(ElementState::Pressed))
+        .unwrap();
+    vis.handle_event(gl, &cursor_moved(cx + 40.0, cy)).unwrap();
+    vis.handle_event(gl, &left_button(ElementState::Released))
+        .unwrap();
+    let (x, y, _) = vis.project(center).unwrap();
+    assert!((x as f64 - (vis.width * 0.5 + 20.0)).abs() < 0.5, "{x}");
+    assert!((y as f64 - vis.height * 0.5).abs() < 0.5, "{y}");
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_scroll_zoom() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let distance = vis.mvp.distance;
+    let edge = project(&vis, 0.0, 30.0).x;
+
+    // scrolling up zooms in, spreading surface points away from center
+    assert!(vis.handle_event(gl, &scroll_lines(1.0)).unwrap());
+    assert!(vis.mvp.distance < distance);
+    assert!(project(&vis, 0.0, 30.0).x > edge);
+
+    // scrolling back by the same amount zooms out to about where it started
+    vis.handle_event(gl, &scroll_lines(-1.0)).unwrap();
+    assert!((vis.mvp.distance - distance).abs() < distance * 0.05);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_pending_hover() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let options = VisOptions {
+        hover: true,
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 2.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    std::thread::sleep(Duration::from_millis(60));
+
+    // first move is reported, move right after it is throttled and left pending
+    vis.handle_event(gl, &cursor_moved(20.0, 40.0)).unwrap();
+    assert!(vis.hover_ready());
+    vis.handle_event(gl, &cursor_moved(30.0, 50.0)).unwrap();
+    assert!(!vis.hover_ready());
+    assert!(vis.pending_hover().is_none());
+    assert!(vis.hover_wait().is_some());
+
+    // last position is flushed in logical pixels once interval passes, then only once
+    std::thread::sleep(vis.hover_wait().unwrap() + Duration::from_millis(5));
+    assert_eq!(vis.pending_hover(), Some((15.0, 25.0)));
+    assert!(vis.pending_hover().is_none());
+    assert!(vis.hover_wait().is_none());
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_shader_fallback() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let options = VisOptions {
+        globe_shaders: Some(ShaderSources::new("void main() {", "void main() {}")),
+        shader_fallback: true,
+        ..VisOptions::default()
+    };
+    // broken custom globe shader falls back, keeping compile error with driver log
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    match &vis.shader_errors[..] {
+        [VisGlError::Globe(GlobeError::Program(ProgramError::Shader(ShaderError::Compilation(
+            log,
+        ))))] => assert!(!log.is_empty()),
+        errors => panic!("unexpected shader errors {:?}", errors),
+    }
+    vis.setup_gl(gl).unwrap();
+    vis.drop(gl);
+}
+
+// custom shader declaring only the matrices, every other uniform is optional
+const MINIMAL_VERT: &str = "
+in vec4 position;
+uniform mat4 projMatrix;
+uniform mat4 viewMatrix;
+uniform mat4 modelMatrix;
+void main() {
+    gl_Position = projMatrix * viewMatrix * modelMatrix * position;
+    gl_PointSize = 4.0;
+}";
+const MINIMAL_FRAG: &str = "
+precision highp float;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(1.0);
+}";
+
+#[test]
+fn test_vis_gl_minimal_custom_shaders() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let options = VisOptions {
+        width: SIZE as f64,
+        height: SIZE as f64,
+        background: [0.0, 0.0, 0.0],
+        globe_shaders: Some(ShaderSources::new(MINIMAL_VERT, MINIMAL_FRAG)),
+        point_shaders: Some(ShaderSources::new(MINIMAL_VERT, MINIMAL_FRAG)),
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 2.0, &options).unwrap();
+    assert!(vis.shader_errors.is_empty());
+    vis.setup_gl(gl).unwrap();
+
+    // setters for features the shaders don't declare are skipped
+    let points = vis.points.as_mut().unwrap();
+    points.set_point_size(gl, 6.0, 2.0).unwrap();
+    points.set_softness(gl, 1.0, 2.0).unwrap();
+    points.set_outline(gl, Some([1.0; 4]), 1.0, 2.0).unwrap();
+    vis.globe.set_opacity(gl, 0.5).unwrap();
+    vis.globe.set_edge_smoothing(gl, true).unwrap();
+    vis.set_map_mode(gl, MapMode::Flat).unwrap();
+    vis.set_map_mode(gl, MapMode::Globe).unwrap();
+    vis.frame(gl, 16.0, Some(&[0.0, 0.0, 1.0]), None).unwrap();
+    unsafe { assert_eq!(gl.get_error(), glow::NO_ERROR) };
+
+    // white globe drawn at center with flat color from minimal fragment shader
+    let pixels = target.read_u8(gl).unwrap();
+    let i = 4 * (SIZE / 2 * SIZE + SIZE / 2) as usize;
+    assert_eq!(&pixels[i..i + 3], &[255, 255, 255]);
+    target.drop(gl);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_split_input_hidpi() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let options = VisOptions {
+        split_view: Some(false),
+        ..VisOptions::default()
+    };
+    let dpi = 2.0;
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, dpi, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let (width, height) = (vis.width * dpi, vis.height * dpi);
+    let distance = vis.mvp.distance;
+    let split_distance = |vis: &VisGl| vis.split.as_ref().unwrap().mvp.distance;
+
+    // events are in physical pixels, left of center only zooms left camera
+    vis.handle_event(gl, &cursor_moved(width * 0.4, height * 0.5))
+        .unwrap();
+    vis.handle_event(gl, &scroll_lines(1.0)).unwrap();
+    assert!(vis.mvp.distance < distance);
+    assert_eq!(split_distance(&vis), distance);
+
+    // right of center only zooms right camera
+    let left_distance = vis.mvp.distance;
+    vis.handle_event(gl, &cursor_moved(width * 0.6, height * 0.5))
+        .unwrap();
+    vis.handle_event(gl, &scroll_lines(1.0)).unwrap();
+    assert_eq!(vis.mvp.distance, left_distance);
+    assert!(split_distance(&vis) < distance);
+
+    // and drags starting there rotate right camera
+    let orientation = vis.mvp.orientation;
+    vis.handle_event(gl, &left_button(ElementState::Pressed))
+        .unwrap();
+    vis.handle_event(gl, &cursor_moved(width * 0.7, height * 0.5))
+        .unwrap();
+    vis.handle_event(gl, &left_button(ElementState::Released))
+        .unwrap();
+    assert_eq!(vis.mvp.orientation, orientation);
+    assert_ne!(vis.split.as_ref().unwrap().mvp.orientation, orientation);
+    vis.drop(gl);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_vis_gl_scene_round_trip() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    vis.fly_to(30.0, -60.0, 3.0, 0.0);
+    vis.step_animation(gl, 16.0).unwrap();
+    let positions = [0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+    let colors = [1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.5];
+    let points = vis.points.as_mut().unwrap();
+    points.set_positions(gl, &positions);
+    points.set_colors(gl, &colors);
+    points.set_point_size(gl, 12.0, 1.0).unwrap();
+    vis.globe.set_color(gl, [0.2, 0.4, 0.6]).unwrap();
+    let camera = vis.camera_state();
+    let json = vis.export_scene(gl).unwrap();
+
+    // change camera, points and globe, then import restores exported state
+    vis.fly_to(-10.0, 90.0, 5.0, 0.0);
+    vis.step_animation(gl, 16.0).unwrap();
+    assert_ne!(vis.camera_state(), camera);
+    let points = vis.points.as_mut().unwrap();
+    points.set_positions(gl, &[0.0, 1.0, 0.0]);
+    points.set_colors(gl, &[0.0, 0.0, 1.0, 1.0]);
+    points.set_point_size(gl, 4.0, 1.0).unwrap();
+    vis.globe.set_color(gl, [1.0, 1.0, 1.0]).unwrap();
+    vis.import_scene(gl, &json).unwrap();
+
+    assert_eq!(vis.camera_state(), camera);
+    let points = vis.points.as_ref().unwrap();
+    assert_eq!(points.read_back(gl), positions);
+    assert_eq!(points.color_buffer.read_back(gl), colors);
+    assert_eq!(points.size, 12.0);
+    assert_eq!(vis.globe.color.data.to_array(), [0.2, 0.4, 0.6]);
+    assert_eq!(vis.export_scene(gl).unwrap(), json);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_initial_points() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let positions = vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+    let colors = vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.5];
+    let options = VisOptions {
+        initial_points: Some((positions.clone(), colors.clone())),
+        ..VisOptions::default()
+    };
+    let vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    let points = vis.points.as_ref().unwrap();
+    assert_eq!(points.read_back(gl), positions);
+    assert_eq!(points.color_buffer.read_back(gl), colors);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_line_width() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let options = VisOptions {
+        width: SIZE as f64,
+        height: SIZE as f64,
+        background: [0.0, 0.0, 0.0],
+        globe_color: [0.0, 0.0, 0.0],
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    // horizontal line in front of globe center
+    vis.set_lines(
+        gl,
+        &[LineSegment {
+            start: [-0.5, 0.0, 1.2],
+            end: [0.5, 0.0, 1.2],
+            color: [0.0, 1.0, 0.0, 1.0],
+        }],
+    );
+    vis.set_line_width(gl, 10.0).unwrap();
+    vis.frame(gl, 16.0, None, None).unwrap();
+
+    // width comes out in pixels of viewport vis drew into
+    let pixels = target.read_u8(gl).unwrap();
+    let covered = (0..SIZE)
+        .filter(|y| pixels[4 * (y * SIZE + SIZE / 2) as usize + 1] > 0)
+        .count();
+    assert!((10..=12).contains(&covered), "{covered}");
+    vis.drop(gl);
+    target.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_program_switches() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut vis =
+        VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &VisOptions::default()).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let markers = [Marker {
+        pos: [0.0, 0.3, 1.0],
+        color: [1.0, 0.0, 0.0, 1.0],
+        size: 0.05,
+    }];
+    vis.set_lines(
+        gl,
+        &[LineSegment {
+            start: [-0.3, -0.3, 1.2],
+            end: [0.3, -0.3, 1.2],
+            color: [0.0, 1.0, 0.0, 1.0],
+        }],
+    );
+    vis.set_paths(
+        gl,
+        &[LinePath {
+            positions: vec![[0.0, 0.0, 1.2], [0.3, 0.3, 1.2]],
+            color: [0.0, 0.0, 1.0, 1.0],
+        }],
+    );
+    let points = [0.0, 0.0, 1.01];
+    vis.frame(gl, 16.0, Some(&points), Some(&markers)).unwrap();
+
+    // one use_program per drawn layer: globe, points, markers, lines and paths
+    let before = program_binds();
+    vis.frame(gl, 16.0, Some(&points), Some(&markers)).unwrap();
+    assert_eq!(program_binds() - before, 5);
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_restore() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let options = VisOptions {
+        width: SIZE as f64,
+        height: SIZE as f64,
+        background: [0.0, 0.0, 0.0],
+        globe_color: [0.0, 0.0, 0.0],
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let markers = [Marker {
+        pos: [0.0, 0.3, 1.0],
+        color: [1.0, 0.0, 0.0, 1.0],
+        size: 0.05,
+    }];
+    vis.set_lines(
+        gl,
+        &[LineSegment {
+            start: [-0.3, -0.3, 1.2],
+            end: [0.3, -0.3, 1.2],
+            color: [0.0, 1.0, 0.0, 1.0],
+        }],
+    );
+    vis.set_line_width(gl, 4.0).unwrap();
+    let points = vis.points.as_mut().unwrap();
+    points.set_softness(gl, 2.0, 1.0).unwrap();
+    points.set_times(gl, &[3.0]);
+    points.set_time_window(gl, 1.0, 5.0).unwrap();
+    vis.frame(gl, 16.0, None, Some(&markers)).unwrap();
+    let before = target.read_u8(gl).unwrap();
+    let count = |pixels: &[u8], channel: usize| {
+        pixels
+            .chunks_exact(4)
+            .filter(|p| p[channel] > 200 && p.iter().take(3).filter(|&&c| c > 50).count() == 1)
+            .count()
+    };
+    assert!(count(&before, 0) > 0 && count(&before, 1) > 0);
+
+    // markers, lines and line width set before restore are drawn the same after it
+    vis.restore_with_gl(gl, common::SHADER_VERSION, &options)
+        .unwrap();
+    vis.frame(gl, 16.0, None, None).unwrap();
+    assert_eq!(target.read_u8(gl).unwrap(), before);
+    // point styling and times are kept, positions are passed again by caller
+    let points = vis.points.as_ref().unwrap();
+    assert_eq!(points.softness, 2.0);
+    assert_eq!(points.times, [3.0]);
+    assert_eq!((points.time_start.data, points.time_end.data), (1.0, 5.0));
+    target.drop(gl);
+    vis.drop(gl);
+}
+
+// layers drawn so far when a pass runs, read from globe center and point pixels
+type PassLog = Rc<RefCell<Vec<(PassStage, bool, bool)>>>;
+
+struct RecordPass {
+    stage: PassStage,
+    log: PassLog,
+    globe_pixel: (i32, i32),
+    point_pixel: (i32, i32),
+}
+
+impl Drop for RecordPass {
+    fn drop(&self, _gl: &glow::Context) {}
+}
+
+impl RenderPass for RecordPass {
+    fn setup(&mut self, _gl: &glow::Context) {}
+
+    fn draw(&mut self, gl: &glow::Context, _camera: &CameraView) {
+        let lit = |(x, y): (i32, i32)| {
+            let mut pixel = [0u8; 4];
+            unsafe {
+                gl.read_pixels(
+                    x,
+                    y,
+                    1,
+                    1,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    glow::PixelPackData::Slice(&mut pixel),
+                );
+            }
+            pixel[..3].iter().any(|&c| c > 0)
+        };
+        let entry = (self.stage, lit(self.globe_pixel), lit(self.point_pixel));
+        self.log.borrow_mut().push(entry);
+    }
+}
+
+#[test]
+fn test_vis_gl_pass_stages() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    // point above globe silhouette, small enough not to cover globe center
+    let point = Vec3::new(0.0, 1.3, 0.0);
+    // depth prepass draws opaque globe first and translucent globe last whatever the order
+    for (order, opacity, depth_prepass, between) in [
+        (DrawOrder::GlobeFirst, 1.0, false, (true, false)),
+        (DrawOrder::PointsFirst, 1.0, false, (false, true)),
+        (DrawOrder::PointsFirst, 1.0, true, (true, false)),
+        (DrawOrder::GlobeFirst, 0.5, true, (false, true)),
+    ] {
+        let options = VisOptions {
+            width: SIZE as f64,
+            height: SIZE as f64,
+            background: [0.0, 0.0, 0.0],
+            point_size: 4.0,
+            draw_order: order,
+            globe_opacity: opacity,
+            depth_prepass,
+            ..VisOptions::default()
+        };
+        let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+        let (x, y, _) = vis.project(point).unwrap();
+        let log = PassLog::default();
+        for stage in [
+            PassStage::AfterLayers,
+            PassStage::BetweenLayers,
+            PassStage::BeforeLayers,
+        ] {
+            vis.add_pass_at(
+                stage,
+                Box::new(RecordPass {
+                    stage,
+                    log: log.clone(),
+                    globe_pixel: (SIZE / 2, SIZE / 2),
+                    point_pixel: (x as i32, SIZE - 1 - y as i32),
+                }),
+            );
+        }
+        vis.setup_gl(gl).unwrap();
+        vis.frame(gl, 16.0, Some(&point.to_array()), None).unwrap();
+
+        // stages run in frame order regardless of order added, between follows draw order
+        assert_eq!(
+            *log.borrow(),
+            [
+                (PassStage::BeforeLayers, false, false),
+                (PassStage::BetweenLayers, between.0, between.1),
+                (PassStage::AfterLayers, true, true),
+            ],
+            "{:?} {} {}",
+            order,
+            opacity,
+            depth_prepass
+        );
+        vis.drop(gl);
+    }
+    target.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_depth_prepass_translucent() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let options = VisOptions {
+        width: SIZE as f64,
+        height: SIZE as f64,
+        background: [0.0, 0.0, 0.0],
+        globe_opacity: 0.5,
+        depth_prepass: true,
+        point_size: 16.0,
+        point_softness: 2.0,
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    // soft red point on near surface of translucent globe
+    let point = Vec3::new(0.0, 0.0, 1.0);
+    let points = vis.points.as_mut().unwrap();
+    points.set_colors(gl, &[1.0, 0.0, 0.0, 1.0]);
+    vis.frame(gl, 16.0, Some(&point.to_array()), None).unwrap();
+    assert!(!vis.points.as_ref().unwrap().depth_prepass);
+
+    // globe is blended behind point, not over it
+    let (x, y, _) = vis.project(point).unwrap();
+    let pixels = target.read_u8(gl).unwrap();
+    let i = 4 * ((SIZE - 1 - y as i32) * SIZE + x as i32) as usize;
+    assert_eq!(pixels[i..i + 3], [255, 0, 0]);
+    vis.drop(gl);
+    target.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_initial_view_skips_fit() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let options = VisOptions {
+        initial_view: Some((30.0, 60.0, 2.5)),
+        fit_to_points: true,
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    assert!(!vis.fit_pending);
+    let (orientation, distance) = (vis.mvp.orientation, vis.mvp.distance);
+
+    // clustered points away from initial view don't move camera
+    let points = [
+        lat_lon_to_xyz(-40.0, -100.0, 1.0),
+        lat_lon_to_xyz(-41.0, -101.0, 1.0),
+    ];
+    vis.frame(gl, 16.0, Some(points.as_flattened()), None)
+        .unwrap();
+    assert_eq!(
+        (vis.mvp.orientation, vis.mvp.distance),
+        (orientation, distance)
+    );
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_click_select_hidpi() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let dpi = 2.0;
+    let options = VisOptions::default();
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, dpi, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    let points = vis.points.as_ref().unwrap();
+    let width = points.selection_width_uniform.data;
+    assert_eq!(width, logical_to_physical(points.selection_width, dpi));
+    vis.frame(gl, 16.0, Some(&[0.0, 0.0, 1.01]), None).unwrap();
+    let (cx, cy) = (vis.width * dpi * 0.5, vis.height * dpi * 0.5);
+    let click = |vis: &mut VisGl| {
+        vis.handle_event(gl, &cursor_moved(cx, cy)).unwrap();
+        vis.handle_event(gl, &left_button(ElementState::Pressed))
+            .unwrap();
+        vis.handle_event(gl, &left_button(ElementState::Released))
+            .unwrap();
+    };
+
+    // clicks only select once enabled
+    click(&mut vis);
+    assert!(!vis.take_selection_changed());
+    vis.click_select = true;
+    click(&mut vis);
+    assert!(vis.take_selection_changed());
+    assert!(vis.points.as_ref().unwrap().is_selected(0));
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_touch_cancel() {
+    use globe_vis::touch::TouchPhase as Phase;
+
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let options = VisOptions::default();
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    vis.click_select = true;
+    vis.frame(gl, 16.0, Some(&[0.0, 0.0, 1.01]), None).unwrap();
+    let (cx, cy) = (vis.width * 0.5, vis.height * 0.5);
+
+    // cancelled touch ends drag without tapping
+    vis.touch(gl, 1, Phase::Started, cx, cy).unwrap();
+    assert!(vis.mouse.dragging);
+    vis.touch(gl, 1, Phase::Cancelled, cx, cy).unwrap();
+    assert!(!vis.mouse.dragging);
+    assert_eq!(vis.mouse.touches.count(), 0);
+    assert!(!vis.take_selection_changed());
+
+    // lifted touch still taps
+    vis.touch(gl, 2, Phase::Started, cx, cy).unwrap();
+    vis.touch(gl, 2, Phase::Ended, cx, cy).unwrap();
+    assert!(vis.take_selection_changed());
+    assert!(vis.points.as_ref().unwrap().is_selected(0));
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_pick_point_sizes() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let dpi = 2.0;
+    let options = VisOptions::default();
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, dpi, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    vis.frame(gl, 16.0, Some(&[0.0, 0.0, 1.01]), None).unwrap();
+    // outside default size radius plus pick radius
+    let (x, y) = (vis.width * 0.5 + 18.0, vis.height * 0.5);
+    assert_eq!(vis.pick_point(gl, x, y), None);
+
+    // larger per point size reaches farther
+    vis.points.as_mut().unwrap().set_sizes(gl, &[40.0], dpi);
+    assert_eq!(vis.pick_point(gl, x, y), Some(0));
+
+    // as does selection scale
+    let points = vis.points.as_mut().unwrap();
+    points.set_sizes(gl, &[0.0], dpi);
+    points.select(0);
+    assert_eq!(vis.pick_point(gl, x, y), Some(0));
+    vis.drop(gl);
+}
+
+#[test]
+fn test_vis_gl_is_animating() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let options = VisOptions {
+        point_interpolation: true,
+        ..VisOptions::default()
+    };
+    let mut vis = VisGl::new_with_gl(gl, common::SHADER_VERSION, 1.0, &options).unwrap();
+    vis.setup_gl(gl).unwrap();
+    // enabled interpolation alone doesn't keep drawing
+    vis.frame(gl, 16.0, Some(&[0.0, 0.0, 1.0]), None).unwrap();
+    assert!(!vis.is_animating());
+
+    // second keyframe animates for one keyframe interval
+    vis.frame(gl, 100.0, Some(&[0.0, 1.0, 0.0]), None).unwrap();
+    assert!(vis.is_animating());
+    vis.frame(gl, 100.0, None, None).unwrap();
+    assert!(!vis.is_animating());
+
+    // camera animation runs until its duration passes
+    vis.fly_to(10.0, 20.0, 3.0, 50.0);
+    assert!(vis.is_animating());
+    vis.frame(gl, 60.0, None, None).unwrap();
+    assert!(!vis.is_animating());
+    vis.drop(gl);
+}
@@ -0,0 +1,42 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use globe_vis::gl_wrap::{ColorFormat, Drop, Framebuffer, FramebufferError};
+use glow::HasContext;
+
+// clear to known color and read back in format's pixel type
+#[test]
+fn test_framebuffer_formats() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    for format in [ColorFormat::Rgba8, ColorFormat::Rgb8, ColorFormat::Rgba16F] {
+        let framebuffer = Framebuffer::new(gl, 2, 2, format).unwrap();
+        unsafe {
+            gl.clear_color(1.0, 0.5, 0.0, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+        let channels = format.channels();
+        if format.is_float() {
+            let pixels = framebuffer.read_f32(gl).unwrap();
+            assert_eq!(pixels.len(), 2 * 2 * channels);
+            assert_eq!(&pixels[..4], &[1.0, 0.5, 0.0, 1.0]);
+            assert!(matches!(
+                framebuffer.read_u8(gl),
+                Err(FramebufferError::ReadType(_))
+            ));
+        } else {
+            let pixels = framebuffer.read_u8(gl).unwrap();
+            assert_eq!(pixels.len(), 2 * 2 * channels);
+            assert_eq!(pixels[0], 255);
+            assert!((pixels[1] as i32 - 128).abs() <= 1);
+            assert_eq!(pixels[2], 0);
+            assert!(matches!(
+                framebuffer.read_f32(gl),
+                Err(FramebufferError::ReadType(_))
+            ));
+        }
+        assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+        framebuffer.drop(gl);
+    }
+}
@@ -0,0 +1,72 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use globe_vis::gl_wrap::{Bind, ColorFormat, Drop, Framebuffer, FullscreenQuad, Program};
+use glow::HasContext;
+
+const VERT: &str = "layout(location = 0) in vec2 position;
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+}";
+
+const FRAG: &str = "out vec4 fragColor;
+void main() {
+    fragColor = vec4(1.0);
+}";
+
+#[test]
+fn test_fullscreen_quad_draw() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let program = Program::new_from_sources(gl, common::SHADER_VERSION, VERT, FRAG).unwrap();
+    let quad = FullscreenQuad::new(gl).unwrap();
+    assert_eq!(quad.buffer.len, 6);
+
+    // surfaceless context has no default framebuffer, draw into small offscreen target
+    let target = Framebuffer::new(gl, 4, 4, ColorFormat::Rgba8).unwrap();
+    unsafe {
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+    }
+    program.bind(gl);
+    quad.draw(gl);
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    let pixels = target.read_u8(gl).unwrap();
+    target.drop(gl);
+    // every pixel covered
+    assert!(pixels.iter().all(|&p| p == 255));
+    quad.drop(gl);
+    program.drop(gl);
+}
+
+#[test]
+fn test_fullscreen_quad_passthrough_uv() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let program = FullscreenQuad::program(
+        gl,
+        common::SHADER_VERSION,
+        "precision highp float;
+        in vec2 screenUv;
+        out vec4 fragColor;
+        void main() {
+            fragColor = vec4(screenUv, 0.0, 1.0);
+        }",
+    )
+    .unwrap();
+    let quad = FullscreenQuad::new(gl).unwrap();
+    let target = Framebuffer::new(gl, 4, 4, ColorFormat::Rgba8).unwrap();
+    program.bind(gl);
+    quad.draw(gl);
+    let pixels = target.read_u8(gl).unwrap();
+    target.drop(gl);
+    // uv increases left to right and bottom to top, pixel centers at 1/8 and 7/8
+    let pixel = |x: usize, y: usize| &pixels[(y * 4 + x) * 4..(y * 4 + x) * 4 + 2];
+    let (low, high) = (32, 223);
+    assert!(pixel(0, 0).iter().all(|&c| c.abs_diff(low) <= 1));
+    assert!(pixel(3, 3).iter().all(|&c| c.abs_diff(high) <= 1));
+    assert!(pixel(3, 0)[0] > pixel(0, 0)[0] && pixel(3, 0)[1] == pixel(0, 0)[1]);
+    quad.drop(gl);
+    program.drop(gl);
+}
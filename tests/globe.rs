@@ -0,0 +1,110 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use glam::Vec3;
+use globe_vis::{
+    gl_wrap::{Drop, Texture2D},
+    globe::Globe,
+};
+
+#[test]
+fn test_globe_vertex_colors() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+
+    // color northern hemisphere red and southern blue
+    let colors = globe.colors_from_lat_lon(|lat, _| {
+        if lat >= 0.0 {
+            [1.0, 0.0, 0.0, 1.0]
+        } else {
+            [0.0, 0.0, 1.0, 1.0]
+        }
+    });
+    assert_eq!(colors.len(), globe.vertex_count() * 4);
+    globe.set_vertex_colors(gl, &colors).unwrap();
+    assert_eq!(globe.color_buffer.read_back(gl), colors);
+
+    // mismatched length rejected
+    assert!(globe.set_vertex_colors(gl, &colors[4..]).is_err());
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_set_subdivisions() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    let (buffer_id, color_id) = (globe.buffer.id, globe.color_buffer.id);
+
+    // switch back and forth, buffers should be reused rather than recreated
+    for _ in 0..5 {
+        for subdivisions in [2, 3] {
+            globe.set_subdivisions(gl, subdivisions).unwrap();
+            let expected_len = 3 * 3 * 20 * 4usize.pow(subdivisions as u32);
+            assert_eq!(globe.data.len(), expected_len);
+            assert_eq!(globe.buffer.read_back(gl), globe.data);
+            assert_eq!(globe.color_buffer.len, globe.vertex_count() * 4);
+            assert_eq!(globe.edge_buffer.len, globe.vertex_count() * 2);
+        }
+    }
+    assert_eq!(globe.buffer.id, buffer_id);
+    assert_eq!(globe.color_buffer.id, color_id);
+
+    assert!(globe.set_subdivisions(gl, 100).is_err());
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_lighting_uniforms() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+
+    // all lighting uniforms declared and used in built in shader
+    globe.set_ambient(gl, Vec3::splat(0.2)).unwrap();
+    globe
+        .set_light(gl, Vec3::new(2.0, 0.0, 0.0), Vec3::ONE)
+        .unwrap();
+    globe
+        .set_fill_light(gl, Vec3::NEG_X, Vec3::splat(0.3))
+        .unwrap();
+
+    globe.set_color(gl, [0.2, 0.4, 0.8]).unwrap();
+    globe.set_double_sided(gl, true).unwrap();
+    assert_eq!(globe.color.data, Vec3::new(0.2, 0.4, 0.8));
+    globe.apply_uniforms(gl).unwrap();
+
+    // directions stored normalized
+    assert_eq!(globe.lighting.light_dir.data, Vec3::X);
+    assert_eq!(globe.lighting.fill_dir.data, Vec3::NEG_X);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_specular_mask() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+
+    // no highlight until mask is set
+    globe.set_specular(gl, 0.8, 16.0).unwrap();
+    assert_eq!(globe.lighting.specular_intensity.data, 0.0);
+    assert_eq!(globe.lighting.shininess.data, 16.0);
+
+    // ocean on bottom row, land on top
+    let mask: Vec<u8> = [[0, 0, 0, 255], [255, 255, 255, 255]]
+        .iter()
+        .flat_map(|row| row.repeat(4))
+        .collect();
+    assert!(Texture2D::new(gl, 4, 2, &mask[4..], false).is_err());
+    let texture = Texture2D::new(gl, 4, 2, &mask, false).unwrap();
+    globe.set_specular_mask(gl, texture).unwrap();
+    assert_eq!(globe.lighting.specular_intensity.data, 0.8);
+
+    globe.clear_specular_mask(gl).unwrap();
+    assert!(globe.specular_mask.is_none());
+    assert_eq!(globe.lighting.specular_intensity.data, 0.0);
+    globe.drop(gl);
+}
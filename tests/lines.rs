@@ -0,0 +1,111 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use glam::Mat4;
+use globe_vis::{
+    gl_wrap::{ColorFormat, Drop, Framebuffer, UniformFloat, UniformMatrix, RESTART_INDEX},
+    lines::{great_circle_arc, LinePath, Lines, Paths},
+    vis_gl::MvpMatrices,
+};
+use glow::HasContext;
+
+const SIZE: i32 = 64;
+
+// draw lines at default camera and get red channel of center column, bottom to top
+fn render_column(gl: &glow::Context, lines: &Lines) -> Vec<u8> {
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let mvp = MvpMatrices::new_default(SIZE as f64, SIZE as f64).unwrap();
+    mvp.apply(gl, &[&lines.program]).unwrap();
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &[&lines.program])
+        .unwrap();
+    unsafe {
+        gl.viewport(0, 0, SIZE, SIZE);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+    }
+    lines.draw(gl, [SIZE as f32; 2]);
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    let pixels = target.read_u8(gl).unwrap();
+    target.drop(gl);
+    (0..SIZE)
+        .map(|y| pixels[4 * (y * SIZE + SIZE / 2) as usize])
+        .collect()
+}
+
+#[test]
+fn test_lines_width() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut lines = Lines::new(gl, common::SHADER_VERSION).unwrap();
+    // horizontal line across center of view along equator
+    let segments = great_circle_arc((0.0, -20.0), (0.0, 20.0), 4, [1.0, 0.0, 0.0, 1.0]);
+    lines.set_segments(gl, &segments);
+    assert_eq!(lines.len(), 4);
+
+    let covered = |column: &[u8]| column.iter().filter(|v| **v > 0).count();
+    lines.set_width(gl, 2.0, 1.0).unwrap();
+    let thin = render_column(gl, &lines);
+    lines.set_width(gl, 8.0, 1.0).unwrap();
+    let thick = render_column(gl, &lines);
+    // thick line covers about its width plus anti aliased edges, thin about its own
+    assert!((8..=10).contains(&covered(&thick)), "{:?}", thick);
+    assert!((2..=4).contains(&covered(&thin)), "{:?}", thin);
+    assert_eq!(thick[SIZE as usize / 2], 255);
+    // edges are partly covered rather than cut off
+    assert!(thick.iter().any(|v| *v > 0 && *v < 255));
+    unsafe {
+        assert!(!gl.is_enabled(glow::BLEND));
+    }
+    lines.drop(gl);
+}
+
+#[test]
+fn test_paths_restart() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let mut paths = Paths::new(gl, common::SHADER_VERSION).unwrap();
+    for name in ["projMatrix", "viewMatrix", "modelMatrix"] {
+        UniformMatrix::new(name, Mat4::IDENTITY)
+            .apply(gl, &[&paths.program])
+            .unwrap();
+    }
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &[&paths.program])
+        .unwrap();
+    paths.set_surface_offset(gl, 0.0).unwrap();
+    // restart is enabled on the sentinel index
+    unsafe {
+        if gl.is_enabled(glow::PRIMITIVE_RESTART) {
+            let index = gl.get_parameter_i32(glow::PRIMITIVE_RESTART_INDEX);
+            assert_eq!(index as u32, RESTART_INDEX);
+        } else {
+            assert!(gl.is_enabled(glow::PRIMITIVE_RESTART_FIXED_INDEX));
+        }
+    }
+    // two vertical paths, a single strip would join them with a diagonal through center
+    let red = [1.0, 0.0, 0.0, 1.0];
+    let path = |x: f32| LinePath {
+        positions: vec![[x, -0.5, 0.0], [x, 0.0, 0.0], [x, 0.5, 0.0]],
+        color: red,
+    };
+    paths.set_paths(gl, &[path(-0.49), path(0.51)]);
+    unsafe {
+        gl.viewport(0, 0, SIZE, SIZE);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+    }
+    paths.draw(gl);
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    let pixels = target.read_u8(gl).unwrap();
+    let red_at = |x: i32, y: i32| pixels[4 * (y * SIZE + x) as usize];
+    assert_eq!(red_at(16, SIZE / 2), 255);
+    assert_eq!(red_at(48, SIZE / 2), 255);
+    for x in SIZE / 2 - 2..=SIZE / 2 + 2 {
+        assert_eq!(red_at(x, SIZE / 2), 0, "{x}");
+    }
+    target.drop(gl);
+    paths.drop(gl);
+}
@@ -0,0 +1,170 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use globe_vis::{
+    cluster::ClusterLayer,
+    geo::lat_lon_to_xyz,
+    gl_wrap::{ColorFormat, Drop, Framebuffer, ProgramCache, UniformFloat},
+    markers::Markers,
+    vis_gl::MvpMatrices,
+    Marker,
+};
+use glow::HasContext;
+
+#[test]
+fn test_markers_upload() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut markers = Markers::new(gl, common::SHADER_VERSION).unwrap();
+
+    // empty set draws nothing
+    markers.draw(gl, Some(&[]));
+    assert!(markers.is_empty());
+
+    let data: Vec<Marker> = (0..1000)
+        .map(|i| {
+            Marker::from_lat_lon_value(i as f32 * 0.1, i as f32 * 0.3, i as f32, (0.0, 1000.0))
+        })
+        .collect();
+    markers.draw(gl, Some(&data));
+    assert_eq!(markers.len(), data.len());
+
+    let uploaded = markers.instance_buffer.read_back(gl);
+    assert_eq!(&uploaded[..3], &data[0].pos);
+    assert_eq!(uploaded[8 * 999 + 7], data[999].size);
+
+    // None keeps previous markers
+    markers.draw(gl, None);
+    assert_eq!(markers.len(), data.len());
+    markers.drop(gl);
+}
+
+#[test]
+fn test_cluster_layer_zoom() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut layer = ClusterLayer::new(gl, common::SHADER_VERSION).unwrap();
+    let points: Vec<f32> = (0..100)
+        .flat_map(|i| lat_lon_to_xyz(i as f32 * 0.01, i as f32 * 0.01, 1.0))
+        .collect();
+
+    // zoomed out groups nearby points into one cluster marker
+    assert!(layer.update(gl, Some(&points), 6.0));
+    assert_eq!(layer.markers.len(), 1);
+    assert_eq!(layer.cluster_at(0.5, 0.5).unwrap().members.len(), 100);
+
+    // zooming in restores data received while clustered, then passes data through
+    assert!(!layer.update(gl, None, 1.2));
+    assert!(layer.cluster_at(0.5, 0.5).is_none());
+    assert_eq!(layer.unclustered_data(None), Some(points.as_slice()));
+    assert_eq!(layer.unclustered_data(None), None);
+    layer.drop(gl);
+}
+
+// width and height in pixels of drawn marker
+fn marker_extent(
+    gl: &glow::Context,
+    markers: &mut Markers,
+    framebuffer: &Framebuffer,
+) -> (i32, i32) {
+    unsafe {
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+    }
+    markers.draw(gl, None);
+    let pixels = framebuffer.read_u8(gl).unwrap();
+    let (mut min, mut max) = ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN));
+    for (i, p) in pixels.chunks_exact(4).enumerate() {
+        if p[0] > 0 {
+            let (x, y) = (i as i32 % framebuffer.width, i as i32 / framebuffer.width);
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+    }
+    (max.0 - min.0 + 1, max.1 - min.1 + 1)
+}
+
+#[test]
+fn test_markers_billboard() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut markers = Markers::new(gl, common::SHADER_VERSION).unwrap();
+    let framebuffer = Framebuffer::new(gl, 64, 64, ColorFormat::Rgba8).unwrap();
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &[&markers.program])
+        .unwrap();
+    let mut mvp = MvpMatrices::new_default(64.0, 64.0).unwrap();
+
+    // marker away from view center at a few globe rotations
+    for (center, marker) in [((0.0, 0.0), (40.0, 0.0)), ((20.0, 30.0), (-10.0, 60.0))] {
+        mvp.set_orientation(mvp.center_orientation(center.0, center.1));
+        mvp.apply(gl, &[&markers.program]).unwrap();
+        let data = [Marker::from_lat_lon(marker.0, marker.1, [1.0; 4], 0.2)];
+        markers.set_markers(gl, &data);
+
+        // camera facing quad projects to screen aligned square
+        markers.set_billboard(gl, true).unwrap();
+        let (w, h) = marker_extent(gl, &mut markers, &framebuffer);
+        assert!(w > 4 && (w - h).abs() <= 1, "{w}x{h}");
+
+        // flat quad foreshortens toward horizon
+        markers.set_billboard(gl, false).unwrap();
+        let (fw, fh) = marker_extent(gl, &mut markers, &framebuffer);
+        assert!(fw * fh < w * h, "{fw}x{fh}");
+    }
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    framebuffer.drop(gl);
+    markers.drop(gl);
+}
+
+// current value of float uniform on marker program
+fn surface_offset(gl: &glow::Context, markers: &Markers) -> f32 {
+    let mut value = [0.0];
+    unsafe {
+        let location = gl
+            .get_uniform_location(markers.program.id, "surfaceOffset")
+            .unwrap();
+        gl.get_uniform_f32(markers.program.id, &location, &mut value);
+    }
+    value[0]
+}
+
+#[test]
+fn test_markers_shared_program() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let framebuffer = Framebuffer::new(gl, 4, 4, ColorFormat::Rgba8).unwrap();
+    let mut cache = ProgramCache::new();
+    // many marker layers link a single program, so drawing them never switches programs
+    let mut layers: Vec<Markers> = (0..8)
+        .map(|_| Markers::new_with_cache(gl, &mut cache, common::SHADER_VERSION).unwrap())
+        .collect();
+    let cluster = ClusterLayer::new_with_cache(gl, &mut cache, common::SHADER_VERSION).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert!(layers
+        .iter()
+        .all(|layer| layer.program.id == cluster.markers.program.id));
+    let separate = Markers::new(gl, common::SHADER_VERSION).unwrap();
+    assert_ne!(separate.program.id, cluster.markers.program.id);
+
+    // each layer draws with its own uniforms despite sharing program state
+    let marker = [Marker::from_lat_lon(0.0, 0.0, [1.0; 4], 0.1)];
+    layers[0].set_surface_offset(gl, 0.5).unwrap();
+    layers[1].set_surface_offset(gl, 0.0).unwrap();
+    layers[0].draw(gl, Some(&marker));
+    assert_eq!(surface_offset(gl, &layers[0]), 0.5);
+    layers[1].draw(gl, Some(&marker));
+    assert_eq!(surface_offset(gl, &layers[1]), 0.0);
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+
+    // dropping layers leaves shared program for cache to delete
+    for layer in layers.drain(..) {
+        layer.drop(gl);
+    }
+    cluster.drop(gl);
+    assert!(unsafe { gl.is_program(cluster.markers.program.id) });
+    cache.drop(gl);
+    separate.drop(gl);
+    framebuffer.drop(gl);
+}
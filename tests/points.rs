@@ -0,0 +1,412 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use glam::Vec3;
+use globe_vis::{
+    gl_wrap::{Buffer, BufferUsage, Drop, VertexLayout},
+    points::{Point, Points},
+};
+use glow::HasContext;
+
+#[test]
+fn test_points_read_back() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+
+    // include values that don't round trip through lossy conversions
+    let data: Vec<f32> = vec![0.1, -0.2, 0.3, f32::MIN_POSITIVE, 1e-30, -1.5e20];
+    points.draw(gl, Some(data.clone()));
+    let read = points.read_back(gl);
+
+    assert_eq!(read.len(), data.len());
+    for (a, b) in read.iter().zip(data.iter()) {
+        assert_eq!(a.to_bits(), b.to_bits());
+    }
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_draw_slice() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+
+    // reuse one buffer across frames as the event loop does
+    let mut data: Vec<f32> = vec![];
+    for frame in 0..3 {
+        data.clear();
+        data.extend((0..300).map(|i| (i + frame) as f32));
+        points.draw_slice(gl, Some(&data));
+        assert_eq!(points.read_back(gl), data);
+    }
+
+    // None keeps previous upload
+    points.draw_slice(gl, None);
+    assert_eq!(points.read_back(gl), data);
+
+    // usage hint applied on next upload
+    let usage = |gl: &glow::Context| unsafe {
+        gl.get_buffer_parameter_i32(glow::ARRAY_BUFFER, glow::BUFFER_USAGE) as u32
+    };
+    assert_eq!(usage(gl), glow::DYNAMIC_DRAW);
+    points.set_usage(BufferUsage::Stream);
+    points.draw_slice(gl, Some(&data));
+    assert_eq!(usage(gl), glow::STREAM_DRAW);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_time_window() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    points.set_point_size(gl, 8.0, 1.0).unwrap();
+    let center = common::POINT_TARGET / 2;
+
+    let times = vec![2.0];
+    points.set_times(gl, &times);
+    assert_eq!(points.time_buffer.read_back(gl), times);
+
+    // point outside window leaves background
+    points.set_time_window(gl, 0.5, 1.5).unwrap();
+    assert_eq!((points.time_start.data, points.time_end.data), (0.5, 1.5));
+    let pixels = common::render_points(gl, &mut points, Some(&[0.0; 3]));
+    assert_eq!(common::point_pixel(&pixels, center, center), [0, 0, 0]);
+
+    // window is inclusive at both ends
+    points.set_time_window(gl, 1.0, 2.0).unwrap();
+    let pixels = common::render_points(gl, &mut points, None);
+    assert_eq!(
+        common::point_pixel(&pixels, center, center),
+        [255, 255, 255]
+    );
+
+    // points without a time for each point use time 0, outside window
+    let pixels = common::render_points(gl, &mut points, Some(&[0.0; 6]));
+    assert_eq!(common::point_pixel(&pixels, center, center), [0, 0, 0]);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_set_colors() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+
+    let positions: Vec<f32> = (0..9).map(|i| i as f32).collect();
+    points.draw_slice(gl, Some(&positions));
+
+    // first upload allocates, second updates in place
+    let red = [1.0, 0.0, 0.0, 1.0].repeat(3);
+    points.set_colors(gl, &red);
+    let color_id = points.color_buffer.id;
+    let blue = [0.0, 0.0, 1.0, 1.0].repeat(3);
+    points.set_colors(gl, &blue);
+
+    assert_eq!(points.color_buffer.id, color_id);
+    assert_eq!(points.color_buffer.read_back(gl), blue);
+    // positions untouched by color updates
+    assert_eq!(points.read_back(gl), positions);
+    points.draw_slice(gl, None);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_upload_raw() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+
+    // interleaved position + color
+    let layout = VertexLayout::new(&[("position", 3), ("color", 4)]);
+    let data: Vec<f32> = (0..14).map(|i| i as f32).collect();
+    points.upload_raw(gl, &data, &layout).unwrap();
+    assert_eq!(points.read_back(gl), data);
+    points.draw_slice(gl, None);
+
+    // partial vertex and undeclared attributes rejected
+    assert!(points.upload_raw(gl, &data[1..], &layout).is_err());
+    let unknown = VertexLayout::new(&[("position", 3), ("notDeclared", 1)]);
+    assert!(points.upload_raw(gl, &[0.0; 8], &unknown).is_err());
+
+    // xyz upload restores default layout
+    points.draw_slice(gl, Some(&[0.0; 9]));
+    assert!(points.raw_layout.is_none());
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_append() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    let point = |i: usize| [i as f32, 0.0, 1.0];
+
+    // unbounded append keeps all points in arrival order
+    points.draw(gl, Some(point(0).to_vec()));
+    for i in 1..5 {
+        points.append(gl, &point(i)).unwrap();
+    }
+    let expected: Vec<f32> = (0..5).flat_map(point).collect();
+    assert_eq!(points.read_back(gl), expected);
+    assert!(points.buffer.capacity >= expected.len());
+    assert!(points.append(gl, &[1.0, 2.0]).is_err());
+
+    // capped at 3, oldest points roll off and are then overwritten in place
+    points.set_max_points(gl, Some(3));
+    let expected: Vec<f32> = (2..5).flat_map(point).collect();
+    assert_eq!(points.read_back(gl), expected);
+    points.append(gl, &point(5)).unwrap();
+    let expected: Vec<f32> = [5, 3, 4].into_iter().flat_map(point).collect();
+    assert_eq!(points.read_back(gl), expected);
+
+    // batch wrapping past end of buffer
+    let batch: Vec<f32> = (6..8).flat_map(point).collect();
+    points.append(gl, &batch).unwrap();
+    let expected: Vec<f32> = [5, 6, 7].into_iter().flat_map(point).collect();
+    assert_eq!(points.read_back(gl), expected);
+
+    // only last max points of a large batch survive
+    let batch: Vec<f32> = (8..15).flat_map(point).collect();
+    points.append(gl, &batch).unwrap();
+    let mut read: Vec<f32> = points.read_back(gl).chunks(3).map(|p| p[0]).collect();
+    read.sort_by(f32::total_cmp);
+    assert_eq!(read, vec![12.0, 13.0, 14.0]);
+
+    // lowering cap after wrapping keeps newest points in arrival order
+    points.append(gl, &point(15)).unwrap();
+    points.set_max_points(gl, Some(2));
+    let expected: Vec<f32> = [14, 15].into_iter().flat_map(point).collect();
+    assert_eq!(points.read_back(gl), expected);
+    points.append(gl, &point(16)).unwrap();
+    let expected: Vec<f32> = [16, 15].into_iter().flat_map(point).collect();
+    assert_eq!(points.read_back(gl), expected);
+    points.draw(gl, None);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_capacity() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    points.reserve(gl, 4).unwrap();
+    assert_eq!(points.buffer.capacity, 12);
+    assert_eq!(points.buffer.len, 0);
+
+    // updates within capacity overwrite in place, keeping allocation
+    let positions: Vec<f32> = (0..9).map(|i| i as f32).collect();
+    points.draw(gl, Some(positions.clone()));
+    assert_eq!(points.buffer.capacity, 12);
+    assert_eq!(points.read_back(gl), positions);
+    points.set_positions(gl, &positions[..3]);
+    assert_eq!(points.buffer.capacity, 12);
+    assert_eq!(points.read_back(gl), positions[..3]);
+
+    // growing past capacity reallocates to fit
+    let positions: Vec<f32> = (0..15).map(|i| i as f32).collect();
+    points.draw(gl, Some(positions.clone()));
+    assert_eq!(points.buffer.capacity, 15);
+    assert_eq!(points.read_back(gl), positions);
+    points.drop(gl);
+
+    // buffers can also be allocated by byte size up front, rounded up to whole floats
+    let mut buffer = Buffer::with_capacity(gl, BufferUsage::Dynamic, 9 * 4 + 2).unwrap();
+    assert_eq!((buffer.capacity, buffer.len), (10, 0));
+    buffer.write(gl, &positions[..9]);
+    assert_eq!(buffer.capacity, 10);
+    assert_eq!(buffer.read_back(gl), positions[..9]);
+    buffer.drop(gl);
+}
+
+#[test]
+fn test_points_outline() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    // single point covering target, with 4px outline
+    points.set_point_size(gl, 16.0, 1.0).unwrap();
+    points
+        .set_outline(gl, Some([1.0, 0.0, 0.0, 1.0]), 4.0, 1.0)
+        .unwrap();
+    assert_eq!(points.outline_width_uniform.data, 4.0);
+    let pixels = common::render_points(gl, &mut points, Some(&[0.0, 0.0, 0.0]));
+    // center in point color, edge in outline color
+    assert_eq!(common::point_pixel(&pixels, 8, 8), [255, 255, 255]);
+    assert_eq!(common::point_pixel(&pixels, 8, 1), [255, 0, 0]);
+
+    // removing outline draws edge in point color
+    points.set_outline(gl, None, 4.0, 1.0).unwrap();
+    assert_eq!(points.outline_width_uniform.data, 0.0);
+    let pixels = common::render_points(gl, &mut points, None);
+    assert_eq!(common::point_pixel(&pixels, 8, 1), [255, 255, 255]);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_softness_dpi() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    // softness uniform is in physical pixels, rescaled when point size sets a new dpi
+    points.set_softness(gl, 2.0, 2.0).unwrap();
+    assert_eq!(points.softness_uniform.data, 4.0);
+    points.set_point_size(gl, 16.0, 1.0).unwrap();
+    assert_eq!(points.softness_uniform.data, 2.0);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_softness() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    points.set_point_size(gl, 16.0, 1.0).unwrap();
+    points.set_softness(gl, 4.0, 1.0).unwrap();
+    let pixels = common::render_points(gl, &mut points, Some(&[0.0, 0.0, 0.0]));
+    // center opaque, pixel within softness of edge partly blended with background
+    assert_eq!(common::point_pixel(&pixels, 8, 8)[0], 255);
+    let edge = common::point_pixel(&pixels, 8, 1)[0];
+    assert!(edge > 0 && edge < 255, "{edge}");
+    assert!(!unsafe { gl.is_enabled(glow::BLEND) });
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_typed() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    points.set_point_size(gl, 16.0, 1.0).unwrap();
+    // center and edge pixels of typed points
+    let draw = |points: &mut Points, typed: &[Point]| {
+        let mut positions = vec![];
+        points.set_typed(gl, typed, 1.0, &mut positions);
+        let pixels = common::render_points(gl, points, Some(&positions));
+        (
+            common::point_pixel(&pixels, 8, 8),
+            common::point_pixel(&pixels, 8, 1),
+        )
+    };
+
+    // per point size and color replace uniform size and default color
+    let small = Point {
+        pos: [0.0, 0.0, 0.0],
+        color: [0.0, 1.0, 0.0, 1.0],
+        size: 4.0,
+    };
+    let (center, edge) = draw(&mut points, &[small]);
+    assert_eq!(center, [0, 255, 0]);
+    assert_eq!(edge, [0, 0, 0]);
+
+    // size 0 uses point size
+    let (center, edge) = draw(&mut points, &[Point::new([0.0, 0.0, 0.0])]);
+    assert_eq!(center, [255, 255, 255]);
+    assert_eq!(edge, [255, 255, 255]);
+
+    // flat data replacing typed points drops their colors and sizes
+    draw(&mut points, &[small]);
+    let pixels = common::render_points(gl, &mut points, Some(&[0.0, 0.0, 0.0]));
+    assert_eq!(common::point_pixel(&pixels, 8, 1), [255, 255, 255]);
+    assert_eq!(points.drawn_sizes(gl, 1, 1.0), [16.0]);
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_selection() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    // half target point, selected points double to cover it with 2px green outline
+    points.set_point_size(gl, 8.0, 1.0).unwrap();
+    points
+        .set_selection_style(gl, [0.0, 1.0, 0.0, 1.0], 2.0, 2.0, 1.0)
+        .unwrap();
+    // center and edge pixels
+    let draw = |points: &mut Points, data: Option<&[f32]>| {
+        let pixels = common::render_points(gl, points, data);
+        (
+            common::point_pixel(&pixels, 8, 8),
+            common::point_pixel(&pixels, 8, 1),
+        )
+    };
+    assert_eq!(
+        draw(&mut points, Some(&[0.0, 0.0, 0.0])),
+        ([255, 255, 255], [0, 0, 0])
+    );
+
+    assert!(points.toggle(0));
+    assert_eq!(draw(&mut points, None), ([255, 255, 255], [0, 255, 0]));
+
+    // deselecting reverts, selection of indices past point count is kept
+    points.select(4);
+    assert!(!points.toggle(0));
+    assert!(points.is_selected(4));
+    assert_eq!(draw(&mut points, None), ([255, 255, 255], [0, 0, 0]));
+    points.clear_selection();
+    assert!(points.selected.is_empty());
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_alpha_sort() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    points.set_point_size(gl, 16.0, 1.0).unwrap();
+    // translucent red near camera stored before translucent blue far from camera
+    points.set_colors(gl, &[1.0, 0.0, 0.0, 0.5, 0.0, 0.0, 1.0, 0.5]);
+    points.set_alpha_sort(gl, true).unwrap();
+    points.set_sort_camera(Vec3::new(0.0, 0.0, 3.0));
+    // far blue drawn first, near red blended over it dominates without hiding it
+    let data = [0.0, 0.0, 0.5, 0.0, 0.0, -0.5];
+    let pixels = common::render_points(gl, &mut points, Some(&data));
+    let [red, _, blue] = common::point_pixel(&pixels, 8, 8);
+    assert!(red > blue && blue > 0, "{red} {blue}");
+
+    // appended positions are read back for sorting, new nearest point drawn last
+    points.append(gl, &[0.0, 0.0, -0.5]).unwrap();
+    points.set_colors(
+        gl,
+        &[1.0, 0.0, 0.0, 0.5, 0.0, 0.0, 1.0, 0.5, 0.0, 0.0, 1.0, 0.5],
+    );
+    points.set_sort_camera(Vec3::new(0.0, 0.0, -3.0));
+    let pixels = common::render_points(gl, &mut points, None);
+    let [red, _, blue] = common::point_pixel(&pixels, 8, 8);
+    assert!(blue > red, "{red} {blue}");
+
+    // blending is only enabled while drawing
+    unsafe { assert!(!gl.is_enabled(glow::BLEND)) };
+    points.set_alpha_sort(gl, false).unwrap();
+    assert!(points.alpha_sort.is_none());
+    points.drop(gl);
+}
+
+#[test]
+fn test_points_depth_prepass() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    points.set_point_size(gl, 16.0, 1.0).unwrap();
+    points.set_softness(gl, 1.0, 1.0).unwrap();
+    // near red drawn before far blue
+    points.set_colors(gl, &[1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+    let data = [0.0, 0.0, -0.5, 0.0, 0.0, 0.5];
+    unsafe {
+        gl.enable(glow::DEPTH_TEST);
+    }
+    let pixels = common::render_points(gl, &mut points, Some(&data));
+    assert_eq!(common::point_pixel(&pixels, 8, 8), [255, 0, 0]);
+
+    // blended points don't write depth, so far point isn't hidden
+    points.depth_prepass = true;
+    let pixels = common::render_points(gl, &mut points, None);
+    assert_eq!(common::point_pixel(&pixels, 8, 8), [0, 0, 255]);
+    unsafe {
+        assert_eq!(gl.get_parameter_i32(glow::DEPTH_WRITEMASK), 1);
+        gl.disable(glow::DEPTH_TEST);
+    }
+    points.drop(gl);
+}
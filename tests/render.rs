@@ -0,0 +1,435 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use glam::Vec3;
+use globe_vis::{
+    geo::DEFAULT_SURFACE_OFFSET,
+    gl_wrap::{ColorFormat, Drop, Framebuffer, UniformFloat},
+    globe::{DirectionalLight, FaceCulling, Globe, GlobeError, GlobeTone, MAX_LIGHTS},
+    markers::Markers,
+    points::Points,
+    vis_gl::MvpMatrices,
+    PassDepth, PolygonOffset,
+};
+use glow::HasContext;
+
+const SIZE: i32 = 64;
+const GRID: i32 = 4;
+// max difference per channel of region average, allows for driver rasterization differences
+const TOLERANCE: f32 = 0.03;
+
+// region averages of default globe at default camera, row major from bottom left
+// globe covers center regions with position gradient colors, corners are background
+const REFERENCE: [[f32; 3]; 16] = [
+    [0.009, 0.009, 0.032],
+    [0.195, 0.109, 0.433],
+    [0.289, 0.109, 0.433],
+    [0.030, 0.009, 0.032],
+    [0.109, 0.195, 0.433],
+    [0.404, 0.404, 0.973],
+    [0.596, 0.404, 0.973],
+    [0.376, 0.195, 0.433],
+    [0.109, 0.289, 0.433],
+    [0.404, 0.596, 0.973],
+    [0.596, 0.596, 0.973],
+    [0.376, 0.289, 0.433],
+    [0.009, 0.030, 0.032],
+    [0.195, 0.376, 0.433],
+    [0.289, 0.376, 0.433],
+    [0.030, 0.030, 0.032],
+];
+
+// draw globe at default camera into offscreen target and get region averages
+fn render_regions(gl: &glow::Context, globe: &Globe) -> Vec<[f32; 3]> {
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let mvp = MvpMatrices::new_default(SIZE as f64, SIZE as f64).unwrap();
+    mvp.apply(gl, &[&globe.program]).unwrap();
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &[&globe.program])
+        .unwrap();
+    globe.apply_uniforms(gl).unwrap();
+    unsafe {
+        gl.enable(glow::DEPTH_TEST);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+    }
+    globe.draw(gl);
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    let pixels = target.read_u8(gl).unwrap();
+    target.drop(gl);
+    common::region_averages(&pixels, SIZE, SIZE, GRID)
+}
+
+#[test]
+fn test_default_globe_render() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    let regions = render_regions(gl, &globe);
+    common::assert_regions_near(&regions, &REFERENCE, TOLERANCE);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_opacity() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    globe.set_opacity(gl, 0.5).unwrap();
+    assert!(globe.is_translucent());
+    let regions = render_regions(gl, &globe);
+    // center blends near and far side over black background, dimmer than opaque
+    // but brighter than near side alone at half alpha
+    for i in [5, 6, 9, 10] {
+        for c in 0..3 {
+            let (value, opaque) = (regions[i][c], REFERENCE[i][c]);
+            assert!(value < opaque - TOLERANCE, "{i} {value} {opaque}");
+            assert!(value > 0.5 * opaque, "{i} {value} {opaque}");
+        }
+    }
+    // blend and depth write state restored
+    unsafe {
+        assert!(!gl.is_enabled(glow::BLEND));
+        assert_eq!(gl.get_parameter_i32(glow::DEPTH_WRITEMASK), 1);
+    }
+
+    globe.set_opacity(gl, 2.0).unwrap();
+    assert!(!globe.is_translucent());
+    let regions = render_regions(gl, &globe);
+    common::assert_regions_near(&regions, &REFERENCE, TOLERANCE);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_depth_prepass() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    globe.set_opacity(gl, 0.5).unwrap();
+    let stacked = render_regions(gl, &globe);
+
+    // only near side blends over black background, far side no longer adds to it
+    globe.set_depth_prepass(true);
+    let regions = render_regions(gl, &globe);
+    for i in [5, 6, 9, 10] {
+        for c in 0..3 {
+            let (value, opaque) = (regions[i][c], REFERENCE[i][c]);
+            assert!(
+                (value - 0.5 * opaque).abs() < TOLERANCE,
+                "{i} {value} {opaque}"
+            );
+            assert!(value < stacked[i][c], "{i} {value} {}", stacked[i][c]);
+        }
+    }
+    unsafe {
+        assert!(!gl.is_enabled(glow::BLEND));
+        assert_eq!(gl.get_parameter_i32(glow::DEPTH_WRITEMASK), 1);
+        assert_eq!(gl.get_parameter_i32(glow::DEPTH_FUNC) as u32, glow::LESS);
+    }
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_edge_smoothing() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    globe.set_edge_smoothing(gl, true).unwrap();
+    assert!(globe.has_edge_smoothing() && !globe.is_translucent());
+    let regions = render_regions(gl, &globe);
+    // interior faces camera and is unchanged
+    for i in [5, 6, 9, 10] {
+        for c in 0..3 {
+            assert!((regions[i][c] - REFERENCE[i][c]).abs() < TOLERANCE);
+        }
+    }
+    // rim pixels fade toward background
+    let rim = |regions: &[[f32; 3]]| -> f32 {
+        [1, 2, 4, 7, 8, 11, 13, 14]
+            .iter()
+            .map(|&i| regions[i].iter().sum::<f32>())
+            .sum()
+    };
+    let smoothed = rim(&regions);
+    globe.set_edge_smoothing(gl, false).unwrap();
+    let aliased = rim(&render_regions(gl, &globe));
+    assert!(smoothed < aliased, "{smoothed} {aliased}");
+    unsafe {
+        assert!(!gl.is_enabled(glow::BLEND));
+    }
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_transparent_background() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let mvp = MvpMatrices::new_default(SIZE as f64, SIZE as f64).unwrap();
+    mvp.apply(gl, &[&globe.program]).unwrap();
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &[&globe.program])
+        .unwrap();
+    globe.apply_uniforms(gl).unwrap();
+    let center_corner_alpha = |globe: &Globe| {
+        unsafe {
+            gl.enable(glow::DEPTH_TEST);
+            gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+        globe.draw(gl);
+        let pixels = target.read_u8(gl).unwrap();
+        let alpha = |x: i32, y: i32| pixels[((y * SIZE + x) * 4 + 3) as usize];
+        (alpha(SIZE / 2, SIZE / 2), alpha(0, 0))
+    };
+    // opaque globe covers page, background around it stays clear
+    assert_eq!(center_corner_alpha(&globe), (255, 0));
+    // translucent faces accumulate coverage, far then near side at half alpha
+    globe.set_opacity(gl, 0.5).unwrap();
+    let (center, corner) = center_corner_alpha(&globe);
+    assert!((center as i32 - 191).abs() <= 2, "{center}");
+    assert_eq!(corner, 0);
+    target.drop(gl);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_polygon_offset_overlay() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    // coplanar overlay, same mesh tinted red
+    let mut overlay = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    let target = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let mvp = MvpMatrices::new_default(SIZE as f64, SIZE as f64).unwrap();
+    let programs = [&globe.program, &overlay.program];
+    mvp.apply(gl, &programs).unwrap();
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &programs)
+        .unwrap();
+    globe.apply_uniforms(gl).unwrap();
+    overlay.apply_uniforms(gl).unwrap();
+    overlay.set_color(gl, [1.0, 0.0, 0.0]).unwrap();
+    let center_green = |globe: &Globe, overlay: &Globe, depth: PassDepth| {
+        unsafe {
+            gl.enable(glow::DEPTH_TEST);
+            gl.clear_color(0.0, 0.0, 0.0, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+        globe.draw(gl);
+        depth.apply(gl);
+        overlay.draw(gl);
+        PassDepth::reset(gl);
+        assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+        let pixels = target.read_u8(gl).unwrap();
+        pixels[((SIZE / 2 * SIZE + SIZE / 2) * 4 + 1) as usize]
+    };
+    // overlay at equal depth fails default less test, globe shows
+    assert!(center_green(&globe, &overlay, PassDepth::default()) > 0);
+    // overlay pass pulled forward draws over globe
+    assert_eq!(center_green(&globe, &overlay, PassDepth::overlay()), 0);
+    // globe pushed back lets overlay draw with default depth
+    globe.set_polygon_offset(Some(PolygonOffset::AWAY));
+    assert_eq!(center_green(&globe, &overlay, PassDepth::default()), 0);
+    unsafe {
+        assert!(!gl.is_enabled(glow::POLYGON_OFFSET_FILL));
+        assert_eq!(gl.get_parameter_i32(glow::DEPTH_FUNC), glow::LESS as i32);
+    }
+    target.drop(gl);
+    overlay.drop(gl);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_lights() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    globe.set_ambient(gl, Vec3::ZERO).unwrap();
+    let center = |regions: &[[f32; 3]]| [5, 6, 9, 10].map(|i| regions[i].iter().sum::<f32>());
+    // unlit without ambient
+    assert!(center(&render_regions(gl, &globe))
+        .iter()
+        .all(|v| *v < 0.05));
+
+    // light from behind globe leaves near side dark
+    let light = |direction: Vec3, intensity: f32| DirectionalLight {
+        direction,
+        color: Vec3::ONE,
+        intensity,
+    };
+    globe.add_light(gl, light(Vec3::NEG_Z, 1.0)).unwrap();
+    assert!(center(&render_regions(gl, &globe))
+        .iter()
+        .all(|v| *v < 0.05));
+    // lights from camera side accumulate to full brightness
+    for _ in 0..2 {
+        globe.add_light(gl, light(Vec3::Z, 0.5)).unwrap();
+    }
+    let regions = render_regions(gl, &globe);
+    for i in [5, 6, 9, 10] {
+        let lit: f32 = regions[i].iter().sum();
+        let reference: f32 = REFERENCE[i].iter().sum();
+        assert!(lit > 0.8 * reference, "{i} {lit} {reference}");
+    }
+    globe.add_light(gl, light(Vec3::X, 1.0)).unwrap();
+    assert_eq!(globe.lighting.lights.len(), MAX_LIGHTS);
+    assert!(matches!(
+        globe.add_light(gl, light(Vec3::Y, 1.0)),
+        Err(GlobeError::MaxLights)
+    ));
+
+    globe.clear_lights(gl).unwrap();
+    assert!(center(&render_regions(gl, &globe))
+        .iter()
+        .all(|v| *v < 0.05));
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_tone() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    let white = globe.colors_from_lat_lon(|_, _| [1.0, 1.0, 1.0, 1.0]);
+    globe.set_vertex_colors(gl, &white).unwrap();
+    globe.set_color(gl, [1.0, 0.0, 0.0]).unwrap();
+    let blue = |regions: &[[f32; 3]], i: &[usize]| i.iter().map(|i| regions[*i][2]).sum::<f32>();
+
+    // solid globe has no second color
+    let regions = render_regions(gl, &globe);
+    assert!(blue(&regions, &[5, 6, 9, 10, 13, 14]) < 0.02);
+
+    // caps above 40 degrees only reach top center regions
+    let tone = GlobeTone::Polar {
+        lat: 40.0,
+        color: [0.0, 0.0, 1.0],
+    };
+    globe.set_tone(gl, tone).unwrap();
+    let regions = render_regions(gl, &globe);
+    assert!(blue(&regions, &[5, 6, 9, 10]) < 0.02);
+    assert!(blue(&regions, &[13, 14]) > 0.05);
+    assert!(regions[5][0] > 0.2);
+
+    // back to solid
+    globe.set_tone(gl, GlobeTone::Solid).unwrap();
+    let regions = render_regions(gl, &globe);
+    assert!(blue(&regions, &[13, 14]) < 0.02);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_flat_globe_render() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    globe.set_flat(gl, true);
+    UniformFloat::new("flatMap", 1.0)
+        .apply(gl, &[&globe.program])
+        .unwrap();
+    let regions = render_regions(gl, &globe);
+    // map is taller and wider than view at default distance, every region is covered
+    for region in &regions {
+        assert!(region.iter().sum::<f32>() > 0.3, "{region:?}");
+    }
+    // position gradient increases east in red and north in green
+    assert!(regions[4][0] < regions[7][0] - 0.2);
+    assert!(regions[1][1] < regions[13][1] - 0.2);
+
+    // switching back restores globe mesh
+    UniformFloat::new("flatMap", 0.0)
+        .apply(gl, &[&globe.program])
+        .unwrap();
+    globe.set_flat(gl, false);
+    let regions = render_regions(gl, &globe);
+    common::assert_regions_near(&regions, &REFERENCE, TOLERANCE);
+    globe.drop(gl);
+}
+
+#[test]
+fn test_globe_face_culling() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let mut globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+
+    // outward winding, culling back faces matches default render
+    globe.set_face_culling(FaceCulling::Back);
+    let regions = render_regions(gl, &globe);
+    common::assert_regions_near(&regions, &REFERENCE, TOLERANCE);
+
+    // culling front faces shows inside of far side, darker blue at center from -z colors
+    globe.set_face_culling(FaceCulling::Front);
+    let regions = render_regions(gl, &globe);
+    assert!(regions[5][2] < REFERENCE[5][2] - 0.2);
+    globe.drop(gl);
+}
+
+// draw point over globe at center of view, on surface where it lands on a globe vertex
+// at equal depth, and get center pixel rgb
+fn center_pixel(
+    gl: &glow::Context,
+    globe: &Globe,
+    points: &mut Points,
+    framebuffer: &Framebuffer,
+) -> [u8; 3] {
+    unsafe {
+        gl.enable(glow::DEPTH_TEST);
+        gl.enable(glow::PROGRAM_POINT_SIZE);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+    }
+    globe.draw(gl);
+    points.draw_slice(gl, Some(&[0.0, 0.0, 1.0]));
+    assert_eq!(unsafe { gl.get_error() }, glow::NO_ERROR);
+    let pixels = framebuffer.read_u8(gl).unwrap();
+    let i = ((SIZE / 2 * SIZE + SIZE / 2) * 4) as usize;
+    [pixels[i], pixels[i + 1], pixels[i + 2]]
+}
+
+#[test]
+fn test_surface_offset_overlay() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let globe = Globe::new(gl, common::SHADER_VERSION).unwrap();
+    let mut points = Points::new(gl, common::SHADER_VERSION).unwrap();
+    let mut markers = Markers::new(gl, common::SHADER_VERSION).unwrap();
+    let framebuffer = Framebuffer::new(gl, SIZE, SIZE, ColorFormat::Rgba8).unwrap();
+    let mvp = MvpMatrices::new_default(SIZE as f64, SIZE as f64).unwrap();
+    let programs = [&globe.program, &points.program, &markers.program];
+    mvp.apply(gl, &programs).unwrap();
+    UniformFloat::new("gamma", 1.0)
+        .apply(gl, &programs)
+        .unwrap();
+    globe.apply_uniforms(gl).unwrap();
+    points.apply_uniforms(gl).unwrap();
+    points.set_colors(gl, &[1.0, 0.0, 0.0, 1.0]);
+
+    // no offset by default, surface offset draws point over globe
+    assert_eq!(points.surface_offset.data, 0.0);
+    points
+        .set_surface_offset(gl, DEFAULT_SURFACE_OFFSET)
+        .unwrap();
+    assert_eq!(
+        center_pixel(gl, &globe, &mut points, &framebuffer),
+        [255, 0, 0]
+    );
+    // offset is radial, pushing into globe hides point
+    points.set_surface_offset(gl, -0.05).unwrap();
+    assert_ne!(
+        center_pixel(gl, &globe, &mut points, &framebuffer),
+        [255, 0, 0]
+    );
+
+    // markers don't move positions by default either
+    assert_eq!(markers.surface_offset.data, 0.0);
+    markers
+        .set_surface_offset(gl, DEFAULT_SURFACE_OFFSET)
+        .unwrap();
+    assert_eq!(markers.surface_offset.data, DEFAULT_SURFACE_OFFSET);
+    markers.drop(gl);
+    framebuffer.drop(gl);
+    points.drop(gl);
+    globe.drop(gl);
+}
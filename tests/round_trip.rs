@@ -0,0 +1,112 @@
+use glam::{Quat, Vec3};
+use globe_vis::{
+    camera::{axial_tilt, orientation_for_center},
+    geo::lat_lon_to_xyz,
+    mouse::{MAX_DISTANCE, MIN_DISTANCE},
+    pick::{is_occluded, ndc_to_ray, project, ray_sphere_intersect, screen_to_ndc},
+    vis_gl::MvpMatrices,
+};
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 600.0;
+
+// world space camera orientations covering rotation about each axis
+fn orientations() -> Vec<Quat> {
+    let mut orientations = vec![Quat::IDENTITY];
+    for angle in [0.3_f32, 1.2, 2.5, -2.0] {
+        orientations.push(Quat::from_rotation_x(angle));
+        orientations.push(Quat::from_rotation_y(angle));
+        orientations.push(Quat::from_rotation_z(angle));
+        orientations.push(Quat::from_rotation_x(angle) * Quat::from_rotation_y(-angle * 0.7));
+    }
+    orientations
+}
+
+fn distances() -> Vec<f32> {
+    vec![MIN_DISTANCE, 1.5, 2.0, 4.0, MAX_DISTANCE]
+}
+
+// grid of lat / lon surface points
+fn surface_points() -> Vec<Vec3> {
+    let mut points = vec![];
+    for lat in (-80..=80).step_by(20) {
+        for lon in (-180..180).step_by(30) {
+            points.push(Vec3::from(lat_lon_to_xyz(lat as f32, lon as f32, 1.0)));
+        }
+    }
+    points
+}
+
+// project visible surface points to screen and cast back onto globe,
+// checking every visible point for each camera orientation / zoom
+fn check_round_trip(tilt: Quat) {
+    let mut checked = 0;
+    for orientation in orientations() {
+        for distance in distances() {
+            let mut mvp = MvpMatrices::new_default(WIDTH, HEIGHT).unwrap();
+            mvp.set_tilt(tilt);
+            mvp.set_orientation(orientation);
+            mvp.set_distance(distance);
+            let model_view = mvp.view.data * mvp.model.data;
+            let full = mvp.proj.data * model_view;
+            let eye = model_view.inverse().transform_point3(Vec3::ZERO);
+
+            for world in surface_points() {
+                if is_occluded(eye, world, 1.0) {
+                    continue;
+                }
+                let Some((x, y)) = project(full, world, WIDTH, HEIGHT) else {
+                    continue;
+                };
+                let (nx, ny) = screen_to_ndc(x as f64, y as f64, WIDTH, HEIGHT);
+                // skip points projected off screen
+                if nx.abs() > 1.0 || ny.abs() > 1.0 {
+                    continue;
+                }
+                // grazing hits near silhouette amplify error, compare only well facing points
+                if world.dot((eye - world).normalize()) < 0.2 {
+                    continue;
+                }
+                let (origin, dir) = ndc_to_ray(full, nx, ny);
+                let hit = ray_sphere_intersect(origin, dir, 1.0).unwrap();
+                let error = hit.distance(world);
+                assert!(
+                    error < 1e-3,
+                    "round trip error {} for {:?} at {:?} distance {}",
+                    error,
+                    world,
+                    orientation,
+                    distance
+                );
+                checked += 1;
+            }
+        }
+    }
+    assert!(checked > 1000);
+}
+
+#[test]
+fn test_project_unproject_round_trip() {
+    check_round_trip(Quat::IDENTITY);
+}
+
+#[test]
+fn test_project_unproject_round_trip_tilted() {
+    check_round_trip(axial_tilt(23.4));
+}
+
+#[test]
+fn test_centered_point_projects_to_screen_center() {
+    for distance in distances() {
+        for (lat, lon) in [(0.0, 0.0), (45.0, 120.0), (-60.0, -30.0)] {
+            let mut mvp = MvpMatrices::new_default(WIDTH, HEIGHT).unwrap();
+            mvp.set_orientation(orientation_for_center(lat, lon));
+            mvp.set_distance(distance);
+            let full = mvp.proj.data * mvp.view.data * mvp.model.data;
+            let world = Vec3::from(lat_lon_to_xyz(lat, lon, 1.0));
+            let (x, y) = project(full, world, WIDTH, HEIGHT).unwrap();
+            assert!((x as f64 - WIDTH * 0.5).abs() < 1e-2);
+            assert!((y as f64 - HEIGHT * 0.5).abs() < 1e-2);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use globe_vis::{
+    gl_wrap::{Drop, Program, ProgramError, Shader, ShaderError, UniformFloat, UniformMatrix},
+    points::Points,
+};
+
+#[test]
+fn test_uniform_location_error_names_uniform() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let points = Points::new(gl, common::SHADER_VERSION).unwrap();
+
+    let found = UniformMatrix::new("projMatrix", glam::Mat4::IDENTITY);
+    assert!(found.apply(gl, &[&points.program]).is_ok());
+
+    let missing = UniformFloat::new("notDeclared", 1.0);
+    let err = missing.apply(gl, &[&points.program]).unwrap_err();
+    assert!(err.to_string().contains("notDeclared"));
+    points.drop(gl);
+}
+
+#[test]
+fn test_shader_info_log() {
+    let headless = headless_or_skip!();
+    let gl = &headless.gl;
+    let vertex = "void main() { gl_Position = vec4(0.0); }";
+    let fragment = "precision mediump float; out vec4 c; void main() { c = vec4(1.0); }";
+
+    // logs readable after successful compile and link, drivers may leave them empty
+    let program = Program::new_from_sources(gl, common::SHADER_VERSION, vertex, fragment).unwrap();
+    assert!(!program.info_log(gl).to_lowercase().contains("error"));
+    program.drop(gl);
+
+    // error path still carries compile log
+    let broken = "void main() { undeclared = 1.0; }";
+    let err = Shader::new(gl, common::SHADER_VERSION, broken, glow::FRAGMENT_SHADER)
+        .err()
+        .unwrap();
+    assert!(matches!(&err, ShaderError::Compilation(log) if !log.is_empty()));
+    let err = Program::new_from_sources(gl, common::SHADER_VERSION, vertex, broken)
+        .err()
+        .unwrap();
+    assert!(matches!(err, ProgramError::Shader(_)));
+}